@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use turbo_commerce::customer::Customer;
 use turbo_commerce::ids::UserId;
 
 /// User role for authorization.
@@ -165,6 +166,29 @@ impl User {
             User::Anonymous { .. } => false,
         }
     }
+
+    /// Create a commerce customer for an authenticated user.
+    ///
+    /// The display name is split at the first space into first and last name.
+    pub fn to_customer(&self) -> Option<Customer> {
+        match self {
+            User::Authenticated {
+                id, email, name, ..
+            } => {
+                let mut customer = Customer::for_user(id.clone(), email.clone());
+                if let Some(name) = name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+                    let (first, last) = match name.split_once(' ') {
+                        Some((first, last)) => (first, Some(last.trim().to_string())),
+                        None => (name, None),
+                    };
+                    customer.first_name = Some(first.to_string());
+                    customer.last_name = last;
+                }
+                Some(customer)
+            }
+            User::Anonymous { .. } => None,
+        }
+    }
 }
 
 impl Default for User {
@@ -366,4 +390,19 @@ mod tests {
         assert!(admin.has_permission(Role::Staff));
         assert!(!admin.has_permission(Role::SuperAdmin));
     }
+
+    #[test]
+    fn test_to_customer() {
+        let user = User::authenticated(
+            UserId::new("user_123"),
+            "ada@example.com",
+            Some("Ada King Lovelace".to_string()),
+            vec![Role::Customer],
+        );
+        let customer = user.to_customer().unwrap();
+        assert_eq!(customer.user_id, Some(UserId::new("user_123")));
+        assert_eq!(customer.first_name.as_deref(), Some("Ada"));
+        assert_eq!(customer.last_name.as_deref(), Some("King Lovelace"));
+        assert!(User::anonymous("s").to_customer().is_none());
+    }
 }
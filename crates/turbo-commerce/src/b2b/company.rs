@@ -0,0 +1,274 @@
+//! Company accounts and their buyers.
+
+use crate::checkout::Address;
+use crate::error::CommerceError;
+use crate::ids::{CompanyId, UserId};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// When an invoiced order must be paid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum PaymentTerms {
+    /// Paid at checkout.
+    #[default]
+    Prepaid,
+    /// Due on receipt of the invoice.
+    DueOnReceipt,
+    /// Due a number of days after the order (e.g., Net 30).
+    Net(u32),
+}
+
+impl PaymentTerms {
+    /// The kind of terms, without the day count (see `Display` for that).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentTerms::Prepaid => "prepaid",
+            PaymentTerms::DueOnReceipt => "due_on_receipt",
+            PaymentTerms::Net(_) => "net",
+        }
+    }
+
+    /// Check if the order is invoiced rather than paid up front.
+    pub fn is_deferred(&self) -> bool {
+        !matches!(self, PaymentTerms::Prepaid)
+    }
+
+    /// Unix timestamp payment is due for an order placed at `ordered_at`.
+    pub fn due_at(&self, ordered_at: i64) -> i64 {
+        match self {
+            PaymentTerms::Prepaid | PaymentTerms::DueOnReceipt => ordered_at,
+            PaymentTerms::Net(days) => ordered_at + i64::from(*days) * 86400,
+        }
+    }
+}
+
+impl fmt::Display for PaymentTerms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentTerms::Net(days) => write!(f, "net_{}", days),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+/// What a buyer may do for their company.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum BuyerRole {
+    /// Manages the account and approves quotes.
+    Admin,
+    /// Requests quotes and places orders.
+    #[default]
+    Purchaser,
+    /// Read-only access.
+    Viewer,
+}
+
+impl BuyerRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BuyerRole::Admin => "admin",
+            BuyerRole::Purchaser => "purchaser",
+            BuyerRole::Viewer => "viewer",
+        }
+    }
+
+    /// Check if the role can request quotes and place orders.
+    pub fn can_purchase(&self) -> bool {
+        matches!(self, BuyerRole::Admin | BuyerRole::Purchaser)
+    }
+}
+
+/// A user buying on behalf of a company.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Buyer {
+    /// The buyer's user account.
+    pub user_id: UserId,
+    /// Contact email.
+    pub email: String,
+    /// Role within the company.
+    pub role: BuyerRole,
+    /// Largest order the buyer may place (None for unlimited).
+    pub spending_limit: Option<Money>,
+}
+
+impl Buyer {
+    /// Create a purchaser.
+    pub fn new(user_id: UserId, email: impl Into<String>) -> Self {
+        Self {
+            user_id,
+            email: email.into(),
+            role: BuyerRole::Purchaser,
+            spending_limit: None,
+        }
+    }
+
+    /// Set the role.
+    pub fn with_role(mut self, role: BuyerRole) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// Set a per-order spending limit.
+    pub fn with_spending_limit(mut self, limit: Money) -> Self {
+        self.spending_limit = Some(limit);
+        self
+    }
+
+    /// Check if the buyer may place an order of `amount`.
+    ///
+    /// An amount in a different currency from the limit is refused.
+    pub fn can_spend(&self, amount: &Money) -> bool {
+        if !self.role.can_purchase() {
+            return false;
+        }
+        match self.spending_limit {
+            Some(limit) => {
+                limit.currency == amount.currency && amount.amount_cents <= limit.amount_cents
+            }
+            None => true,
+        }
+    }
+}
+
+/// A business customer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Company {
+    /// Unique company identifier.
+    pub id: CompanyId,
+    /// Legal or trading name.
+    pub name: String,
+    /// Tax/VAT registration number.
+    pub tax_id: Option<String>,
+    /// People who buy for the company.
+    pub buyers: Vec<Buyer>,
+    /// Default payment terms for orders.
+    pub payment_terms: PaymentTerms,
+    /// Whether orders must carry a purchase order number.
+    pub requires_po_number: bool,
+    /// Billing address for invoices.
+    pub billing_address: Option<Address>,
+    /// Ship-to locations.
+    pub shipping_addresses: Vec<Address>,
+    /// Unix timestamp of creation.
+    pub created_at: i64,
+    /// Unix timestamp of last update.
+    pub updated_at: i64,
+}
+
+impl Company {
+    /// Create a prepaid company account.
+    pub fn new(name: impl Into<String>) -> Self {
+        let now = current_timestamp();
+        Self {
+            id: CompanyId::generate(),
+            name: name.into(),
+            tax_id: None,
+            buyers: Vec::new(),
+            payment_terms: PaymentTerms::Prepaid,
+            requires_po_number: false,
+            billing_address: None,
+            shipping_addresses: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Set the tax registration number.
+    pub fn with_tax_id(mut self, tax_id: impl Into<String>) -> Self {
+        self.tax_id = Some(tax_id.into());
+        self
+    }
+
+    /// Set payment terms. Invoiced terms require a PO number on orders.
+    pub fn with_payment_terms(mut self, terms: PaymentTerms) -> Self {
+        self.payment_terms = terms;
+        self.requires_po_number = terms.is_deferred();
+        self
+    }
+
+    /// Set the invoice address.
+    pub fn with_billing_address(mut self, address: Address) -> Self {
+        self.billing_address = Some(address);
+        self
+    }
+
+    /// Add a buyer. Fails if the user already belongs to the company.
+    pub fn add_buyer(&mut self, buyer: Buyer) -> Result<(), CommerceError> {
+        if self.buyer(&buyer.user_id).is_some() {
+            return Err(CommerceError::ValidationError(format!(
+                "user {} is already a buyer for {}",
+                buyer.user_id, self.name
+            )));
+        }
+        self.buyers.push(buyer);
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    /// Remove a buyer.
+    pub fn remove_buyer(&mut self, user_id: &UserId) -> bool {
+        let len = self.buyers.len();
+        self.buyers.retain(|b| &b.user_id != user_id);
+        if self.buyers.len() != len {
+            self.updated_at = current_timestamp();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Find a buyer by user.
+    pub fn buyer(&self, user_id: &UserId) -> Option<&Buyer> {
+        self.buyers.iter().find(|b| &b.user_id == user_id)
+    }
+
+    /// Check if a user can approve quotes for the company.
+    pub fn can_approve(&self, user_id: &UserId) -> bool {
+        self.buyer(user_id)
+            .is_some_and(|b| b.role == BuyerRole::Admin)
+    }
+}
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    #[test]
+    fn test_buyers_and_terms() {
+        let mut company = Company::new("Acme").with_payment_terms(PaymentTerms::Net(30));
+        assert!(company.requires_po_number);
+        assert_eq!(company.payment_terms.as_str(), "net");
+        assert_eq!(company.payment_terms.to_string(), "net_30");
+        assert_eq!(PaymentTerms::DueOnReceipt.to_string(), "due_on_receipt");
+        assert_eq!(company.payment_terms.due_at(0), 30 * 86400);
+
+        let alice = UserId::new("alice");
+        company
+            .add_buyer(
+                Buyer::new(alice.clone(), "alice@acme.test")
+                    .with_spending_limit(Money::new(100_000, Currency::USD)),
+            )
+            .unwrap();
+        assert!(company
+            .add_buyer(Buyer::new(alice.clone(), "alice@acme.test"))
+            .is_err());
+
+        let buyer = company.buyer(&alice).unwrap();
+        assert!(buyer.can_spend(&Money::new(100_000, Currency::USD)));
+        assert!(!buyer.can_spend(&Money::new(100_001, Currency::USD)));
+        assert!(!buyer.can_spend(&Money::new(100, Currency::EUR)));
+        assert!(!company.can_approve(&alice));
+        assert!(company.remove_buyer(&alice));
+    }
+}
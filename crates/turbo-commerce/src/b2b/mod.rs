@@ -0,0 +1,10 @@
+//! B2B module.
+//!
+//! Contains company accounts with their buyers and payment terms, and
+//! negotiated quotes that convert to purchase orders.
+
+mod company;
+mod quote;
+
+pub use company::{Buyer, BuyerRole, Company, PaymentTerms};
+pub use quote::{Quote, QuoteLine, QuoteStatus};
@@ -0,0 +1,687 @@
+//! Negotiated quotes and purchase orders.
+//!
+//! A [`Quote`] is drafted from a buyer's cart, prices are negotiated per
+//! line, the merchant approves it, and a company admin accepts it. An
+//! accepted quote converts to an [`Order`] carrying the buyer's purchase
+//! order (PO) number and the company's payment terms, as long as it is
+//! still within its validity window.
+
+use crate::b2b::Company;
+use crate::cart::{Cart, LineItemProperty};
+use crate::checkout::{
+    Address, FinancialStatus, FulfillmentStatus, Order, OrderLineItem, OrderStatus,
+    ShippingSelection,
+};
+use crate::error::CommerceError;
+use crate::ids::{
+    CompanyId, LineItemId, OrderId, OrderLineItemId, ProductId, QuoteId, UserId, VariantId,
+};
+use crate::money::{Currency, Money};
+use crate::tax::{TaxCalculator, TaxClass, TaxLine, TaxRequest};
+use serde::{Deserialize, Serialize};
+
+/// Quote status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum QuoteStatus {
+    /// Being put together.
+    #[default]
+    Draft,
+    /// Sent to the merchant for pricing.
+    Submitted,
+    /// Merchant approved the prices; awaiting the buyer.
+    Approved,
+    /// Company accepted the quote.
+    Accepted,
+    /// Merchant or company declined.
+    Rejected,
+    /// Validity window passed before conversion.
+    Expired,
+    /// Turned into an order.
+    Converted,
+}
+
+impl QuoteStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuoteStatus::Draft => "draft",
+            QuoteStatus::Submitted => "submitted",
+            QuoteStatus::Approved => "approved",
+            QuoteStatus::Accepted => "accepted",
+            QuoteStatus::Rejected => "rejected",
+            QuoteStatus::Expired => "expired",
+            QuoteStatus::Converted => "converted",
+        }
+    }
+
+    /// Check if the quote is finished.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            QuoteStatus::Rejected | QuoteStatus::Expired | QuoteStatus::Converted
+        )
+    }
+
+    /// Check if line prices can still change.
+    pub fn is_negotiable(&self) -> bool {
+        matches!(self, QuoteStatus::Draft | QuoteStatus::Submitted)
+    }
+}
+
+/// A quoted line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuoteLine {
+    /// Line identifier (the cart line it came from).
+    pub id: LineItemId,
+    /// Variant quoted.
+    pub variant_id: VariantId,
+    /// Product ID.
+    pub product_id: ProductId,
+    /// Product name.
+    pub product_name: String,
+    /// Variant name.
+    pub variant_name: Option<String>,
+    /// Variant SKU.
+    #[serde(default)]
+    pub sku: Option<String>,
+    /// Quantity quoted.
+    pub quantity: i64,
+    /// Catalog unit price.
+    pub list_price: Money,
+    /// Negotiated unit price.
+    pub quoted_price: Money,
+    /// Custom properties.
+    pub properties: Vec<LineItemProperty>,
+}
+
+impl QuoteLine {
+    /// Quoted price times quantity.
+    pub fn total(&self) -> Result<Money, CommerceError> {
+        self.quoted_price
+            .try_multiply(self.quantity)
+            .ok_or(CommerceError::Overflow)
+    }
+
+    /// List price times quantity.
+    pub fn list_total(&self) -> Result<Money, CommerceError> {
+        self.list_price
+            .try_multiply(self.quantity)
+            .ok_or(CommerceError::Overflow)
+    }
+}
+
+/// A quote for a company.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Quote {
+    /// Unique quote identifier.
+    pub id: QuoteId,
+    /// Company being quoted.
+    pub company_id: CompanyId,
+    /// Buyer who requested it.
+    pub requested_by: UserId,
+    /// Current status.
+    pub status: QuoteStatus,
+    /// Quoted lines.
+    pub lines: Vec<QuoteLine>,
+    /// Quote currency.
+    pub currency: Currency,
+    /// Buyer's note to the merchant.
+    pub note: Option<String>,
+    /// Why it was rejected.
+    pub rejection_reason: Option<String>,
+    /// Company admin who accepted it.
+    pub accepted_by: Option<UserId>,
+    /// Unix timestamp until which the quoted prices hold.
+    pub valid_until: i64,
+    /// Order created from the quote.
+    pub order_id: Option<OrderId>,
+    /// Unix timestamp of creation.
+    pub created_at: i64,
+    /// Unix timestamp of last update.
+    pub updated_at: i64,
+}
+
+impl Quote {
+    /// Draft a quote from a buyer's cart, valid for `valid_for_secs`.
+    pub fn from_cart(
+        cart: &Cart,
+        company: &Company,
+        buyer: &UserId,
+        valid_for_secs: i64,
+    ) -> Result<Self, CommerceError> {
+        if !company.buyer(buyer).is_some_and(|b| b.role.can_purchase()) {
+            return Err(CommerceError::ValidationError(format!(
+                "user {} can't request quotes for {}",
+                buyer, company.name
+            )));
+        }
+        if cart.is_empty() {
+            return Err(CommerceError::ValidationError("cart is empty".to_string()));
+        }
+        let lines = cart
+            .items
+            .iter()
+            .map(|item| QuoteLine {
+                id: item.id.clone(),
+                variant_id: item.variant_id.clone(),
+                product_id: item.product_id.clone(),
+                product_name: item.product_name.clone(),
+                variant_name: item.variant_name.clone(),
+                sku: item.sku.clone(),
+                quantity: item.quantity,
+                list_price: item.unit_price,
+                quoted_price: item.unit_price,
+                properties: item.properties.clone(),
+            })
+            .collect();
+
+        let now = current_timestamp();
+        Ok(Self {
+            id: QuoteId::generate(),
+            company_id: company.id.clone(),
+            requested_by: buyer.clone(),
+            status: QuoteStatus::Draft,
+            lines,
+            currency: cart.currency,
+            note: cart.note.clone(),
+            rejection_reason: None,
+            accepted_by: None,
+            valid_until: now + valid_for_secs,
+            order_id: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Negotiate a line's unit price.
+    pub fn set_line_price(
+        &mut self,
+        line_id: &LineItemId,
+        unit_price: Money,
+    ) -> Result<(), CommerceError> {
+        self.check_negotiable()?;
+        if unit_price.currency != self.currency {
+            return Err(CommerceError::CurrencyMismatch {
+                expected: self.currency.code().to_string(),
+                got: unit_price.currency.code().to_string(),
+            });
+        }
+        if unit_price.is_negative() {
+            return Err(CommerceError::ValidationError(
+                "unit price cannot be negative".to_string(),
+            ));
+        }
+        self.line_mut(line_id)?.quoted_price = unit_price;
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    /// Change a line's quantity.
+    pub fn set_line_quantity(
+        &mut self,
+        line_id: &LineItemId,
+        quantity: i64,
+    ) -> Result<(), CommerceError> {
+        self.check_negotiable()?;
+        if quantity <= 0 {
+            return Err(CommerceError::InvalidQuantity(quantity));
+        }
+        self.line_mut(line_id)?.quantity = quantity;
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    /// Move the validity window.
+    pub fn extend_validity(&mut self, valid_until: i64) -> Result<(), CommerceError> {
+        if self.status.is_terminal() {
+            return Err(self.invalid_transition(self.status));
+        }
+        self.valid_until = valid_until;
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    /// Quoted total.
+    pub fn total(&self) -> Result<Money, CommerceError> {
+        self.sum(QuoteLine::total)
+    }
+
+    /// Total at list prices.
+    pub fn list_total(&self) -> Result<Money, CommerceError> {
+        self.sum(QuoteLine::list_total)
+    }
+
+    /// Negotiated savings against list prices.
+    pub fn savings(&self) -> Result<Money, CommerceError> {
+        self.list_total()?
+            .try_subtract(&self.total()?)
+            .ok_or(CommerceError::Overflow)
+    }
+
+    /// Check if the quoted prices have lapsed.
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        self.status == QuoteStatus::Expired
+            || (!self.status.is_terminal() && now > self.valid_until)
+    }
+
+    /// Mark the quote expired if its window has passed. Returns true if it expired.
+    pub fn expire_if_due(&mut self, now: i64) -> bool {
+        if self.status.is_terminal() || now <= self.valid_until {
+            return false;
+        }
+        self.status = QuoteStatus::Expired;
+        self.updated_at = now;
+        true
+    }
+
+    /// Send the quote to the merchant.
+    pub fn submit(&mut self) -> Result<(), CommerceError> {
+        self.transition(&[QuoteStatus::Draft], QuoteStatus::Submitted)
+    }
+
+    /// Merchant approves the quoted prices.
+    pub fn approve(&mut self) -> Result<(), CommerceError> {
+        self.transition(&[QuoteStatus::Submitted], QuoteStatus::Approved)
+    }
+
+    /// Company admin accepts an approved quote.
+    pub fn accept(&mut self, company: &Company, user_id: &UserId) -> Result<(), CommerceError> {
+        self.check_company(company)?;
+        if !company.can_approve(user_id) {
+            return Err(CommerceError::ValidationError(format!(
+                "user {} can't accept quotes for {}",
+                user_id, company.name
+            )));
+        }
+        self.check_not_expired()?;
+        self.transition(&[QuoteStatus::Approved], QuoteStatus::Accepted)?;
+        self.accepted_by = Some(user_id.clone());
+        Ok(())
+    }
+
+    /// Decline the quote (either side).
+    pub fn reject(&mut self, reason: impl Into<String>) -> Result<(), CommerceError> {
+        self.transition(
+            &[
+                QuoteStatus::Draft,
+                QuoteStatus::Submitted,
+                QuoteStatus::Approved,
+            ],
+            QuoteStatus::Rejected,
+        )?;
+        self.rejection_reason = Some(reason.into());
+        Ok(())
+    }
+
+    /// Convert an accepted quote into an order billed on the company's terms.
+    ///
+    /// The lines are taxed by `taxes` for the shipping address. The PO
+    /// number and payment terms are recorded in the order's metadata
+    /// (`po_number`, `payment_terms`, `payment_due_at`, `quote_id`). Orders
+    /// on deferred terms start unpaid and confirmed; prepaid orders wait for
+    /// payment as usual.
+    pub fn to_order(
+        &mut self,
+        company: &Company,
+        po_number: Option<&str>,
+        shipping_address: Address,
+        shipping_method: ShippingSelection,
+        taxes: &dyn TaxCalculator,
+    ) -> Result<Order, CommerceError> {
+        self.check_company(company)?;
+        self.check_not_expired()?;
+        if self.status != QuoteStatus::Accepted {
+            return Err(self.invalid_transition(QuoteStatus::Converted));
+        }
+        if shipping_method.rate.currency != self.currency {
+            return Err(CommerceError::ValidationError(format!(
+                "shipping rate is in {}, but quote {} is in {}",
+                shipping_method.rate.currency, self.id, self.currency
+            )));
+        }
+        let po_number = po_number.map(str::trim).filter(|po| !po.is_empty());
+        if company.requires_po_number && po_number.is_none() {
+            return Err(CommerceError::ValidationError(format!(
+                "{} requires a purchase order number",
+                company.name
+            )));
+        }
+        let buyer = company.buyer(&self.requested_by).ok_or_else(|| {
+            CommerceError::ValidationError(format!(
+                "user {} no longer buys for {}",
+                self.requested_by, company.name
+            ))
+        })?;
+        let total = self.total()?;
+
+        let zero = Money::zero(self.currency);
+        let mut line_items = self
+            .lines
+            .iter()
+            .map(|line| {
+                Ok(OrderLineItem {
+                    id: OrderLineItemId::generate(),
+                    variant_id: line.variant_id.clone(),
+                    product_id: line.product_id.clone(),
+                    sku: line.sku.clone().unwrap_or_default(),
+                    name: line.product_name.clone(),
+                    variant_title: line.variant_name.clone(),
+                    quantity: line.quantity,
+                    unit_price: line.quoted_price,
+                    total_price: line.total()?,
+                    discount_amount: zero,
+                    tax_amount: zero,
+                    fulfillment_status: FulfillmentStatus::Unfulfilled,
+                    fulfilled_quantity: 0,
+                    properties: line.properties.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>, CommerceError>>()?;
+
+        let mut request = TaxRequest::new(shipping_address.clone(), self.currency);
+        request.lines = line_items
+            .iter()
+            .map(|line| TaxLine {
+                id: line.id.to_string(),
+                product_id: Some(line.product_id.clone()),
+                amount: line.total_price,
+                class: TaxClass::Standard,
+            })
+            .collect();
+        let tax = taxes.calculate(&request)?;
+        for line in &mut line_items {
+            if let Some(line_tax) = tax.line(line.id.as_str()) {
+                line.tax_amount = line_tax.tax;
+            }
+        }
+        let mut grand_total = total
+            .try_add(&shipping_method.rate)
+            .ok_or(CommerceError::Overflow)?;
+        if !tax.prices_include_tax {
+            grand_total = grand_total
+                .try_add(&tax.total_tax)
+                .ok_or(CommerceError::Overflow)?;
+        }
+        if !buyer.can_spend(&grand_total) {
+            return Err(CommerceError::ValidationError(format!(
+                "{} exceeds the spending limit for {}",
+                grand_total.display(),
+                buyer.email
+            )));
+        }
+
+        let now = current_timestamp();
+        let terms = company.payment_terms;
+        let mut order = Order {
+            id: OrderId::generate(),
+            order_number: Order::generate_order_number(),
+            user_id: Some(self.requested_by.clone()),
+            email: buyer.email.clone(),
+            status: if terms.is_deferred() {
+                OrderStatus::Confirmed
+            } else {
+                OrderStatus::Pending
+            },
+            financial_status: FinancialStatus::Pending,
+            fulfillment_status: FulfillmentStatus::Unfulfilled,
+            line_items,
+            billing_address: company
+                .billing_address
+                .clone()
+                .unwrap_or_else(|| shipping_address.clone()),
+            shipping_address,
+            subtotal: total,
+            discount_total: zero,
+            shipping_total: shipping_method.rate,
+            tax_total: tax.total_tax,
+            grand_total,
+            shipping_method,
+            currency: self.currency,
+            note: self.note.clone(),
+            tags: vec!["b2b".to_string()],
+            metadata: serde_json::json!({
+                "company_id": company.id.as_str(),
+                "quote_id": self.id.as_str(),
+                "po_number": po_number,
+                "payment_terms": terms.to_string(),
+                "payment_due_at": terms.due_at(now),
+            }),
+            created_at: now,
+            updated_at: now,
+            cancelled_at: None,
+            pending_events: Vec::new(),
+        };
+        order.record_created();
+
+        self.transition(&[QuoteStatus::Accepted], QuoteStatus::Converted)?;
+        self.order_id = Some(order.id.clone());
+        Ok(order)
+    }
+
+    fn sum(
+        &self,
+        amount: impl Fn(&QuoteLine) -> Result<Money, CommerceError>,
+    ) -> Result<Money, CommerceError> {
+        self.lines
+            .iter()
+            .try_fold(Money::zero(self.currency), |acc, line| {
+                acc.try_add(&amount(line)?).ok_or(CommerceError::Overflow)
+            })
+    }
+
+    fn line_mut(&mut self, line_id: &LineItemId) -> Result<&mut QuoteLine, CommerceError> {
+        let id = self.id.clone();
+        self.lines
+            .iter_mut()
+            .find(|l| &l.id == line_id)
+            .ok_or_else(|| {
+                CommerceError::ValidationError(format!("line {} is not on quote {}", line_id, id))
+            })
+    }
+
+    fn check_negotiable(&self) -> Result<(), CommerceError> {
+        if !self.status.is_negotiable() {
+            return Err(CommerceError::ValidationError(format!(
+                "quote {} is {} and can no longer be changed",
+                self.id,
+                self.status.as_str()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_company(&self, company: &Company) -> Result<(), CommerceError> {
+        if company.id != self.company_id {
+            return Err(CommerceError::ValidationError(format!(
+                "quote {} belongs to company {}, not {}",
+                self.id, self.company_id, company.id
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_not_expired(&mut self) -> Result<(), CommerceError> {
+        if self.expire_if_due(current_timestamp()) || self.status == QuoteStatus::Expired {
+            return Err(CommerceError::QuoteExpired(self.id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn transition(&mut self, from: &[QuoteStatus], to: QuoteStatus) -> Result<(), CommerceError> {
+        if !from.contains(&self.status) {
+            return Err(self.invalid_transition(to));
+        }
+        self.status = to;
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    fn invalid_transition(&self, to: QuoteStatus) -> CommerceError {
+        CommerceError::InvalidQuoteTransition {
+            from: self.status.as_str().to_string(),
+            to: to.as_str().to_string(),
+        }
+    }
+}
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::b2b::{Buyer, BuyerRole, PaymentTerms};
+    use crate::checkout::fixtures::usd;
+    use crate::checkout::OrderEventKind;
+    use crate::ids::ShippingMethodId;
+    use crate::tax::{TableTaxCalculator, TaxRate, TaxZone};
+
+    fn company() -> Company {
+        let mut company = Company::new("Acme").with_payment_terms(PaymentTerms::Net(30));
+        company
+            .add_buyer(Buyer::new(UserId::new("pat"), "pat@acme.test"))
+            .unwrap();
+        company
+            .add_buyer(Buyer::new(UserId::new("ada"), "ada@acme.test").with_role(BuyerRole::Admin))
+            .unwrap();
+        company
+    }
+
+    fn cart() -> Cart {
+        let mut cart = Cart::new("session");
+        cart.add_item(
+            VariantId::new("v-chair"),
+            ProductId::new("p-chair"),
+            "Chair",
+            50,
+            usd(10000),
+        )
+        .unwrap();
+        cart.items[0].sku = Some("CHAIR-OAK".to_string());
+        cart
+    }
+
+    fn address() -> Address {
+        Address::new("Pat", "Lee", "1 Main St", "Austin", "US", "US", "73301")
+    }
+
+    fn taxes() -> TableTaxCalculator {
+        TableTaxCalculator::new().with_zone(
+            TaxZone::country("United States", "US").with_rate(TaxRate::new(
+                "Sales tax",
+                TaxClass::Standard,
+                10.0,
+            )),
+        )
+    }
+
+    fn ground() -> ShippingSelection {
+        ShippingSelection {
+            method_id: ShippingMethodId::new("freight"),
+            method_name: "Freight".to_string(),
+            rate: usd(25000),
+            carrier: None,
+            delivery_estimate: None,
+        }
+    }
+
+    #[test]
+    fn test_negotiate_accept_convert() {
+        let company = company();
+        let cart = cart();
+        let line_id = cart.items[0].id.clone();
+        let mut quote = Quote::from_cart(&cart, &company, &UserId::new("pat"), 86400).unwrap();
+
+        quote.submit().unwrap();
+        quote.set_line_price(&line_id, usd(8500)).unwrap();
+        assert_eq!(quote.savings().unwrap(), usd(75000));
+        quote.approve().unwrap();
+        assert!(quote.set_line_price(&line_id, usd(8000)).is_err());
+
+        assert!(quote.accept(&company, &UserId::new("pat")).is_err());
+        quote.accept(&company, &UserId::new("ada")).unwrap();
+
+        let address = address();
+        let taxes = taxes();
+        assert!(quote
+            .to_order(&company, Some("  "), address.clone(), ground(), &taxes)
+            .is_err());
+        let mut euro_freight = ground();
+        euro_freight.rate = Money::new(25000, Currency::EUR);
+        assert!(matches!(
+            quote.to_order(
+                &company,
+                Some("PO-7781"),
+                address.clone(),
+                euro_freight,
+                &taxes
+            ),
+            Err(CommerceError::ValidationError(_))
+        ));
+        let mut order = quote
+            .to_order(&company, Some("PO-7781"), address, ground(), &taxes)
+            .unwrap();
+        assert_eq!(order.line_items[0].unit_price, usd(8500));
+        assert_eq!(order.line_items[0].sku, "CHAIR-OAK");
+        assert_eq!(order.line_items[0].tax_amount, usd(42500));
+        assert_eq!(order.tax_total, usd(42500));
+        assert_eq!(order.grand_total, usd(492500));
+        assert_eq!(order.status, OrderStatus::Confirmed);
+        assert_eq!(order.take_events()[0].kind, OrderEventKind::Created);
+        assert_eq!(order.metadata["po_number"], "PO-7781");
+        assert_eq!(order.metadata["payment_terms"], "net_30");
+        assert_eq!(quote.status, QuoteStatus::Converted);
+        assert_eq!(quote.order_id, Some(order.id));
+    }
+
+    #[test]
+    fn test_spending_limit_covers_shipping_and_tax() {
+        let mut company = Company::new("Acme");
+        company
+            .add_buyer(
+                Buyer::new(UserId::new("pat"), "pat@acme.test").with_spending_limit(usd(550_000)),
+            )
+            .unwrap();
+        company
+            .add_buyer(Buyer::new(UserId::new("ada"), "ada@acme.test").with_role(BuyerRole::Admin))
+            .unwrap();
+        let mut quote = Quote::from_cart(&cart(), &company, &UserId::new("pat"), 86400).unwrap();
+        quote.submit().unwrap();
+        quote.approve().unwrap();
+        quote.accept(&company, &UserId::new("ada")).unwrap();
+
+        // Lines are $5,000, under the $5,500 limit; with freight and 10% tax
+        // the order comes to $5,750.
+        assert_eq!(quote.total().unwrap(), usd(500_000));
+        assert!(matches!(
+            quote.to_order(&company, None, address(), ground(), &taxes()),
+            Err(CommerceError::ValidationError(message)) if message.contains("spending limit")
+        ));
+        assert_eq!(quote.status, QuoteStatus::Accepted);
+    }
+
+    #[test]
+    fn test_validity_window_and_access() {
+        let company = company();
+        assert!(Quote::from_cart(&cart(), &company, &UserId::new("stranger"), 60).is_err());
+
+        let mut quote = Quote::from_cart(&cart(), &company, &UserId::new("pat"), 60).unwrap();
+        quote.submit().unwrap();
+        quote.approve().unwrap();
+        assert!(!quote.is_expired_at(quote.valid_until));
+        assert!(quote.is_expired_at(quote.valid_until + 1));
+
+        quote.valid_until = 0;
+        assert!(matches!(
+            quote.accept(&company, &UserId::new("ada")),
+            Err(CommerceError::QuoteExpired(_))
+        ));
+        assert_eq!(quote.status, QuoteStatus::Expired);
+        assert!(quote.reject("too late").is_err());
+    }
+}
@@ -34,6 +34,9 @@ pub struct Cart {
     pub updated_at: i64,
     /// Unix timestamp when cart expires.
     pub expires_at: Option<i64>,
+    /// Unix timestamp when the cart was reported abandoned.
+    #[serde(default)]
+    pub abandoned_at: Option<i64>,
 }
 
 impl Cart {
@@ -52,6 +55,7 @@ impl Cart {
             created_at: now,
             updated_at: now,
             expires_at: None,
+            abandoned_at: None,
         }
     }
 
@@ -276,6 +280,22 @@ impl Cart {
         Ok(())
     }
 
+    /// Check if the cart has expired.
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        self.expires_at.map(|e| now >= e).unwrap_or(false)
+    }
+
+    /// Expire the cart `ttl_secs` after its last update.
+    ///
+    /// Also clears the abandoned marker if the cart changed since it was
+    /// reported, so it can be reported again if it's abandoned later.
+    pub fn extend_expiry(&mut self, ttl_secs: i64) {
+        self.expires_at = Some(self.updated_at + ttl_secs);
+        if self.abandoned_at.is_some_and(|at| self.updated_at > at) {
+            self.abandoned_at = None;
+        }
+    }
+
     /// Set the cart for an authenticated user.
     pub fn set_user(&mut self, user_id: UserId) {
         self.user_id = Some(user_id);
@@ -302,6 +322,9 @@ pub struct LineItem {
     pub product_name: String,
     /// Variant name (e.g., "Large / Blue").
     pub variant_name: Option<String>,
+    /// Variant SKU, copied onto the order line.
+    #[serde(default)]
+    pub sku: Option<String>,
     /// Quantity.
     pub quantity: i64,
     /// Unit price.
@@ -330,6 +353,7 @@ impl LineItem {
             product_id,
             product_name: product_name.into(),
             variant_name: None,
+            sku: None,
             quantity,
             unit_price,
             total_price,
@@ -0,0 +1,731 @@
+//! Coupon codes and redemption.
+//!
+//! A [`Coupon`] is a code that unlocks a [`Discount`]. One discount can have
+//! a single shared code ("SUMMER10") or thousands of generated single-use
+//! codes. The discount carries the global and per-customer limits and the
+//! validity window; each code can carry its own usage limit.
+//!
+//! [`CouponBook`] validates and redeems in memory. With the `storage` feature,
+//! [`Coupon::redeem_stored`] performs the same checks inside a turbo-db
+//! transaction so concurrent checkouts can't over-redeem a code.
+
+use crate::cart::Discount;
+use crate::error::CommerceError;
+use crate::ids::{DiscountId, OrderId, UserId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Characters used in generated codes (no 0/O or 1/I/L lookalikes).
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+/// Default length of the random part of generated codes.
+pub const DEFAULT_CODE_LENGTH: usize = 8;
+
+/// Normalize a code for lookup (trimmed, uppercase).
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// A redeemable code for a discount.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Coupon {
+    /// Normalized code.
+    pub code: String,
+    /// Discount the code unlocks.
+    pub discount_id: DiscountId,
+    /// Maximum uses of this code (None = only the discount's limits apply).
+    pub usage_limit: Option<i64>,
+    /// Times this code has been redeemed.
+    pub usage_count: i64,
+    /// Customer the code was issued to (None = anyone).
+    pub customer_id: Option<UserId>,
+    /// Whether the code can be redeemed.
+    pub active: bool,
+    /// Unix timestamp of creation.
+    pub created_at: i64,
+}
+
+impl Coupon {
+    /// Create a code for a discount.
+    pub fn new(code: &str, discount_id: DiscountId) -> Self {
+        Self {
+            code: normalize_code(code),
+            discount_id,
+            usage_limit: None,
+            usage_count: 0,
+            customer_id: None,
+            active: true,
+            created_at: current_timestamp(),
+        }
+    }
+
+    /// Limit how many times this code can be used.
+    pub fn with_usage_limit(mut self, limit: i64) -> Self {
+        self.usage_limit = Some(limit);
+        self
+    }
+
+    /// Restrict the code to one customer.
+    pub fn for_customer(mut self, customer_id: UserId) -> Self {
+        self.customer_id = Some(customer_id);
+        self
+    }
+
+    /// Check if this code has been used up.
+    pub fn is_exhausted(&self) -> bool {
+        self.usage_limit
+            .map(|limit| self.usage_count >= limit)
+            .unwrap_or(false)
+    }
+}
+
+/// Generates unique, human-friendly codes (e.g., "SPRING-7KQ2M9XA").
+#[derive(Debug, Clone)]
+pub struct CouponCodeGenerator {
+    /// Prefix joined to the random part with a dash (empty = none).
+    pub prefix: String,
+    /// Length of the random part.
+    pub length: usize,
+    seed: Vec<u8>,
+    counter: u64,
+}
+
+impl CouponCodeGenerator {
+    /// Create a generator with a fresh seed.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        let seed = format!("{}:{}", DiscountId::generate(), current_timestamp());
+        Self::with_seed(prefix, seed.as_bytes())
+    }
+
+    /// Create a generator with a fixed seed (same seed, same codes).
+    pub fn with_seed(prefix: impl Into<String>, seed: &[u8]) -> Self {
+        Self {
+            prefix: normalize_code(&prefix.into()),
+            length: DEFAULT_CODE_LENGTH,
+            seed: seed.to_vec(),
+            counter: 0,
+        }
+    }
+
+    /// Set the length of the random part.
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.length = length.max(4);
+        self
+    }
+
+    /// Number of distinct codes this generator can produce (`None` if it
+    /// doesn't fit in a `u64`).
+    pub fn capacity(&self) -> Option<u64> {
+        (CODE_ALPHABET.len() as u64).checked_pow(u32::try_from(self.length).ok()?)
+    }
+
+    /// Whether `code` has the shape of a code from this generator.
+    fn could_produce(&self, code: &str) -> bool {
+        let body = if self.prefix.is_empty() {
+            Some(code)
+        } else {
+            code.strip_prefix(self.prefix.as_str())
+                .and_then(|rest| rest.strip_prefix('-'))
+        };
+        body.is_some_and(|body| {
+            body.len() == self.length && body.bytes().all(|b| CODE_ALPHABET.contains(&b))
+        })
+    }
+
+    /// Generate the next code.
+    pub fn next_code(&mut self) -> String {
+        let mut body = String::with_capacity(self.length);
+        while body.len() < self.length {
+            let mut hasher = Sha256::new();
+            hasher.update(&self.seed);
+            hasher.update(self.counter.to_be_bytes());
+            self.counter += 1;
+            for byte in hasher.finalize() {
+                if body.len() == self.length {
+                    break;
+                }
+                body.push(CODE_ALPHABET[byte as usize % CODE_ALPHABET.len()] as char);
+            }
+        }
+        if self.prefix.is_empty() {
+            body
+        } else {
+            format!("{}-{}", self.prefix, body)
+        }
+    }
+}
+
+/// Who is redeeming a code, and when.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RedemptionContext {
+    /// Customer redeeming (None = guest).
+    pub customer_id: Option<UserId>,
+    /// Orders the customer has already completed.
+    pub completed_orders: i64,
+    /// Unix timestamp of the redemption.
+    pub now: i64,
+}
+
+impl RedemptionContext {
+    /// Create a guest context.
+    pub fn guest(now: i64) -> Self {
+        Self {
+            now,
+            ..Self::default()
+        }
+    }
+
+    /// Create a context for a signed-in customer.
+    pub fn customer(customer_id: UserId, completed_orders: i64, now: i64) -> Self {
+        Self {
+            customer_id: Some(customer_id),
+            completed_orders,
+            now,
+        }
+    }
+}
+
+/// A recorded use of a code.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CouponRedemption {
+    /// Code redeemed.
+    pub code: String,
+    /// Discount unlocked.
+    pub discount_id: DiscountId,
+    /// Customer who redeemed it.
+    pub customer_id: Option<UserId>,
+    /// Order it was redeemed on.
+    pub order_id: Option<OrderId>,
+    /// Unix timestamp of the redemption.
+    pub redeemed_at: i64,
+}
+
+/// Check whether a code can be redeemed in a context.
+///
+/// `discount_uses` and `customer_uses` are the redemptions so far across all
+/// of the discount's codes.
+fn check_eligibility(
+    discount: &Discount,
+    coupon: &Coupon,
+    context: &RedemptionContext,
+    discount_uses: i64,
+    customer_uses: i64,
+) -> Result<(), CommerceError> {
+    let code = &coupon.code;
+    if !discount.active || !coupon.active {
+        return Err(CommerceError::InvalidDiscountCode(code.clone()));
+    }
+    if discount.starts_at.map(|s| context.now < s).unwrap_or(false) {
+        return Err(CommerceError::DiscountNotApplicable {
+            code: code.clone(),
+            reason: "not started yet".to_string(),
+        });
+    }
+    if discount.ends_at.map(|e| context.now > e).unwrap_or(false) {
+        return Err(CommerceError::DiscountExpired(code.clone()));
+    }
+    if let Some(ref owner) = coupon.customer_id {
+        if context.customer_id.as_ref() != Some(owner) {
+            return Err(CommerceError::DiscountNotApplicable {
+                code: code.clone(),
+                reason: "issued to another customer".to_string(),
+            });
+        }
+    }
+    if discount.is_first_order_only() && context.completed_orders > 0 {
+        return Err(CommerceError::DiscountNotApplicable {
+            code: code.clone(),
+            reason: "first order only".to_string(),
+        });
+    }
+    if coupon.is_exhausted() {
+        return Err(CommerceError::DiscountUsageLimitReached(code.clone()));
+    }
+    if discount
+        .usage_limit
+        .map(|l| discount_uses >= l)
+        .unwrap_or(false)
+    {
+        return Err(CommerceError::DiscountUsageLimitReached(code.clone()));
+    }
+    if let Some(limit) = discount.per_customer_limit {
+        if context.customer_id.is_none() {
+            return Err(CommerceError::DiscountNotApplicable {
+                code: code.clone(),
+                reason: "sign in to use this code".to_string(),
+            });
+        }
+        if customer_uses >= limit {
+            return Err(CommerceError::DiscountUsageLimitReached(code.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// In-memory registry of discounts, their codes, and redemptions.
+#[derive(Debug, Clone, Default)]
+pub struct CouponBook {
+    discounts: HashMap<DiscountId, Discount>,
+    coupons: HashMap<String, Coupon>,
+    redemptions: Vec<CouponRedemption>,
+}
+
+impl CouponBook {
+    /// Create an empty book.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or replace) a discount.
+    pub fn add_discount(&mut self, discount: Discount) {
+        self.discounts.insert(discount.id.clone(), discount);
+    }
+
+    /// Get a discount.
+    pub fn discount(&self, id: &DiscountId) -> Option<&Discount> {
+        self.discounts.get(id)
+    }
+
+    /// Look up a code (case-insensitive).
+    pub fn coupon(&self, code: &str) -> Option<&Coupon> {
+        self.coupons.get(&normalize_code(code))
+    }
+
+    /// Issue a code. Fails if the discount is unknown or the code is taken.
+    pub fn issue(&mut self, coupon: Coupon) -> Result<&Coupon, CommerceError> {
+        if !self.discounts.contains_key(&coupon.discount_id) {
+            return Err(CommerceError::ValidationError(format!(
+                "unknown discount {}",
+                coupon.discount_id
+            )));
+        }
+        if coupon.code.is_empty() {
+            return Err(CommerceError::ValidationError(
+                "coupon code is empty".to_string(),
+            ));
+        }
+        if self.coupons.contains_key(&coupon.code) {
+            return Err(CommerceError::ValidationError(format!(
+                "coupon code {} already exists",
+                coupon.code
+            )));
+        }
+        let code = coupon.code.clone();
+        Ok(self.coupons.entry(code).or_insert(coupon))
+    }
+
+    /// Generate `count` unique codes for a discount, each usable `uses_per_code` times.
+    ///
+    /// Fails with [`CommerceError::QuantityExceedsLimit`] if the generator
+    /// has fewer than `count` unused codes left.
+    pub fn issue_bulk(
+        &mut self,
+        discount_id: &DiscountId,
+        generator: &mut CouponCodeGenerator,
+        count: usize,
+        uses_per_code: i64,
+    ) -> Result<Vec<String>, CommerceError> {
+        if uses_per_code <= 0 {
+            return Err(CommerceError::InvalidQuantity(uses_per_code));
+        }
+        if let Some(capacity) = generator.capacity() {
+            let taken = self
+                .coupons
+                .keys()
+                .filter(|code| generator.could_produce(code))
+                .count() as u64;
+            let available = capacity.saturating_sub(taken);
+            if count as u64 > available {
+                return Err(CommerceError::QuantityExceedsLimit(
+                    count as i64,
+                    available as i64,
+                ));
+            }
+        }
+        let mut codes = Vec::with_capacity(count);
+        while codes.len() < count {
+            let code = generator.next_code();
+            if self.coupons.contains_key(&code) {
+                continue;
+            }
+            let coupon = Coupon::new(&code, discount_id.clone()).with_usage_limit(uses_per_code);
+            self.issue(coupon)?;
+            codes.push(code);
+        }
+        Ok(codes)
+    }
+
+    /// Times a discount has been redeemed, across all codes.
+    pub fn discount_usage(&self, discount_id: &DiscountId) -> i64 {
+        self.redemptions
+            .iter()
+            .filter(|r| &r.discount_id == discount_id)
+            .count() as i64
+    }
+
+    /// Times a customer has redeemed a discount, across all codes.
+    pub fn customer_usage(&self, discount_id: &DiscountId, customer_id: &UserId) -> i64 {
+        self.redemptions
+            .iter()
+            .filter(|r| {
+                &r.discount_id == discount_id && r.customer_id.as_ref() == Some(customer_id)
+            })
+            .count() as i64
+    }
+
+    /// Check that a code can be redeemed, returning its discount.
+    pub fn validate(
+        &self,
+        code: &str,
+        context: &RedemptionContext,
+    ) -> Result<&Discount, CommerceError> {
+        let coupon = self
+            .coupon(code)
+            .ok_or_else(|| CommerceError::InvalidDiscountCode(code.to_string()))?;
+        let discount = self
+            .discounts
+            .get(&coupon.discount_id)
+            .ok_or_else(|| CommerceError::InvalidDiscountCode(code.to_string()))?;
+        let customer_uses = context
+            .customer_id
+            .as_ref()
+            .map(|c| self.customer_usage(&discount.id, c))
+            .unwrap_or(0);
+        check_eligibility(
+            discount,
+            coupon,
+            context,
+            self.discount_usage(&discount.id),
+            customer_uses,
+        )?;
+        Ok(discount)
+    }
+
+    /// Validate and record a redemption in one step.
+    pub fn redeem(
+        &mut self,
+        code: &str,
+        context: &RedemptionContext,
+        order_id: Option<OrderId>,
+    ) -> Result<CouponRedemption, CommerceError> {
+        let discount_id = self.validate(code, context)?.id.clone();
+        let normalized = normalize_code(code);
+        if let Some(coupon) = self.coupons.get_mut(&normalized) {
+            coupon.usage_count += 1;
+        }
+        if let Some(discount) = self.discounts.get_mut(&discount_id) {
+            discount.record_usage();
+        }
+        let redemption = CouponRedemption {
+            code: normalized,
+            discount_id,
+            customer_id: context.customer_id.clone(),
+            order_id,
+            redeemed_at: context.now,
+        };
+        self.redemptions.push(redemption.clone());
+        Ok(redemption)
+    }
+}
+
+#[cfg(feature = "storage")]
+mod storage {
+    use super::*;
+    use turbo_db::{params, Backend, Db, Value};
+
+    /// SQL to create the table of issued codes.
+    pub const COUPON_CODES_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS coupon_codes (
+        code TEXT PRIMARY KEY,
+        discount_id TEXT NOT NULL,
+        usage_limit INTEGER,
+        usage_count INTEGER NOT NULL DEFAULT 0,
+        coupon TEXT NOT NULL
+    )";
+
+    /// SQL to create the table of redemptions.
+    pub const COUPON_REDEMPTIONS_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS coupon_redemptions (
+        code TEXT NOT NULL,
+        discount_id TEXT NOT NULL,
+        customer_id TEXT,
+        order_id TEXT,
+        redeemed_at INTEGER NOT NULL
+    )";
+
+    #[derive(Deserialize)]
+    struct CouponRow {
+        usage_count: i64,
+        coupon: String,
+    }
+
+    #[derive(Deserialize)]
+    struct CountRow {
+        count: i64,
+    }
+
+    fn optional(value: Option<&str>) -> Value {
+        value.map(Value::from).unwrap_or(Value::Null)
+    }
+
+    impl Coupon {
+        /// Insert the code. Fails if the code already exists.
+        pub fn insert(&self, db: &Db) -> Result<(), CommerceError> {
+            db.execute(
+                "INSERT INTO coupon_codes (code, discount_id, usage_limit, usage_count, coupon)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![
+                    self.code.as_str(),
+                    self.discount_id.as_str(),
+                    self.usage_limit.map(Value::from).unwrap_or(Value::Null),
+                    self.usage_count,
+                    serde_json::to_string(self)?
+                ],
+            )?;
+            Ok(())
+        }
+
+        /// Load a code (case-insensitive).
+        pub fn load(db: &Db, code: &str) -> Result<Option<Self>, CommerceError> {
+            let row: Option<CouponRow> = db.query_optional(
+                "SELECT usage_count, coupon FROM coupon_codes WHERE code = ?",
+                params![normalize_code(code)],
+            )?;
+            row.map(|row| {
+                let mut coupon: Coupon = serde_json::from_str(&row.coupon)?;
+                coupon.usage_count = row.usage_count;
+                Ok(coupon)
+            })
+            .transpose()
+        }
+
+        /// Validate and redeem a code against the database.
+        ///
+        /// Runs in a [`Db::transaction`]. SQLite serializes it with
+        /// `BEGIN IMMEDIATE`; on Postgres the redemptions table is locked
+        /// first when the discount has a limit, so the counts can't go stale
+        /// under READ COMMITTED. The code's usage count is only incremented
+        /// while it is below its limit, which guards the last use of a code
+        /// on its own.
+        pub fn redeem_stored(
+            db: &Db,
+            code: &str,
+            discount: &Discount,
+            context: &RedemptionContext,
+            order_id: Option<OrderId>,
+        ) -> Result<CouponRedemption, CommerceError> {
+            db.transaction(|db| Self::redeem_in_transaction(db, code, discount, context, order_id))
+        }
+
+        fn redeem_in_transaction(
+            db: &Db,
+            code: &str,
+            discount: &Discount,
+            context: &RedemptionContext,
+            order_id: Option<OrderId>,
+        ) -> Result<CouponRedemption, CommerceError> {
+            let coupon = Self::load(db, code)?
+                .filter(|c| c.discount_id == discount.id)
+                .ok_or_else(|| CommerceError::InvalidDiscountCode(code.to_string()))?;
+            let limited = discount.usage_limit.is_some() || discount.per_customer_limit.is_some();
+            if limited && db.backend() == Backend::Postgres {
+                // Self-conflicting, so concurrent redemptions queue here
+                // instead of both counting the same rows.
+                db.execute(
+                    "LOCK TABLE coupon_redemptions IN SHARE ROW EXCLUSIVE MODE",
+                    &[],
+                )?;
+            }
+            let discount_uses: CountRow = db.query_one(
+                "SELECT COUNT(*) AS count FROM coupon_redemptions WHERE discount_id = ?",
+                params![discount.id.as_str()],
+            )?;
+            let customer_uses = match context.customer_id {
+                Some(ref customer) => {
+                    let row: CountRow = db.query_one(
+                        "SELECT COUNT(*) AS count FROM coupon_redemptions
+                         WHERE discount_id = ? AND customer_id = ?",
+                        params![discount.id.as_str(), customer.as_str()],
+                    )?;
+                    row.count
+                }
+                None => 0,
+            };
+            check_eligibility(
+                discount,
+                &coupon,
+                context,
+                discount_uses.count,
+                customer_uses,
+            )?;
+
+            let claimed: Option<CountRow> = db.query_optional(
+                "UPDATE coupon_codes SET usage_count = usage_count + 1
+                 WHERE code = ? AND (usage_limit IS NULL OR usage_count < usage_limit)
+                 RETURNING usage_count AS count",
+                params![coupon.code.as_str()],
+            )?;
+            if claimed.is_none() {
+                return Err(CommerceError::DiscountUsageLimitReached(coupon.code));
+            }
+
+            let redemption = CouponRedemption {
+                code: coupon.code,
+                discount_id: discount.id.clone(),
+                customer_id: context.customer_id.clone(),
+                order_id,
+                redeemed_at: context.now,
+            };
+            db.execute(
+                "INSERT INTO coupon_redemptions
+                 (code, discount_id, customer_id, order_id, redeemed_at)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![
+                    redemption.code.as_str(),
+                    redemption.discount_id.as_str(),
+                    optional(redemption.customer_id.as_ref().map(|c| c.as_str())),
+                    optional(redemption.order_id.as_ref().map(|o| o.as_str())),
+                    redemption.redeemed_at
+                ],
+            )?;
+            Ok(redemption)
+        }
+    }
+}
+
+#[cfg(feature = "storage")]
+pub use storage::{COUPON_CODES_SCHEMA, COUPON_REDEMPTIONS_SCHEMA};
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book_with(discount: Discount) -> (CouponBook, DiscountId) {
+        let id = discount.id.clone();
+        let mut book = CouponBook::new();
+        book.add_discount(discount);
+        (book, id)
+    }
+
+    #[test]
+    fn test_generated_codes_are_unique() {
+        let (mut book, id) = book_with(Discount::percentage("", "Welcome", 10.0));
+        let mut generator = CouponCodeGenerator::with_seed("welcome", b"seed");
+        let codes = book.issue_bulk(&id, &mut generator, 50, 1).unwrap();
+        assert_eq!(codes.len(), 50);
+        assert!(codes[0].starts_with("WELCOME-"));
+        assert_eq!(codes[0].len(), "WELCOME-".len() + DEFAULT_CODE_LENGTH);
+
+        let mut again = CouponCodeGenerator::with_seed("welcome", b"seed");
+        assert_eq!(again.next_code(), codes[0]);
+        assert!(book
+            .issue(Coupon::new(&codes[3].to_lowercase(), id))
+            .is_err());
+    }
+
+    #[test]
+    fn test_issue_bulk_rejects_more_codes_than_the_generator_can_make() {
+        let (mut book, id) = book_with(Discount::percentage("", "Tiny", 10.0));
+        let mut generator = CouponCodeGenerator::with_seed("tiny", b"seed").with_length(4);
+        assert_eq!(generator.capacity(), Some(31u64.pow(4)));
+        book.issue(Coupon::new("TINY-AAAA", id.clone())).unwrap();
+        book.issue(Coupon::new("OTHER", id.clone())).unwrap();
+        assert!(matches!(
+            book.issue_bulk(&id, &mut generator, 31usize.pow(4), 1),
+            Err(CommerceError::QuantityExceedsLimit(_, available)) if available == 31i64.pow(4) - 1
+        ));
+
+        let mut huge = CouponCodeGenerator::with_seed("", b"seed").with_length(64);
+        assert_eq!(huge.capacity(), None);
+        assert_eq!(book.issue_bulk(&id, &mut huge, 2, 1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_discount_limit_of_one_admits_one_redemption() {
+        let discount = Discount::percentage("", "Launch", 20.0).with_usage_limit(1);
+        let (mut book, id) = book_with(discount);
+        book.issue(Coupon::new("FIRST", id.clone())).unwrap();
+        book.issue(Coupon::new("SECOND", id.clone())).unwrap();
+
+        let alice = RedemptionContext::customer(UserId::new("alice"), 0, 100);
+        let bob = RedemptionContext::customer(UserId::new("bob"), 0, 100);
+        assert!(book.redeem("FIRST", &alice, None).is_ok());
+        assert!(matches!(
+            book.redeem("SECOND", &bob, None),
+            Err(CommerceError::DiscountUsageLimitReached(_))
+        ));
+        assert_eq!(book.discount_usage(&id), 1);
+    }
+
+    #[test]
+    fn test_usage_limits() {
+        let discount = Discount::percentage("", "Spring", 15.0)
+            .with_usage_limit(3)
+            .with_per_customer_limit(1);
+        let (mut book, id) = book_with(discount);
+        book.issue(Coupon::new("spring", id.clone())).unwrap();
+        book.issue(Coupon::new("ONCE", id.clone()).with_usage_limit(1))
+            .unwrap();
+
+        let alice = RedemptionContext::customer(UserId::new("alice"), 2, 100);
+        let bob = RedemptionContext::customer(UserId::new("bob"), 0, 100);
+        let carol = RedemptionContext::customer(UserId::new("carol"), 0, 100);
+        let dave = RedemptionContext::customer(UserId::new("dave"), 0, 100);
+
+        assert!(book.redeem(" Spring ", &alice, None).is_ok());
+        assert!(matches!(
+            book.redeem("SPRING", &alice, None),
+            Err(CommerceError::DiscountUsageLimitReached(_))
+        ));
+        assert!(book.redeem("once", &bob, None).is_ok());
+        assert!(book.redeem("ONCE", &carol, None).is_err());
+        assert!(book.redeem("SPRING", &carol, None).is_ok());
+        assert!(book.redeem("SPRING", &dave, None).is_err());
+        assert!(matches!(
+            book.validate("SPRING", &RedemptionContext::guest(100)),
+            Err(CommerceError::DiscountUsageLimitReached(_))
+        ));
+        assert_eq!(book.discount_usage(&id), 3);
+        assert_eq!(book.discount(&id).unwrap().usage_count, 3);
+    }
+
+    #[test]
+    fn test_window_first_order_and_assignment() {
+        let discount = Discount::fixed_amount(
+            "",
+            "Welcome",
+            crate::money::Money::new(500, crate::money::Currency::USD),
+        )
+        .with_window(100, 200)
+        .first_order_only();
+        let (mut book, id) = book_with(discount);
+        book.issue(Coupon::new("HELLO", id.clone())).unwrap();
+        book.issue(Coupon::new("VIP", id).for_customer(UserId::new("alice")))
+            .unwrap();
+
+        let new_customer = |now| RedemptionContext::customer(UserId::new("bob"), 0, now);
+        assert!(book.validate("HELLO", &new_customer(50)).is_err());
+        assert!(book.validate("HELLO", &new_customer(150)).is_ok());
+        assert!(matches!(
+            book.validate("HELLO", &new_customer(250)),
+            Err(CommerceError::DiscountExpired(_))
+        ));
+        let returning = RedemptionContext::customer(UserId::new("bob"), 1, 150);
+        assert!(book.validate("HELLO", &returning).is_err());
+        assert!(book.validate("VIP", &new_customer(150)).is_err());
+        let alice = RedemptionContext::customer(UserId::new("alice"), 0, 150);
+        assert!(book.validate("VIP", &alice).is_ok());
+        assert!(matches!(
+            book.validate("NOPE", &alice),
+            Err(CommerceError::InvalidDiscountCode(_))
+        ));
+    }
+}
@@ -240,6 +240,30 @@ impl Discount {
         self
     }
 
+    /// Limit how many times each customer can use the discount.
+    pub fn with_per_customer_limit(mut self, limit: i64) -> Self {
+        self.per_customer_limit = Some(limit);
+        self
+    }
+
+    /// Only allow the discount between two timestamps.
+    pub fn with_window(mut self, starts_at: i64, ends_at: i64) -> Self {
+        self.starts_at = Some(starts_at);
+        self.ends_at = Some(ends_at);
+        self
+    }
+
+    /// Only allow the discount on a customer's first order.
+    pub fn first_order_only(mut self) -> Self {
+        self.conditions.push(DiscountCondition::FirstOrder);
+        self
+    }
+
+    /// Check if the discount is restricted to first orders.
+    pub fn is_first_order_only(&self) -> bool {
+        self.conditions.contains(&DiscountCondition::FirstOrder)
+    }
+
     /// Set expiration date.
     pub fn expires_at(mut self, timestamp: i64) -> Self {
         self.ends_at = Some(timestamp);
@@ -0,0 +1,342 @@
+//! Cart expiration and abandoned-cart detection.
+//!
+//! A [`CartExpiryPolicy`] decides when a cart expires and when an idle cart
+//! counts as abandoned. Sweeping a set of carts reports expired carts for
+//! deletion and emits one [`CartAbandoned`] event per abandoned cart, for
+//! reminder emails or remarketing jobs.
+
+use crate::cart::Cart;
+use crate::ids::{CartId, ProductId, UserId, VariantId};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// Default lifetime of a guest cart after its last update (7 days).
+pub const DEFAULT_GUEST_CART_TTL_SECS: i64 = 7 * 24 * 3600;
+
+/// Default lifetime of a signed-in customer's cart after its last update (30 days).
+pub const DEFAULT_CUSTOMER_CART_TTL_SECS: i64 = 30 * 24 * 3600;
+
+/// Default idle time before a cart counts as abandoned (1 hour).
+pub const DEFAULT_ABANDONED_AFTER_SECS: i64 = 3600;
+
+/// Lifecycle state of a cart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CartStatus {
+    /// Recently updated, or empty.
+    Active,
+    /// Has items but has been idle past the abandonment threshold.
+    Abandoned,
+    /// Past its expiry; safe to delete.
+    Expired,
+}
+
+impl CartStatus {
+    /// Lowercase name, as stored and reported.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CartStatus::Active => "active",
+            CartStatus::Abandoned => "abandoned",
+            CartStatus::Expired => "expired",
+        }
+    }
+}
+
+/// When carts expire and when they count as abandoned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CartExpiryPolicy {
+    /// Lifetime of a guest cart after its last update.
+    pub guest_ttl_secs: i64,
+    /// Lifetime of a signed-in customer's cart after its last update.
+    pub customer_ttl_secs: i64,
+    /// Idle time before a non-empty cart counts as abandoned.
+    pub abandoned_after_secs: i64,
+}
+
+impl Default for CartExpiryPolicy {
+    fn default() -> Self {
+        Self {
+            guest_ttl_secs: DEFAULT_GUEST_CART_TTL_SECS,
+            customer_ttl_secs: DEFAULT_CUSTOMER_CART_TTL_SECS,
+            abandoned_after_secs: DEFAULT_ABANDONED_AFTER_SECS,
+        }
+    }
+}
+
+impl CartExpiryPolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the guest cart lifetime.
+    pub fn with_guest_ttl(mut self, secs: i64) -> Self {
+        self.guest_ttl_secs = secs;
+        self
+    }
+
+    /// Set the customer cart lifetime.
+    pub fn with_customer_ttl(mut self, secs: i64) -> Self {
+        self.customer_ttl_secs = secs;
+        self
+    }
+
+    /// Set the idle time before a cart counts as abandoned.
+    pub fn with_abandoned_after(mut self, secs: i64) -> Self {
+        self.abandoned_after_secs = secs;
+        self
+    }
+
+    /// Lifetime for a cart.
+    pub fn ttl_for(&self, cart: &Cart) -> i64 {
+        if cart.user_id.is_some() {
+            self.customer_ttl_secs
+        } else {
+            self.guest_ttl_secs
+        }
+    }
+
+    /// Set the cart's expiry from its last update. Call before saving.
+    pub fn apply(&self, cart: &mut Cart) {
+        cart.extend_expiry(self.ttl_for(cart));
+    }
+
+    /// Classify a cart.
+    ///
+    /// A cart without an explicit expiry expires `ttl_for` after its last update.
+    pub fn status(&self, cart: &Cart, now: i64) -> CartStatus {
+        let expires_at = cart
+            .expires_at
+            .unwrap_or(cart.updated_at + self.ttl_for(cart));
+        if now >= expires_at {
+            CartStatus::Expired
+        } else if !cart.is_empty() && now - cart.updated_at >= self.abandoned_after_secs {
+            CartStatus::Abandoned
+        } else {
+            CartStatus::Active
+        }
+    }
+
+    /// Sweep carts, marking newly abandoned ones.
+    ///
+    /// Each abandonment is reported once; `abandoned_at` is set so the next
+    /// sweep skips the cart until it's updated again. Save the carts
+    /// afterwards to persist the marker.
+    pub fn sweep<'a>(&self, carts: impl IntoIterator<Item = &'a mut Cart>, now: i64) -> CartSweep {
+        let mut sweep = CartSweep::default();
+        for cart in carts {
+            match self.status(cart, now) {
+                CartStatus::Expired => sweep.expired.push(cart.id.clone()),
+                CartStatus::Abandoned if newly_abandoned(cart) => {
+                    cart.abandoned_at = Some(now);
+                    sweep.abandoned.push(CartAbandoned::from_cart(cart, now));
+                }
+                _ => {}
+            }
+        }
+        sweep
+    }
+}
+
+/// Whether a cart hasn't been reported abandoned since its last update.
+fn newly_abandoned(cart: &Cart) -> bool {
+    match cart.abandoned_at {
+        Some(at) => cart.updated_at > at,
+        None => true,
+    }
+}
+
+/// A line in an abandoned cart.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AbandonedItem {
+    /// Product.
+    pub product_id: ProductId,
+    /// Variant.
+    pub variant_id: VariantId,
+    /// Product name.
+    pub product_name: String,
+    /// Variant name.
+    pub variant_name: Option<String>,
+    /// Quantity.
+    pub quantity: i64,
+    /// Line total.
+    pub total_price: Money,
+}
+
+/// Event emitted when a cart is detected as abandoned.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CartAbandoned {
+    /// The cart.
+    pub cart_id: CartId,
+    /// Session the cart belongs to.
+    pub session_id: String,
+    /// Customer (None = guest).
+    pub user_id: Option<UserId>,
+    /// Items left in the cart.
+    pub items: Vec<AbandonedItem>,
+    /// Sum of line totals (None if the lines mix currencies).
+    pub subtotal: Option<Money>,
+    /// Unix timestamp of the last cart update.
+    pub last_activity_at: i64,
+    /// Unix timestamp when the abandonment was detected.
+    pub detected_at: i64,
+}
+
+impl CartAbandoned {
+    /// Build the event for a cart.
+    pub fn from_cart(cart: &Cart, detected_at: i64) -> Self {
+        Self {
+            cart_id: cart.id.clone(),
+            session_id: cart.session_id.clone(),
+            user_id: cart.user_id.clone(),
+            items: cart
+                .items
+                .iter()
+                .map(|item| AbandonedItem {
+                    product_id: item.product_id.clone(),
+                    variant_id: item.variant_id.clone(),
+                    product_name: item.product_name.clone(),
+                    variant_name: item.variant_name.clone(),
+                    quantity: item.quantity,
+                    total_price: item.total_price,
+                })
+                .collect(),
+            subtotal: Money::try_sum(cart.items.iter().map(|i| &i.total_price), cart.currency),
+            last_activity_at: cart.updated_at,
+            detected_at,
+        }
+    }
+}
+
+/// Result of sweeping carts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CartSweep {
+    /// Carts past their expiry.
+    pub expired: Vec<CartId>,
+    /// Carts newly detected as abandoned.
+    pub abandoned: Vec<CartAbandoned>,
+}
+
+#[cfg(feature = "storage")]
+mod storage {
+    use super::*;
+    use crate::error::CommerceError;
+    use turbo_cache::Cache;
+
+    impl CartExpiryPolicy {
+        /// Sweep carts stored in the cache under keys starting with `prefix`
+        /// (e.g., "cart:").
+        ///
+        /// Expired carts are deleted. Newly abandoned carts are written back
+        /// with their abandoned marker so they are only reported once.
+        pub fn sweep_store(
+            &self,
+            cache: &Cache,
+            prefix: &str,
+            now: i64,
+        ) -> Result<CartSweep, CommerceError> {
+            let mut sweep = CartSweep::default();
+            for key in cache.keys()? {
+                if !key.starts_with(prefix) {
+                    continue;
+                }
+                let Some(mut cart) = cache.get::<Cart>(&key)? else {
+                    continue;
+                };
+                let mut found = self.sweep(std::iter::once(&mut cart), now);
+                if !found.expired.is_empty() {
+                    cache.delete(&key)?;
+                } else if !found.abandoned.is_empty() {
+                    cache.set(&key, &cart)?;
+                }
+                sweep.expired.append(&mut found.expired);
+                sweep.abandoned.append(&mut found.abandoned);
+            }
+            Ok(sweep)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    fn cart(updated_at: i64, items: i64) -> Cart {
+        let mut cart = Cart::new("session");
+        for i in 0..items {
+            cart.add_item(
+                VariantId::new(format!("v{}", i)),
+                ProductId::new(format!("p{}", i)),
+                "Item",
+                1,
+                Money::new(1000, Currency::USD),
+            )
+            .unwrap();
+        }
+        cart.updated_at = updated_at;
+        cart
+    }
+
+    #[test]
+    fn test_status_and_expiry() {
+        let policy = CartExpiryPolicy::new()
+            .with_guest_ttl(1000)
+            .with_customer_ttl(5000)
+            .with_abandoned_after(100);
+
+        let mut guest = cart(0, 1);
+        policy.apply(&mut guest);
+        assert_eq!(guest.expires_at, Some(1000));
+        assert_eq!(policy.status(&guest, 50), CartStatus::Active);
+        assert_eq!(policy.status(&guest, 200), CartStatus::Abandoned);
+        assert_eq!(policy.status(&guest, 1000), CartStatus::Expired);
+        assert!(guest.is_expired_at(1000));
+
+        let mut customer = cart(0, 1);
+        customer.set_user(UserId::new("u1"));
+        customer.updated_at = 0;
+        assert_eq!(policy.status(&customer, 1000), CartStatus::Abandoned);
+        assert_eq!(policy.status(&cart(0, 0), 200), CartStatus::Active);
+    }
+
+    #[test]
+    fn test_sweep_reports_once() {
+        let policy = CartExpiryPolicy::new()
+            .with_guest_ttl(1000)
+            .with_abandoned_after(100);
+        let mut carts = [cart(0, 2), cart(0, 0), cart(-5000, 1)];
+
+        let sweep = policy.sweep(carts.iter_mut(), 200);
+        assert_eq!(sweep.expired, vec![carts[2].id.clone()]);
+        assert_eq!(sweep.abandoned.len(), 1);
+        let event = &sweep.abandoned[0];
+        assert_eq!(event.items.len(), 2);
+        assert_eq!(event.subtotal, Some(Money::new(2000, Currency::USD)));
+        assert_eq!(carts[0].abandoned_at, Some(200));
+
+        assert!(policy.sweep(carts.iter_mut(), 300).abandoned.is_empty());
+
+        // Activity re-arms the cart.
+        carts[0].updated_at = 400;
+        policy.apply(&mut carts[0]);
+        assert_eq!(carts[0].abandoned_at, None);
+        assert_eq!(policy.sweep(carts.iter_mut(), 600).abandoned.len(), 1);
+    }
+
+    #[test]
+    fn test_sweep_rearms_updated_carts() {
+        let policy = CartExpiryPolicy::new()
+            .with_guest_ttl(1000)
+            .with_abandoned_after(100);
+        let mut carts = [cart(0, 2)];
+        assert_eq!(policy.sweep(carts.iter_mut(), 200).abandoned.len(), 1);
+
+        // Updated without going through `apply`, so the marker is stale.
+        carts[0].updated_at = 300;
+        assert_eq!(carts[0].abandoned_at, Some(200));
+        let sweep = policy.sweep(carts.iter_mut(), 500);
+        assert_eq!(sweep.abandoned.len(), 1);
+        assert_eq!(carts[0].abandoned_at, Some(500));
+        assert!(policy.sweep(carts.iter_mut(), 600).abandoned.is_empty());
+    }
+}
@@ -1,12 +1,30 @@
 //! Shopping cart module.
 //!
-//! Contains types for cart, line items, pricing, and discounts.
+//! Contains types for cart, line items, pricing, discounts, coupon codes,
+//! rule-based promotions, and cart expiry.
 
 #[allow(clippy::module_inception)]
 mod cart;
+mod coupon;
 mod discount;
+mod expiry;
 mod pricing;
+mod promotion;
 
 pub use cart::{Cart, LineItem, LineItemProperty, MAX_QUANTITY_PER_ITEM};
+pub use coupon::{
+    normalize_code, Coupon, CouponBook, CouponCodeGenerator, CouponRedemption, RedemptionContext,
+    DEFAULT_CODE_LENGTH,
+};
+#[cfg(feature = "storage")]
+pub use coupon::{COUPON_CODES_SCHEMA, COUPON_REDEMPTIONS_SCHEMA};
 pub use discount::{AppliedDiscount, Discount, DiscountCondition, DiscountType, DiscountValue};
+pub use expiry::{
+    AbandonedItem, CartAbandoned, CartExpiryPolicy, CartStatus, CartSweep,
+    DEFAULT_ABANDONED_AFTER_SECS, DEFAULT_CUSTOMER_CART_TTL_SECS, DEFAULT_GUEST_CART_TTL_SECS,
+};
 pub use pricing::{CartPricing, LineItemPricing};
+pub use promotion::{
+    Promotion, PromotionAction, PromotionCondition, PromotionContext, PromotionEngine,
+    PromotionResult, PromotionTarget, Stacking,
+};
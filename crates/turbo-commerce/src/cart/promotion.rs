@@ -0,0 +1,606 @@
+//! Rule-based promotions.
+//!
+//! A [`Promotion`] pairs a [`PromotionCondition`] with a [`PromotionAction`].
+//! The [`PromotionEngine`] evaluates every active promotion against a cart in
+//! priority order, honours each promotion's [`Stacking`] rule, and returns
+//! the resulting [`AppliedDiscount`]s.
+
+use crate::cart::{AppliedDiscount, Cart, LineItem};
+use crate::error::CommerceError;
+use crate::ids::{CategoryId, DiscountId, ProductId};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A condition a cart must meet for a promotion to apply.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PromotionCondition {
+    /// Always true.
+    Always,
+    /// Cart subtotal is at least this amount.
+    SubtotalAtLeast(Money),
+    /// Cart has at least this many units.
+    QuantityAtLeast(i64),
+    /// Cart contains the product.
+    ContainsProduct(ProductId),
+    /// Cart contains a product from the category.
+    ContainsCategory(CategoryId),
+    /// Customer belongs to the group.
+    CustomerGroup(String),
+    /// All conditions hold.
+    All(Vec<PromotionCondition>),
+    /// At least one condition holds.
+    Any(Vec<PromotionCondition>),
+    /// The condition does not hold.
+    Not(Box<PromotionCondition>),
+}
+
+impl PromotionCondition {
+    /// Combine with another condition (both must hold).
+    pub fn and(self, other: PromotionCondition) -> Self {
+        match self {
+            PromotionCondition::Always => other,
+            PromotionCondition::All(mut all) => {
+                all.push(other);
+                PromotionCondition::All(all)
+            }
+            this => PromotionCondition::All(vec![this, other]),
+        }
+    }
+
+    /// Combine with another condition (either may hold).
+    pub fn or(self, other: PromotionCondition) -> Self {
+        match self {
+            PromotionCondition::Any(mut any) => {
+                any.push(other);
+                PromotionCondition::Any(any)
+            }
+            this => PromotionCondition::Any(vec![this, other]),
+        }
+    }
+
+    /// Evaluate the condition against a cart.
+    pub fn evaluate(&self, cart: &Cart, context: &PromotionContext) -> bool {
+        match self {
+            PromotionCondition::Always => true,
+            PromotionCondition::SubtotalAtLeast(min) => {
+                let subtotal: i64 = cart.items.iter().map(|i| i.total_price.amount_cents).sum();
+                min.currency == cart.currency && subtotal >= min.amount_cents
+            }
+            PromotionCondition::QuantityAtLeast(min) => cart.item_count() >= *min,
+            PromotionCondition::ContainsProduct(id) => {
+                cart.items.iter().any(|i| &i.product_id == id)
+            }
+            PromotionCondition::ContainsCategory(id) => cart
+                .items
+                .iter()
+                .any(|i| context.in_category(&i.product_id, id)),
+            PromotionCondition::CustomerGroup(group) => context
+                .customer_groups
+                .iter()
+                .any(|g| g.eq_ignore_ascii_case(group)),
+            PromotionCondition::All(all) => all.iter().all(|c| c.evaluate(cart, context)),
+            PromotionCondition::Any(any) => any.iter().any(|c| c.evaluate(cart, context)),
+            PromotionCondition::Not(inner) => !inner.evaluate(cart, context),
+        }
+    }
+}
+
+/// Which cart lines an action discounts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum PromotionTarget {
+    /// Every line.
+    #[default]
+    Cart,
+    /// Lines for these products.
+    Products(Vec<ProductId>),
+    /// Lines for products in these categories.
+    Categories(Vec<CategoryId>),
+}
+
+impl PromotionTarget {
+    fn includes(&self, item: &LineItem, context: &PromotionContext) -> bool {
+        match self {
+            PromotionTarget::Cart => true,
+            PromotionTarget::Products(ids) => ids.contains(&item.product_id),
+            PromotionTarget::Categories(ids) => {
+                ids.iter().any(|c| context.in_category(&item.product_id, c))
+            }
+        }
+    }
+}
+
+/// What a promotion does when its condition holds.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PromotionAction {
+    /// Percentage off the targeted lines.
+    PercentageOff {
+        percent: f64,
+        target: PromotionTarget,
+    },
+    /// Fixed amount off the cart (capped at the subtotal).
+    FixedOff(Money),
+    /// Buy `buy` units, get `get` more at `percent_off` (100 = free).
+    ///
+    /// Units are grouped most expensive first, so the discounted units are
+    /// the cheapest of each group.
+    BuyXGetY {
+        buy: i64,
+        get: i64,
+        percent_off: f64,
+        target: PromotionTarget,
+    },
+    /// The cheapest targeted unit is free (needs at least two units).
+    CheapestItemFree { target: PromotionTarget },
+    /// Shipping is free.
+    FreeShipping,
+}
+
+impl PromotionAction {
+    /// Calculate the discount amount for a cart.
+    fn amount(&self, cart: &Cart, context: &PromotionContext) -> Money {
+        let zero = Money::zero(cart.currency);
+        match self {
+            PromotionAction::PercentageOff { percent, target } => {
+                let subtotal: i64 = cart
+                    .items
+                    .iter()
+                    .filter(|i| target.includes(i, context))
+                    .map(|i| i.total_price.amount_cents)
+                    .sum();
+                Money::new(subtotal, cart.currency).percentage(percent.clamp(0.0, 100.0))
+            }
+            PromotionAction::FixedOff(amount) if amount.currency == cart.currency => *amount,
+            PromotionAction::FixedOff(_) => zero,
+            PromotionAction::BuyXGetY {
+                buy,
+                get,
+                percent_off,
+                target,
+            } => {
+                if *buy < 1 || *get < 1 {
+                    return zero;
+                }
+                let units = unit_prices(cart, target, context);
+                let group = buy.saturating_add(*get);
+                let total = unit_count(&units);
+                // Only complete groups earn the discount.
+                let eligible = total - total % group;
+                // Discounted positions in `0..n`: the last `get` of every group.
+                let discounted_before = |n: i64| (n / group) * get + (n % group - buy).max(0);
+                let mut start = 0i64;
+                let mut discounted = 0i64;
+                for (price, qty) in units {
+                    let end = start.saturating_add(qty).min(eligible);
+                    if start >= end {
+                        break;
+                    }
+                    let count = discounted_before(end) - discounted_before(start);
+                    discounted = discounted.saturating_add(price.saturating_mul(count));
+                    start = end;
+                }
+                Money::new(discounted, cart.currency).percentage(percent_off.clamp(0.0, 100.0))
+            }
+            PromotionAction::CheapestItemFree { target } => {
+                let units = unit_prices(cart, target, context);
+                let total = unit_count(&units);
+                match units.last() {
+                    Some((cheapest, _)) if total >= 2 => Money::new(*cheapest, cart.currency),
+                    _ => zero,
+                }
+            }
+            PromotionAction::FreeShipping => zero,
+        }
+    }
+}
+
+/// `(unit_price, quantity)` of the targeted lines, most expensive first.
+fn unit_prices(
+    cart: &Cart,
+    target: &PromotionTarget,
+    context: &PromotionContext,
+) -> Vec<(i64, i64)> {
+    let mut units: Vec<(i64, i64)> = cart
+        .items
+        .iter()
+        .filter(|i| target.includes(i, context) && i.quantity > 0)
+        .map(|i| (i.unit_price.amount_cents, i.quantity))
+        .collect();
+    units.sort_unstable_by_key(|(price, _)| std::cmp::Reverse(*price));
+    units
+}
+
+/// Total units across `(unit_price, quantity)` pairs.
+fn unit_count(units: &[(i64, i64)]) -> i64 {
+    units
+        .iter()
+        .fold(0, |total, (_, qty)| total.saturating_add(*qty))
+}
+
+/// How a promotion combines with others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum Stacking {
+    /// Applies alongside other promotions.
+    #[default]
+    Stackable,
+    /// Applies only if nothing else has, and blocks everything after it.
+    Exclusive,
+    /// Applies alongside earlier promotions, then stops evaluation.
+    StopFurther,
+}
+
+/// A rule-based promotion.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Promotion {
+    /// Unique identifier (shared with coupon discounts).
+    pub id: DiscountId,
+    /// Display name.
+    pub name: String,
+    /// Code the customer must enter (None = automatic).
+    pub code: Option<String>,
+    /// When the promotion applies.
+    pub condition: PromotionCondition,
+    /// What the promotion does.
+    pub action: PromotionAction,
+    /// Evaluation order (lower first).
+    pub priority: i32,
+    /// How the promotion combines with others.
+    pub stacking: Stacking,
+    /// Start date (Unix timestamp).
+    pub starts_at: Option<i64>,
+    /// End date (Unix timestamp).
+    pub ends_at: Option<i64>,
+    /// Whether the promotion is active.
+    pub active: bool,
+}
+
+impl Promotion {
+    /// Create an automatic promotion that always applies.
+    pub fn new(name: impl Into<String>, action: PromotionAction) -> Self {
+        Self {
+            id: DiscountId::generate(),
+            name: name.into(),
+            code: None,
+            condition: PromotionCondition::Always,
+            action,
+            priority: 0,
+            stacking: Stacking::Stackable,
+            starts_at: None,
+            ends_at: None,
+            active: true,
+        }
+    }
+
+    /// Require a code.
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Add a condition (combined with existing conditions).
+    pub fn when(mut self, condition: PromotionCondition) -> Self {
+        self.condition = self.condition.and(condition);
+        self
+    }
+
+    /// Set the priority.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set the stacking rule.
+    pub fn with_stacking(mut self, stacking: Stacking) -> Self {
+        self.stacking = stacking;
+        self
+    }
+
+    /// Limit the promotion to a date window.
+    pub fn scheduled(mut self, starts_at: Option<i64>, ends_at: Option<i64>) -> Self {
+        self.starts_at = starts_at;
+        self.ends_at = ends_at;
+        self
+    }
+
+    /// Check if the promotion is eligible to run (active, in window, code entered).
+    pub fn is_eligible(&self, context: &PromotionContext) -> bool {
+        if !self.active {
+            return false;
+        }
+        if self.starts_at.map(|s| context.now < s).unwrap_or(false)
+            || self.ends_at.map(|e| context.now > e).unwrap_or(false)
+        {
+            return false;
+        }
+        match &self.code {
+            Some(code) => context.codes.iter().any(|c| c.eq_ignore_ascii_case(code)),
+            None => true,
+        }
+    }
+
+    fn applied(&self, amount: Money) -> AppliedDiscount {
+        AppliedDiscount {
+            discount_id: self.id.clone(),
+            code: self.code.clone().unwrap_or_else(|| self.id.to_string()),
+            description: self.name.clone(),
+            amount,
+        }
+    }
+}
+
+/// Information about the customer and catalog needed to evaluate promotions.
+#[derive(Debug, Clone, Default)]
+pub struct PromotionContext {
+    /// Groups the customer belongs to (e.g., "vip").
+    pub customer_groups: Vec<String>,
+    /// Codes the customer entered.
+    pub codes: Vec<String>,
+    /// Categories of the products in the cart.
+    pub product_categories: HashMap<ProductId, Vec<CategoryId>>,
+    /// Evaluation time (Unix timestamp).
+    pub now: i64,
+}
+
+impl PromotionContext {
+    /// Create a context at the given time.
+    pub fn new(now: i64) -> Self {
+        Self {
+            now,
+            ..Self::default()
+        }
+    }
+
+    /// Add a customer group.
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.customer_groups.push(group.into());
+        self
+    }
+
+    /// Add an entered code.
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.codes.push(code.into());
+        self
+    }
+
+    /// Record a product's categories.
+    pub fn with_categories(mut self, product_id: ProductId, categories: Vec<CategoryId>) -> Self {
+        self.product_categories.insert(product_id, categories);
+        self
+    }
+
+    fn in_category(&self, product_id: &ProductId, category_id: &CategoryId) -> bool {
+        self.product_categories
+            .get(product_id)
+            .map(|cats| cats.contains(category_id))
+            .unwrap_or(false)
+    }
+}
+
+/// The outcome of evaluating promotions against a cart.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PromotionResult {
+    /// Discounts to apply, in evaluation order.
+    pub discounts: Vec<AppliedDiscount>,
+    /// Whether a promotion granted free shipping.
+    pub free_shipping: bool,
+}
+
+/// Evaluates promotions against carts.
+#[derive(Debug, Clone, Default)]
+pub struct PromotionEngine {
+    /// Known promotions.
+    pub promotions: Vec<Promotion>,
+}
+
+impl PromotionEngine {
+    /// Create an engine with no promotions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a promotion.
+    pub fn with_promotion(mut self, promotion: Promotion) -> Self {
+        self.promotions.push(promotion);
+        self
+    }
+
+    /// Evaluate all promotions against a cart.
+    ///
+    /// The total discount never exceeds the cart subtotal; a promotion that
+    /// would go past it is reduced to what remains.
+    pub fn evaluate(
+        &self,
+        cart: &Cart,
+        context: &PromotionContext,
+    ) -> Result<PromotionResult, CommerceError> {
+        let subtotal = Money::try_sum(cart.items.iter().map(|i| &i.total_price), cart.currency)
+            .ok_or(CommerceError::Overflow)?;
+        let mut remaining = subtotal.amount_cents;
+
+        let mut candidates: Vec<&Promotion> = self
+            .promotions
+            .iter()
+            .filter(|p| p.is_eligible(context))
+            .collect();
+        candidates.sort_by_key(|p| p.priority);
+
+        let mut result = PromotionResult::default();
+        for promotion in candidates {
+            if !promotion.condition.evaluate(cart, context) {
+                continue;
+            }
+            let already_applied = !result.discounts.is_empty() || result.free_shipping;
+            if promotion.stacking == Stacking::Exclusive && already_applied {
+                continue;
+            }
+
+            let amount = promotion.action.amount(cart, context);
+            let capped = amount.amount_cents.clamp(0, remaining);
+            let is_free_shipping = promotion.action == PromotionAction::FreeShipping;
+            if capped == 0 && !is_free_shipping {
+                continue;
+            }
+            remaining -= capped;
+            result.free_shipping |= is_free_shipping;
+            result
+                .discounts
+                .push(promotion.applied(Money::new(capped, cart.currency)));
+
+            if promotion.stacking != Stacking::Stackable {
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Evaluate promotions and apply the resulting discounts to the cart.
+    pub fn apply(
+        &self,
+        cart: &mut Cart,
+        context: &PromotionContext,
+    ) -> Result<PromotionResult, CommerceError> {
+        let result = self.evaluate(cart, context)?;
+        let promotion_ids: Vec<&DiscountId> = self.promotions.iter().map(|p| &p.id).collect();
+        cart.discounts
+            .retain(|d| !promotion_ids.contains(&&d.discount_id));
+        for discount in &result.discounts {
+            cart.apply_discount(discount.clone());
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkout::fixtures::usd;
+    use crate::ids::VariantId;
+
+    fn cart(items: &[(&str, i64, i64)]) -> Cart {
+        let mut cart = Cart::new("session");
+        for (product, price, quantity) in items {
+            cart.add_item(
+                VariantId::new(format!("v-{}", product)),
+                ProductId::new(*product),
+                product.to_string(),
+                *quantity,
+                usd(*price),
+            )
+            .unwrap();
+        }
+        cart
+    }
+
+    #[test]
+    fn test_condition_dsl() {
+        let cart = cart(&[("shirt", 3000, 2)]);
+        let context = PromotionContext::new(0)
+            .with_group("vip")
+            .with_categories(ProductId::new("shirt"), vec![CategoryId::new("apparel")]);
+
+        let condition = PromotionCondition::SubtotalAtLeast(usd(5000))
+            .and(PromotionCondition::ContainsCategory(CategoryId::new(
+                "apparel",
+            )))
+            .and(PromotionCondition::CustomerGroup("VIP".into()));
+        assert!(condition.evaluate(&cart, &context));
+        assert!(!PromotionCondition::Not(Box::new(condition)).evaluate(&cart, &context));
+        assert!(!PromotionCondition::SubtotalAtLeast(usd(6001)).evaluate(&cart, &context));
+    }
+
+    #[test]
+    fn test_bogo_and_cheapest_free() {
+        let cart = cart(&[("a", 1000, 3), ("b", 400, 1)]);
+        let context = PromotionContext::new(0);
+
+        let bogo = PromotionAction::BuyXGetY {
+            buy: 1,
+            get: 1,
+            percent_off: 100.0,
+            target: PromotionTarget::Cart,
+        };
+        // Units: 1000, 1000, 1000, 400 -> the second unit of each pair is free.
+        assert_eq!(bogo.amount(&cart, &context), usd(1400));
+
+        let cheapest = PromotionAction::CheapestItemFree {
+            target: PromotionTarget::Products(vec![ProductId::new("a")]),
+        };
+        assert_eq!(cheapest.amount(&cart, &context), usd(1000));
+    }
+
+    #[test]
+    fn test_bogo_with_huge_quantity() {
+        // Quantities past the cart limit can still arrive via deserialized carts.
+        let mut cart = cart(&[("a", 2, 1), ("b", 1, 2)]);
+        cart.items[0].quantity = 1_000_000_001;
+        let context = PromotionContext::new(0);
+
+        let buy_two_get_one = PromotionAction::BuyXGetY {
+            buy: 2,
+            get: 1,
+            percent_off: 100.0,
+            target: PromotionTarget::Cart,
+        };
+        // 1_000_000_003 units -> 333_333_334 groups; the last group is (2, 1, 1).
+        assert_eq!(
+            buy_two_get_one.amount(&cart, &context),
+            usd(333_333_333 * 2 + 1)
+        );
+    }
+
+    #[test]
+    fn test_stacking_and_priority() {
+        let cart = cart(&[("a", 10000, 1)]);
+        let ten_off = Promotion::new(
+            "10% off",
+            PromotionAction::PercentageOff {
+                percent: 10.0,
+                target: PromotionTarget::Cart,
+            },
+        )
+        .with_priority(2);
+        let shipping = Promotion::new("Free shipping", PromotionAction::FreeShipping)
+            .when(PromotionCondition::SubtotalAtLeast(usd(5000)))
+            .with_priority(1);
+        let vip = Promotion::new("VIP $30 off", PromotionAction::FixedOff(usd(3000)))
+            .when(PromotionCondition::CustomerGroup("vip".into()))
+            .with_stacking(Stacking::Exclusive);
+
+        let engine = PromotionEngine::new()
+            .with_promotion(ten_off)
+            .with_promotion(shipping)
+            .with_promotion(vip);
+
+        let regular = engine.evaluate(&cart, &PromotionContext::new(0)).unwrap();
+        assert!(regular.free_shipping);
+        assert_eq!(regular.discounts.len(), 2);
+        assert_eq!(regular.discounts[1].amount, usd(1000));
+
+        let vip_result = engine
+            .evaluate(&cart, &PromotionContext::new(0).with_group("vip"))
+            .unwrap();
+        assert!(!vip_result.free_shipping);
+        assert_eq!(vip_result.discounts.len(), 1);
+        assert_eq!(vip_result.discounts[0].amount, usd(3000));
+    }
+
+    #[test]
+    fn test_code_required_and_apply() {
+        let mut cart = cart(&[("a", 2000, 1)]);
+        let engine = PromotionEngine::new().with_promotion(
+            Promotion::new("Big discount", PromotionAction::FixedOff(usd(5000))).with_code("BIG"),
+        );
+
+        let without = engine.apply(&mut cart, &PromotionContext::new(0)).unwrap();
+        assert!(without.discounts.is_empty());
+
+        engine
+            .apply(&mut cart, &PromotionContext::new(0).with_code("big"))
+            .unwrap();
+        // Capped at the subtotal.
+        assert_eq!(cart.calculate_pricing().unwrap().grand_total, usd(0));
+
+        engine.apply(&mut cart, &PromotionContext::new(0)).unwrap();
+        assert!(cart.discounts.is_empty());
+    }
+}
@@ -0,0 +1,369 @@
+//! Typed product attributes.
+//!
+//! An [`AttributeSchema`] describes the attributes a kind of product carries
+//! (e.g., "Apparel" has a material and a fit; "Furniture" has dimensions).
+//! Values are validated against the schema so a bad catalog import fails
+//! instead of producing facets nobody can filter on.
+
+use crate::error::CommerceError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Attribute values keyed by attribute key.
+pub type Attributes = BTreeMap<String, AttributeValue>;
+
+/// Unit for dimension attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum DimensionUnit {
+    /// Millimeters.
+    Mm,
+    /// Centimeters.
+    #[default]
+    Cm,
+    /// Meters.
+    M,
+    /// Inches.
+    In,
+}
+
+impl DimensionUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DimensionUnit::Mm => "mm",
+            DimensionUnit::Cm => "cm",
+            DimensionUnit::M => "m",
+            DimensionUnit::In => "in",
+        }
+    }
+}
+
+/// The type of an attribute and its constraints.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AttributeKind {
+    /// Free text, optionally length-limited.
+    Text { max_length: Option<usize> },
+    /// A number, optionally bounded.
+    Number { min: Option<f64>, max: Option<f64> },
+    /// One of a fixed set of values.
+    Enum(Vec<String>),
+    /// True or false.
+    Boolean,
+    /// Length x width x height in a fixed unit.
+    Dimensions(DimensionUnit),
+}
+
+impl AttributeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributeKind::Text { .. } => "text",
+            AttributeKind::Number { .. } => "number",
+            AttributeKind::Enum(_) => "enum",
+            AttributeKind::Boolean => "boolean",
+            AttributeKind::Dimensions(_) => "dimensions",
+        }
+    }
+}
+
+/// A typed attribute value.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AttributeValue {
+    /// Text value (also used for enum values).
+    Text(String),
+    /// Numeric value.
+    Number(f64),
+    /// Boolean value.
+    Boolean(bool),
+    /// Dimensions.
+    Dimensions {
+        length: f64,
+        width: f64,
+        height: f64,
+        unit: DimensionUnit,
+    },
+}
+
+impl AttributeValue {
+    /// Create a text value.
+    pub fn text(value: impl Into<String>) -> Self {
+        AttributeValue::Text(value.into())
+    }
+
+    /// Get the value as a facet string (e.g., "Cotton", "42", "10x20x5 cm").
+    pub fn facet_value(&self) -> String {
+        match self {
+            AttributeValue::Text(s) => s.clone(),
+            AttributeValue::Number(n) => n.to_string(),
+            AttributeValue::Boolean(b) => b.to_string(),
+            AttributeValue::Dimensions {
+                length,
+                width,
+                height,
+                unit,
+            } => format!("{}x{}x{} {}", length, width, height, unit.as_str()),
+        }
+    }
+}
+
+/// Definition of one attribute within a schema.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AttributeDefinition {
+    /// Machine key (e.g., "material").
+    pub key: String,
+    /// Display label (e.g., "Material").
+    pub label: String,
+    /// Type and constraints.
+    pub kind: AttributeKind,
+    /// Whether every product must set this attribute.
+    pub required: bool,
+    /// Whether this attribute is exposed as a search facet.
+    pub facetable: bool,
+}
+
+impl AttributeDefinition {
+    /// Create an optional, non-facetable attribute.
+    pub fn new(key: impl Into<String>, label: impl Into<String>, kind: AttributeKind) -> Self {
+        Self {
+            key: key.into(),
+            label: label.into(),
+            kind,
+            required: false,
+            facetable: false,
+        }
+    }
+
+    /// Mark the attribute as required.
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Mark the attribute as a search facet.
+    pub fn facetable(mut self) -> Self {
+        self.facetable = true;
+        self
+    }
+
+    /// Check a value against this definition.
+    pub fn check(&self, value: &AttributeValue) -> Result<(), CommerceError> {
+        let fail = |reason: String| {
+            Err(CommerceError::InvalidAttribute {
+                key: self.key.clone(),
+                reason,
+            })
+        };
+        match (&self.kind, value) {
+            (AttributeKind::Text { max_length }, AttributeValue::Text(s)) => match max_length {
+                Some(max) if s.chars().count() > *max => {
+                    fail(format!("longer than {} characters", max))
+                }
+                _ => Ok(()),
+            },
+            (AttributeKind::Number { min, max }, AttributeValue::Number(n)) => {
+                if !n.is_finite() {
+                    return fail("not a finite number".to_string());
+                }
+                if let Some(min) = min {
+                    if n < min {
+                        return fail(format!("{} is below minimum {}", n, min));
+                    }
+                }
+                if let Some(max) = max {
+                    if n > max {
+                        return fail(format!("{} is above maximum {}", n, max));
+                    }
+                }
+                Ok(())
+            }
+            (AttributeKind::Enum(allowed), AttributeValue::Text(s)) => {
+                if allowed.iter().any(|a| a.eq_ignore_ascii_case(s)) {
+                    Ok(())
+                } else {
+                    fail(format!("'{}' is not one of {}", s, allowed.join(", ")))
+                }
+            }
+            (AttributeKind::Boolean, AttributeValue::Boolean(_)) => Ok(()),
+            (
+                AttributeKind::Dimensions(expected),
+                AttributeValue::Dimensions {
+                    length,
+                    width,
+                    height,
+                    unit,
+                },
+            ) => {
+                if unit != expected {
+                    return fail(format!(
+                        "expected {} but got {}",
+                        expected.as_str(),
+                        unit.as_str()
+                    ));
+                }
+                if [length, width, height]
+                    .iter()
+                    .any(|d| !d.is_finite() || **d <= 0.0)
+                {
+                    return fail("dimensions must be positive".to_string());
+                }
+                Ok(())
+            }
+            (kind, _) => fail(format!("expected a {} value", kind.as_str())),
+        }
+    }
+}
+
+/// The set of attributes for a kind of product (e.g., "Apparel").
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AttributeSchema {
+    /// Schema name, usually the product type (e.g., "Apparel").
+    pub name: String,
+    /// Attribute definitions, in display order.
+    pub attributes: Vec<AttributeDefinition>,
+}
+
+impl AttributeSchema {
+    /// Create an empty schema.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Add an attribute definition.
+    pub fn with_attribute(mut self, definition: AttributeDefinition) -> Self {
+        self.attributes.push(definition);
+        self
+    }
+
+    /// Get a definition by key.
+    pub fn attribute(&self, key: &str) -> Option<&AttributeDefinition> {
+        self.attributes.iter().find(|a| a.key == key)
+    }
+
+    /// Keys of attributes exposed as search facets.
+    pub fn facet_keys(&self) -> impl Iterator<Item = &str> {
+        self.attributes
+            .iter()
+            .filter(|a| a.facetable)
+            .map(|a| a.key.as_str())
+    }
+
+    /// Check a single value against the schema.
+    pub fn check(&self, key: &str, value: &AttributeValue) -> Result<(), CommerceError> {
+        match self.attribute(key) {
+            Some(definition) => definition.check(value),
+            None => Err(CommerceError::InvalidAttribute {
+                key: key.to_string(),
+                reason: format!("not defined in schema '{}'", self.name),
+            }),
+        }
+    }
+
+    /// Collect every problem with a set of values.
+    ///
+    /// Reports unknown keys, missing required attributes, and invalid
+    /// values, so an import can show all errors for a row at once.
+    pub fn errors(&self, values: &Attributes) -> Vec<CommerceError> {
+        let mut errors: Vec<CommerceError> = values
+            .iter()
+            .filter_map(|(key, value)| self.check(key, value).err())
+            .collect();
+        for definition in self.attributes.iter().filter(|a| a.required) {
+            if !values.contains_key(&definition.key) {
+                errors.push(CommerceError::InvalidAttribute {
+                    key: definition.key.clone(),
+                    reason: "required".to_string(),
+                });
+            }
+        }
+        errors
+    }
+
+    /// Validate a set of values, returning the first error.
+    pub fn validate(&self, values: &Attributes) -> Result<(), CommerceError> {
+        match self.errors(values).into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apparel() -> AttributeSchema {
+        AttributeSchema::new("Apparel")
+            .with_attribute(
+                AttributeDefinition::new(
+                    "material",
+                    "Material",
+                    AttributeKind::Enum(vec!["Cotton".into(), "Wool".into()]),
+                )
+                .required()
+                .facetable(),
+            )
+            .with_attribute(AttributeDefinition::new(
+                "weight",
+                "Weight (g)",
+                AttributeKind::Number {
+                    min: Some(0.0),
+                    max: None,
+                },
+            ))
+            .with_attribute(AttributeDefinition::new(
+                "box",
+                "Box",
+                AttributeKind::Dimensions(DimensionUnit::Cm),
+            ))
+    }
+
+    #[test]
+    fn test_valid_attributes() {
+        let mut values = Attributes::new();
+        values.insert("material".into(), AttributeValue::text("cotton"));
+        values.insert("weight".into(), AttributeValue::Number(180.0));
+        assert!(apparel().validate(&values).is_ok());
+        assert_eq!(apparel().facet_keys().collect::<Vec<_>>(), vec!["material"]);
+    }
+
+    #[test]
+    fn test_reports_all_errors() {
+        let mut values = Attributes::new();
+        values.insert("weight".into(), AttributeValue::text("heavy"));
+        values.insert("colour".into(), AttributeValue::text("red"));
+        values.insert(
+            "box".into(),
+            AttributeValue::Dimensions {
+                length: 10.0,
+                width: 5.0,
+                height: 2.0,
+                unit: DimensionUnit::In,
+            },
+        );
+
+        let errors = apparel().errors(&values);
+        let keys: Vec<String> = errors
+            .iter()
+            .map(|e| match e {
+                CommerceError::InvalidAttribute { key, .. } => key.clone(),
+                other => panic!("unexpected error: {}", other),
+            })
+            .collect();
+        assert_eq!(keys, vec!["box", "colour", "weight", "material"]);
+    }
+
+    #[test]
+    fn test_enum_and_bounds() {
+        let schema = apparel();
+        assert!(schema
+            .check("material", &AttributeValue::text("Silk"))
+            .is_err());
+        assert!(schema
+            .check("weight", &AttributeValue::Number(-1.0))
+            .is_err());
+        assert!(schema
+            .check("weight", &AttributeValue::Number(f64::NAN))
+            .is_err());
+    }
+}
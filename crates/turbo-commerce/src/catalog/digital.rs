@@ -0,0 +1,446 @@
+//! Digital products: downloadable assets, license keys, and entitlements.
+//!
+//! Downloads are served through signed, expiring URLs so a link shared
+//! outside the order stops working once it expires.
+
+use crate::error::CommerceError;
+use crate::ids::{DigitalAssetId, EntitlementId, OrderId, OrderLineItemId, ProductId, VariantId};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// Default lifetime of a signed download URL (15 minutes).
+pub const DEFAULT_LINK_TTL_SECS: i64 = 15 * 60;
+
+/// A downloadable file attached to a digital product.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DigitalAsset {
+    /// Unique asset identifier.
+    pub id: DigitalAssetId,
+    /// Product this asset belongs to.
+    pub product_id: ProductId,
+    /// Restrict the asset to one variant (None = all variants).
+    pub variant_id: Option<VariantId>,
+    /// File name shown to the customer.
+    pub file_name: String,
+    /// Key of the file in object storage.
+    pub storage_key: String,
+    /// File size in bytes.
+    pub size_bytes: i64,
+    /// Maximum downloads per purchase (None = unlimited).
+    pub download_limit: Option<i64>,
+    /// Days the customer can download after purchase (None = forever).
+    pub access_days: Option<i64>,
+}
+
+impl DigitalAsset {
+    /// Create a new asset for a product.
+    pub fn new(
+        product_id: ProductId,
+        file_name: impl Into<String>,
+        storage_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: DigitalAssetId::generate(),
+            product_id,
+            variant_id: None,
+            file_name: file_name.into(),
+            storage_key: storage_key.into(),
+            size_bytes: 0,
+            download_limit: None,
+            access_days: None,
+        }
+    }
+
+    /// Restrict the asset to a variant.
+    pub fn with_variant(mut self, variant_id: VariantId) -> Self {
+        self.variant_id = Some(variant_id);
+        self
+    }
+
+    /// Set the file size.
+    pub fn with_size(mut self, size_bytes: i64) -> Self {
+        self.size_bytes = size_bytes;
+        self
+    }
+
+    /// Set the download limit.
+    pub fn with_download_limit(mut self, limit: i64) -> Self {
+        self.download_limit = Some(limit);
+        self
+    }
+
+    /// Set how many days the asset stays downloadable.
+    pub fn with_access_days(mut self, days: i64) -> Self {
+        self.access_days = Some(days);
+        self
+    }
+
+    /// Check if this asset is delivered for a purchased variant.
+    pub fn applies_to(&self, product_id: &ProductId, variant_id: &VariantId) -> bool {
+        &self.product_id == product_id
+            && self
+                .variant_id
+                .as_ref()
+                .map(|v| v == variant_id)
+                .unwrap_or(true)
+    }
+}
+
+/// A license key in a pool.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LicenseKey {
+    /// The key as given to the customer.
+    pub key: String,
+    /// Order the key was issued to.
+    pub order_id: Option<OrderId>,
+    /// Unix timestamp when issued.
+    pub issued_at: Option<i64>,
+}
+
+impl LicenseKey {
+    /// Check if the key has not been issued.
+    pub fn is_available(&self) -> bool {
+        self.order_id.is_none()
+    }
+}
+
+/// Pre-loaded license keys for a variant.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LicenseKeyPool {
+    /// Variant the keys are sold with.
+    pub variant_id: VariantId,
+    /// Keys in the pool, issued in order.
+    pub keys: Vec<LicenseKey>,
+}
+
+impl LicenseKeyPool {
+    /// Create an empty pool.
+    pub fn new(variant_id: VariantId) -> Self {
+        Self {
+            variant_id,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Add keys, skipping blanks and duplicates. Returns the number added.
+    pub fn add_keys<K: Into<String>>(&mut self, keys: impl IntoIterator<Item = K>) -> usize {
+        let mut added = 0;
+        for key in keys {
+            let key = key.into().trim().to_string();
+            if key.is_empty() || self.keys.iter().any(|k| k.key == key) {
+                continue;
+            }
+            self.keys.push(LicenseKey {
+                key,
+                order_id: None,
+                issued_at: None,
+            });
+            added += 1;
+        }
+        added
+    }
+
+    /// Number of keys not yet issued.
+    pub fn available(&self) -> i64 {
+        self.keys.iter().filter(|k| k.is_available()).count() as i64
+    }
+
+    /// Keys already issued to an order.
+    pub fn issued_to<'a>(&'a self, order_id: &'a OrderId) -> impl Iterator<Item = &'a str> + 'a {
+        self.keys
+            .iter()
+            .filter(move |k| k.order_id.as_ref() == Some(order_id))
+            .map(|k| k.key.as_str())
+    }
+
+    /// Issue keys to an order.
+    ///
+    /// Either all requested keys are issued or none are.
+    pub fn allocate(
+        &mut self,
+        order_id: &OrderId,
+        quantity: i64,
+        now: i64,
+    ) -> Result<Vec<String>, CommerceError> {
+        if quantity <= 0 {
+            return Err(CommerceError::InvalidQuantity(quantity));
+        }
+        let available = self.available();
+        if available < quantity {
+            return Err(CommerceError::InsufficientInventory {
+                product_id: self.variant_id.to_string(),
+                requested: quantity,
+                available,
+            });
+        }
+        Ok(self
+            .keys
+            .iter_mut()
+            .filter(|k| k.is_available())
+            .take(quantity as usize)
+            .map(|k| {
+                k.order_id = Some(order_id.clone());
+                k.issued_at = Some(now);
+                k.key.clone()
+            })
+            .collect())
+    }
+
+    /// Return an order's keys to the pool (e.g., after a refund).
+    pub fn release(&mut self, order_id: &OrderId) -> usize {
+        let mut released = 0;
+        for key in self
+            .keys
+            .iter_mut()
+            .filter(|k| k.order_id.as_ref() == Some(order_id))
+        {
+            key.order_id = None;
+            key.issued_at = None;
+            released += 1;
+        }
+        released
+    }
+}
+
+/// A customer's right to download an asset they bought.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DownloadEntitlement {
+    /// Unique entitlement identifier.
+    pub id: EntitlementId,
+    /// Order that granted the entitlement.
+    pub order_id: OrderId,
+    /// Order line the entitlement belongs to.
+    pub line_item_id: OrderLineItemId,
+    /// Asset that can be downloaded.
+    pub asset_id: DigitalAssetId,
+    /// Downloads so far.
+    pub download_count: i64,
+    /// Maximum downloads (None = unlimited).
+    pub download_limit: Option<i64>,
+    /// Unix timestamp after which downloads are refused.
+    pub expires_at: Option<i64>,
+    /// Whether the entitlement was revoked (e.g., refund).
+    pub revoked: bool,
+    /// Unix timestamp of creation.
+    pub created_at: i64,
+}
+
+impl DownloadEntitlement {
+    /// Grant an entitlement for an asset.
+    pub fn grant(
+        order_id: OrderId,
+        line_item_id: OrderLineItemId,
+        asset: &DigitalAsset,
+        now: i64,
+    ) -> Self {
+        Self {
+            id: EntitlementId::generate(),
+            order_id,
+            line_item_id,
+            asset_id: asset.id.clone(),
+            download_count: 0,
+            download_limit: asset.download_limit,
+            expires_at: asset.access_days.map(|days| now + days * 86_400),
+            revoked: false,
+            created_at: now,
+        }
+    }
+
+    /// Downloads left (None = unlimited).
+    pub fn remaining_downloads(&self) -> Option<i64> {
+        self.download_limit
+            .map(|limit| (limit - self.download_count).max(0))
+    }
+
+    /// Check that a download is allowed at the given time.
+    pub fn check(&self, now: i64) -> Result<(), CommerceError> {
+        if self.revoked {
+            return Err(CommerceError::DownloadDenied("entitlement revoked".into()));
+        }
+        if self.expires_at.map(|e| now >= e).unwrap_or(false) {
+            return Err(CommerceError::DownloadDenied("access expired".into()));
+        }
+        if self.remaining_downloads() == Some(0) {
+            return Err(CommerceError::DownloadDenied(
+                "download limit reached".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Record a download, failing if it isn't allowed.
+    pub fn record_download(&mut self, now: i64) -> Result<(), CommerceError> {
+        self.check(now)?;
+        self.download_count += 1;
+        Ok(())
+    }
+
+    /// Revoke the entitlement.
+    pub fn revoke(&mut self) {
+        self.revoked = true;
+    }
+}
+
+/// Signs and verifies expiring download URLs with HMAC-SHA256.
+#[derive(Clone)]
+pub struct DownloadSigner {
+    secret: Vec<u8>,
+    ttl_secs: i64,
+}
+
+impl std::fmt::Debug for DownloadSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DownloadSigner")
+            .field("ttl_secs", &self.ttl_secs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DownloadSigner {
+    /// Create a signer with the default link lifetime.
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+            ttl_secs: DEFAULT_LINK_TTL_SECS,
+        }
+    }
+
+    /// Set the link lifetime.
+    pub fn with_ttl(mut self, ttl_secs: i64) -> Self {
+        self.ttl_secs = ttl_secs;
+        self
+    }
+
+    /// Build a signed URL for an entitlement.
+    ///
+    /// The link never outlives the entitlement itself.
+    pub fn sign_url(
+        &self,
+        base_url: &str,
+        entitlement: &DownloadEntitlement,
+        now: i64,
+    ) -> Result<String, CommerceError> {
+        entitlement.check(now)?;
+        let mut expires = now + self.ttl_secs;
+        if let Some(limit) = entitlement.expires_at {
+            expires = expires.min(limit);
+        }
+        Ok(format!(
+            "{}/downloads/{}?expires={}&signature={}",
+            base_url.trim_end_matches('/'),
+            entitlement.id,
+            expires,
+            to_hex(&self.signature(&entitlement.id, expires))
+        ))
+    }
+
+    /// Verify the parameters of a signed URL.
+    pub fn verify(
+        &self,
+        entitlement_id: &EntitlementId,
+        expires: i64,
+        signature: &str,
+        now: i64,
+    ) -> Result<(), CommerceError> {
+        if now >= expires {
+            return Err(CommerceError::DownloadDenied("link expired".into()));
+        }
+        let provided = from_hex(signature)
+            .ok_or_else(|| CommerceError::DownloadDenied("invalid signature".into()))?;
+        self.mac(entitlement_id, expires)
+            .verify_slice(&provided)
+            .map_err(|_| CommerceError::DownloadDenied("invalid signature".into()))
+    }
+
+    fn mac(&self, entitlement_id: &EntitlementId, expires: i64) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(entitlement_id.as_str().as_bytes());
+        mac.update(b":");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+
+    fn signature(&self, entitlement_id: &EntitlementId, expires: i64) -> Vec<u8> {
+        self.mac(entitlement_id, expires)
+            .finalize()
+            .into_bytes()
+            .to_vec()
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok().filter(|p| p.len() == 2)?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entitlement(asset: &DigitalAsset) -> DownloadEntitlement {
+        DownloadEntitlement::grant(OrderId::new("o1"), OrderLineItemId::new("l1"), asset, 1_000)
+    }
+
+    #[test]
+    fn test_license_pool_allocation() {
+        let mut pool = LicenseKeyPool::new(VariantId::new("v1"));
+        assert_eq!(pool.add_keys(["AAA", "BBB", " ", "AAA", "CCC"]), 3);
+
+        let order = OrderId::new("o1");
+        assert!(pool.allocate(&order, 4, 0).is_err());
+        assert_eq!(pool.available(), 3);
+
+        assert_eq!(pool.allocate(&order, 2, 0).unwrap(), vec!["AAA", "BBB"]);
+        assert_eq!(pool.available(), 1);
+        assert_eq!(pool.release(&order), 2);
+        assert_eq!(pool.available(), 3);
+    }
+
+    #[test]
+    fn test_entitlement_limits() {
+        let asset = DigitalAsset::new(ProductId::new("p1"), "book.pdf", "assets/book.pdf")
+            .with_download_limit(1)
+            .with_access_days(1);
+        let mut ent = entitlement(&asset);
+        assert_eq!(ent.expires_at, Some(1_000 + 86_400));
+
+        ent.record_download(2_000).unwrap();
+        assert!(ent.record_download(2_001).is_err());
+
+        let mut fresh = entitlement(&asset);
+        assert!(fresh.check(1_000 + 86_400).is_err());
+        fresh.revoke();
+        assert!(fresh.check(2_000).is_err());
+    }
+
+    #[test]
+    fn test_signed_url_roundtrip() {
+        let asset = DigitalAsset::new(ProductId::new("p1"), "book.pdf", "assets/book.pdf");
+        let ent = entitlement(&asset);
+        let signer = DownloadSigner::new("secret").with_ttl(60);
+
+        let url = signer
+            .sign_url("https://shop.example/", &ent, 1_000)
+            .unwrap();
+        assert!(url.starts_with(&format!("https://shop.example/downloads/{}?", ent.id)));
+        let signature = url.rsplit("signature=").next().unwrap();
+
+        assert!(signer.verify(&ent.id, 1_060, signature, 1_030).is_ok());
+        assert!(signer.verify(&ent.id, 1_060, signature, 1_060).is_err());
+        assert!(signer.verify(&ent.id, 9_999, signature, 1_030).is_err());
+        assert!(DownloadSigner::new("other")
+            .verify(&ent.id, 1_060, signature, 1_030)
+            .is_err());
+    }
+}
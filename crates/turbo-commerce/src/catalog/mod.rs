@@ -1,13 +1,32 @@
 //! Product catalog module.
 //!
-//! Contains types for products, variants, categories, and inventory.
+//! Contains types for products, variants, typed attributes, digital assets,
+//! categories, price lists, and inventory (including multi-location stock allocation).
 
+mod attributes;
 mod category;
+mod digital;
 mod inventory;
+mod options;
+mod price_list;
 mod product;
+mod warehouse;
 
+pub use attributes::{
+    AttributeDefinition, AttributeKind, AttributeSchema, AttributeValue, Attributes, DimensionUnit,
+};
 pub use category::Category;
+pub use digital::{
+    DigitalAsset, DownloadEntitlement, DownloadSigner, LicenseKey, LicenseKeyPool,
+    DEFAULT_LINK_TTL_SECS,
+};
 pub use inventory::{AdjustmentReason, InventoryAdjustment, InventoryLevel};
+pub use options::{ProductOption, VariantOverride, MAX_VARIANTS_PER_PRODUCT};
+pub use price_list::{PriceEntry, PriceList, PriceResolver, PriceRule, ResolvedPrice};
 pub use product::{
     MediaType, Product, ProductMedia, ProductStatus, ProductType, ProductVariant, VariantOption,
 };
+pub use warehouse::{
+    AllocatedLine, AllocationStrategy, FulfillmentPlan, InventoryAllocator, LocationInventory,
+    PlannedShipment, StockLocation, VariantStock,
+};
@@ -0,0 +1,239 @@
+//! Product option sets and variant matrix generation.
+
+use crate::catalog::{InventoryLevel, VariantOption};
+use crate::error::CommerceError;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of variants a single product's option matrix may produce.
+pub const MAX_VARIANTS_PER_PRODUCT: usize = 100;
+
+/// A configurable product option (e.g., Size: S, M, L).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProductOption {
+    /// Option name (e.g., "Size", "Color").
+    pub name: String,
+    /// Allowed values, in display order.
+    pub values: Vec<String>,
+}
+
+impl ProductOption {
+    /// Create a new option with its allowed values.
+    pub fn new<V: Into<String>>(
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Check if this option has the given name (case-insensitive).
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Check if a value is allowed for this option (case-insensitive).
+    pub fn has_value(&self, value: &str) -> bool {
+        self.values.iter().any(|v| v.eq_ignore_ascii_case(value))
+    }
+
+    /// Add a value if it isn't already present.
+    pub fn add_value(&mut self, value: impl Into<String>) {
+        let value = value.into();
+        if !self.has_value(&value) {
+            self.values.push(value);
+        }
+    }
+}
+
+/// Per-variant overrides applied while generating the variant matrix.
+///
+/// An override matches every generated variant that carries all of its
+/// options, so `[("Color", "Red")]` applies to every red variant while
+/// `[("Size", "L"), ("Color", "Red")]` targets a single one. Overrides are
+/// applied in order, so later entries win.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VariantOverride {
+    /// Options the variant must have for this override to apply.
+    pub options: Vec<VariantOption>,
+    /// SKU to use instead of the generated one.
+    pub sku: Option<String>,
+    /// Price to use instead of the base price.
+    pub price: Option<Money>,
+    /// Compare-at price.
+    pub compare_at_price: Option<Money>,
+    /// Inventory level.
+    pub inventory: Option<InventoryLevel>,
+    /// Weight in grams.
+    pub weight_grams: Option<i64>,
+}
+
+impl VariantOverride {
+    /// Create an override matching the given option selection.
+    pub fn new(options: &[(&str, &str)]) -> Self {
+        Self {
+            options: options
+                .iter()
+                .map(|(name, value)| VariantOption::new(*name, *value))
+                .collect(),
+            sku: None,
+            price: None,
+            compare_at_price: None,
+            inventory: None,
+            weight_grams: None,
+        }
+    }
+
+    /// Set the SKU.
+    pub fn with_sku(mut self, sku: impl Into<String>) -> Self {
+        self.sku = Some(sku.into());
+        self
+    }
+
+    /// Set the price.
+    pub fn with_price(mut self, price: Money) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Set the compare-at price.
+    pub fn with_compare_at_price(mut self, price: Money) -> Self {
+        self.compare_at_price = Some(price);
+        self
+    }
+
+    /// Set the inventory level.
+    pub fn with_inventory(mut self, inventory: InventoryLevel) -> Self {
+        self.inventory = Some(inventory);
+        self
+    }
+
+    /// Set the weight in grams.
+    pub fn with_weight_grams(mut self, grams: i64) -> Self {
+        self.weight_grams = Some(grams);
+        self
+    }
+
+    /// Check if this override applies to a variant with the given options.
+    pub fn matches(&self, variant_options: &[VariantOption]) -> bool {
+        self.options.iter().all(|wanted| {
+            variant_options.iter().any(|o| {
+                o.name.eq_ignore_ascii_case(&wanted.name)
+                    && o.value.eq_ignore_ascii_case(&wanted.value)
+            })
+        })
+    }
+}
+
+/// Validate option sets and build their cartesian product.
+///
+/// Returns one option combination per variant, ordered with the last
+/// option varying fastest (S/Red, S/Blue, M/Red, ...).
+pub(crate) fn option_matrix(
+    options: &[ProductOption],
+) -> Result<Vec<Vec<VariantOption>>, CommerceError> {
+    let mut total: usize = 1;
+    for (i, option) in options.iter().enumerate() {
+        if option.name.trim().is_empty() {
+            return Err(CommerceError::ValidationError(
+                "option name cannot be empty".to_string(),
+            ));
+        }
+        if option.values.is_empty() {
+            return Err(CommerceError::ValidationError(format!(
+                "option '{}' has no values",
+                option.name
+            )));
+        }
+        if options[..i].iter().any(|o| o.is_named(&option.name)) {
+            return Err(CommerceError::ValidationError(format!(
+                "duplicate option '{}'",
+                option.name
+            )));
+        }
+        total = total
+            .checked_mul(option.values.len())
+            .filter(|t| *t <= MAX_VARIANTS_PER_PRODUCT)
+            .ok_or_else(|| {
+                CommerceError::ValidationError(format!(
+                    "option matrix exceeds {} variants",
+                    MAX_VARIANTS_PER_PRODUCT
+                ))
+            })?;
+    }
+
+    let mut matrix: Vec<Vec<VariantOption>> = vec![Vec::new()];
+    for option in options {
+        matrix = matrix
+            .into_iter()
+            .flat_map(|combo| {
+                option.values.iter().map(move |value| {
+                    let mut next = combo.clone();
+                    next.push(VariantOption::new(option.name.clone(), value.clone()));
+                    next
+                })
+            })
+            .collect();
+    }
+
+    if options.is_empty() {
+        matrix.clear();
+    }
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_option_matrix_order() {
+        let options = vec![
+            ProductOption::new("Size", ["S", "M"]),
+            ProductOption::new("Color", ["Red", "Blue"]),
+        ];
+        let matrix = option_matrix(&options).unwrap();
+        let names: Vec<String> = matrix
+            .iter()
+            .map(|c| {
+                c.iter()
+                    .map(|o| o.value.as_str())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect();
+        assert_eq!(names, vec!["S/Red", "S/Blue", "M/Red", "M/Blue"]);
+    }
+
+    #[test]
+    fn test_option_matrix_rejects_invalid() {
+        let empty = vec![ProductOption::new("Size", Vec::<String>::new())];
+        assert!(option_matrix(&empty).is_err());
+
+        let duplicate = vec![
+            ProductOption::new("Size", ["S"]),
+            ProductOption::new("size", ["M"]),
+        ];
+        assert!(option_matrix(&duplicate).is_err());
+
+        let values: Vec<String> = (0..11).map(|i| i.to_string()).collect();
+        let too_many = vec![
+            ProductOption::new("A", values.clone()),
+            ProductOption::new("B", values),
+        ];
+        assert!(option_matrix(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_override_partial_match() {
+        let red = VariantOverride::new(&[("color", "red")]);
+        let options = vec![
+            VariantOption::new("Size", "M"),
+            VariantOption::new("Color", "Red"),
+        ];
+        assert!(red.matches(&options));
+        assert!(!VariantOverride::new(&[("Color", "Blue")]).matches(&options));
+    }
+}
@@ -0,0 +1,438 @@
+//! Price lists and price resolution.
+//!
+//! A [`PriceList`] holds prices for a customer group (e.g., wholesale, VIP)
+//! or for everyone, optionally within a date window. Individual entries can
+//! also be scheduled, which is how sale prices are expressed. The
+//! [`PriceResolver`] picks the effective price for a variant.
+
+use crate::catalog::ProductVariant;
+use crate::error::CommerceError;
+use crate::ids::{PriceListId, VariantId};
+use crate::money::{Currency, Money};
+use serde::{Deserialize, Serialize};
+
+/// A price for one variant within a price list.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PriceEntry {
+    /// The variant this price applies to.
+    pub variant_id: VariantId,
+    /// The price.
+    pub price: Money,
+    /// Start date (Unix timestamp).
+    pub starts_at: Option<i64>,
+    /// End date (Unix timestamp).
+    pub ends_at: Option<i64>,
+}
+
+impl PriceEntry {
+    /// Create an unscheduled price entry.
+    pub fn new(variant_id: VariantId, price: Money) -> Self {
+        Self {
+            variant_id,
+            price,
+            starts_at: None,
+            ends_at: None,
+        }
+    }
+
+    /// Limit this entry to a date window.
+    pub fn scheduled(mut self, starts_at: Option<i64>, ends_at: Option<i64>) -> Self {
+        self.starts_at = starts_at;
+        self.ends_at = ends_at;
+        self
+    }
+
+    /// Check if the entry is in effect at the given time.
+    pub fn is_active_at(&self, timestamp: i64) -> bool {
+        in_window(self.starts_at, self.ends_at, timestamp)
+    }
+
+    /// Check if the entry has a date window.
+    pub fn is_scheduled(&self) -> bool {
+        self.starts_at.is_some() || self.ends_at.is_some()
+    }
+}
+
+/// A named set of prices (e.g., "Retail", "Wholesale", "VIP").
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PriceList {
+    /// Unique price list identifier.
+    pub id: PriceListId,
+    /// Display name.
+    pub name: String,
+    /// Customer group this list is for (None = everyone).
+    pub customer_group: Option<String>,
+    /// Currency of every price in this list.
+    pub currency: Currency,
+    /// Priority for tie-breaking (lower wins).
+    pub priority: i32,
+    /// Start date (Unix timestamp).
+    pub starts_at: Option<i64>,
+    /// End date (Unix timestamp).
+    pub ends_at: Option<i64>,
+    /// Whether the list is active.
+    pub active: bool,
+    /// Prices in this list.
+    pub entries: Vec<PriceEntry>,
+}
+
+impl PriceList {
+    /// Create a new price list available to everyone.
+    pub fn new(name: impl Into<String>, currency: Currency) -> Self {
+        Self {
+            id: PriceListId::generate(),
+            name: name.into(),
+            customer_group: None,
+            currency,
+            priority: 0,
+            starts_at: None,
+            ends_at: None,
+            active: true,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Restrict the list to a customer group.
+    pub fn for_group(mut self, group: impl Into<String>) -> Self {
+        self.customer_group = Some(group.into());
+        self
+    }
+
+    /// Set the priority.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Limit the whole list to a date window.
+    ///
+    /// Returns an error if the window ends before it starts.
+    pub fn scheduled(
+        mut self,
+        starts_at: Option<i64>,
+        ends_at: Option<i64>,
+    ) -> Result<Self, CommerceError> {
+        check_window(starts_at, ends_at)?;
+        self.starts_at = starts_at;
+        self.ends_at = ends_at;
+        Ok(self)
+    }
+
+    /// Add a price entry.
+    ///
+    /// Returns an error if the price is not in the list's currency or is
+    /// negative.
+    pub fn add_entry(&mut self, entry: PriceEntry) -> Result<(), CommerceError> {
+        if entry.price.currency != self.currency {
+            return Err(CommerceError::CurrencyMismatch {
+                expected: self.currency.code().to_string(),
+                got: entry.price.currency.code().to_string(),
+            });
+        }
+        if entry.price.is_negative() {
+            return Err(CommerceError::ValidationError(format!(
+                "price for variant {} cannot be negative",
+                entry.variant_id
+            )));
+        }
+        check_window(entry.starts_at, entry.ends_at)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Add a price entry, builder-style.
+    pub fn with_entry(mut self, entry: PriceEntry) -> Result<Self, CommerceError> {
+        self.add_entry(entry)?;
+        Ok(self)
+    }
+
+    /// Check if the list applies to a customer group at the given time.
+    pub fn applies_to(&self, customer_group: Option<&str>, timestamp: i64) -> bool {
+        if !self.active || !in_window(self.starts_at, self.ends_at, timestamp) {
+            return false;
+        }
+        match (&self.customer_group, customer_group) {
+            (None, _) => true,
+            (Some(list), Some(group)) => list.eq_ignore_ascii_case(group),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Get the entry in effect for a variant at the given time.
+    ///
+    /// A scheduled entry takes precedence over an unscheduled one.
+    pub fn entry_for(&self, variant_id: &VariantId, timestamp: i64) -> Option<&PriceEntry> {
+        let mut active = self
+            .entries
+            .iter()
+            .filter(|e| &e.variant_id == variant_id && e.is_active_at(timestamp));
+        active
+            .clone()
+            .find(|e| e.is_scheduled())
+            .or_else(|| active.next())
+    }
+}
+
+/// The rule that produced a resolved price.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PriceRule {
+    /// The variant's own price.
+    BasePrice,
+    /// An entry from a price list.
+    PriceList {
+        /// The winning price list.
+        price_list_id: PriceListId,
+        /// Name of the winning price list.
+        name: String,
+        /// Whether the winning entry was scheduled (a sale price).
+        scheduled: bool,
+    },
+}
+
+/// The effective price for a variant.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResolvedPrice {
+    /// The price to charge.
+    pub price: Money,
+    /// The variant's regular price, if the resolved price is lower.
+    pub compare_at_price: Option<Money>,
+    /// The rule that won.
+    pub rule: PriceRule,
+}
+
+impl ResolvedPrice {
+    /// Check if the resolved price is lower than the regular price.
+    pub fn is_discounted(&self) -> bool {
+        self.compare_at_price.is_some()
+    }
+}
+
+/// Resolves the effective price of a variant from a set of price lists.
+///
+/// Among the lists that apply to the customer group, currency, and time,
+/// the lowest price wins, with list priority breaking ties. If no list has
+/// a price, the variant's own price is used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceResolver {
+    /// Known price lists.
+    pub price_lists: Vec<PriceList>,
+}
+
+impl PriceResolver {
+    /// Create a resolver with no price lists.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a price list.
+    pub fn with_list(mut self, list: PriceList) -> Self {
+        self.price_lists.push(list);
+        self
+    }
+
+    /// Resolve the effective price of a variant.
+    pub fn resolve(
+        &self,
+        variant: &ProductVariant,
+        customer_group: Option<&str>,
+        currency: Currency,
+        timestamp: i64,
+    ) -> Result<ResolvedPrice, CommerceError> {
+        let winner = self
+            .price_lists
+            .iter()
+            .filter(|list| list.currency == currency && list.applies_to(customer_group, timestamp))
+            .filter_map(|list| {
+                list.entry_for(&variant.id, timestamp)
+                    .map(|entry| (list, entry))
+            })
+            .min_by_key(|(list, entry)| (entry.price.amount_cents, list.priority));
+
+        let regular = if variant.price.currency == currency {
+            Some(variant.price)
+        } else {
+            None
+        };
+
+        match (winner, regular) {
+            (Some((list, entry)), _) => Ok(ResolvedPrice {
+                price: entry.price,
+                compare_at_price: regular.filter(|r| r.amount_cents > entry.price.amount_cents),
+                rule: PriceRule::PriceList {
+                    price_list_id: list.id.clone(),
+                    name: list.name.clone(),
+                    scheduled: entry.is_scheduled(),
+                },
+            }),
+            (None, Some(price)) => Ok(ResolvedPrice {
+                price,
+                compare_at_price: None,
+                rule: PriceRule::BasePrice,
+            }),
+            (None, None) => Err(CommerceError::CurrencyMismatch {
+                expected: currency.code().to_string(),
+                got: variant.price.currency.code().to_string(),
+            }),
+        }
+    }
+
+    /// Resolve the effective price at the current time.
+    pub fn resolve_now(
+        &self,
+        variant: &ProductVariant,
+        customer_group: Option<&str>,
+        currency: Currency,
+    ) -> Result<ResolvedPrice, CommerceError> {
+        self.resolve(variant, customer_group, currency, current_timestamp())
+    }
+}
+
+fn check_window(starts_at: Option<i64>, ends_at: Option<i64>) -> Result<(), CommerceError> {
+    match (starts_at, ends_at) {
+        (Some(start), Some(end)) if end <= start => Err(CommerceError::ValidationError(
+            "price schedule must end after it starts".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn in_window(starts_at: Option<i64>, ends_at: Option<i64>, timestamp: i64) -> bool {
+    if let Some(starts) = starts_at {
+        if timestamp < starts {
+            return false;
+        }
+    }
+    if let Some(ends) = ends_at {
+        if timestamp >= ends {
+            return false;
+        }
+    }
+    true
+}
+
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkout::fixtures::usd;
+    use crate::ids::ProductId;
+
+    fn variant() -> ProductVariant {
+        ProductVariant::new(ProductId::new("p1"), "SKU-1", usd(2000))
+    }
+
+    #[test]
+    fn test_base_price_fallback() {
+        let v = variant();
+        let resolved = PriceResolver::new()
+            .resolve(&v, None, Currency::USD, 0)
+            .unwrap();
+        assert_eq!(resolved.price, usd(2000));
+        assert_eq!(resolved.rule, PriceRule::BasePrice);
+        assert!(PriceResolver::new()
+            .resolve(&v, None, Currency::EUR, 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_customer_group_list() {
+        let v = variant();
+        let wholesale = PriceList::new("Wholesale", Currency::USD)
+            .for_group("wholesale")
+            .with_entry(PriceEntry::new(v.id.clone(), usd(1200)))
+            .unwrap();
+        let resolver = PriceResolver::new().with_list(wholesale);
+
+        let retail = resolver.resolve(&v, None, Currency::USD, 0).unwrap();
+        assert_eq!(retail.price, usd(2000));
+
+        let trade = resolver
+            .resolve(&v, Some("Wholesale"), Currency::USD, 0)
+            .unwrap();
+        assert_eq!(trade.price, usd(1200));
+        assert_eq!(trade.compare_at_price, Some(usd(2000)));
+        assert!(matches!(trade.rule, PriceRule::PriceList { ref name, .. } if name == "Wholesale"));
+    }
+
+    #[test]
+    fn test_scheduled_sale_price() {
+        let v = variant();
+        let mut retail = PriceList::new("Retail", Currency::USD);
+        retail
+            .add_entry(PriceEntry::new(v.id.clone(), usd(1900)))
+            .unwrap();
+        retail
+            .add_entry(PriceEntry::new(v.id.clone(), usd(1500)).scheduled(Some(100), Some(200)))
+            .unwrap();
+        let resolver = PriceResolver::new().with_list(retail);
+
+        let before = resolver.resolve(&v, None, Currency::USD, 50).unwrap();
+        assert_eq!(before.price, usd(1900));
+
+        let during = resolver.resolve(&v, None, Currency::USD, 150).unwrap();
+        assert_eq!(during.price, usd(1500));
+        assert!(matches!(
+            during.rule,
+            PriceRule::PriceList {
+                scheduled: true,
+                ..
+            }
+        ));
+
+        let after = resolver.resolve(&v, None, Currency::USD, 200).unwrap();
+        assert_eq!(after.price, usd(1900));
+    }
+
+    #[test]
+    fn test_lowest_price_wins() {
+        let v = variant();
+        let vip = PriceList::new("VIP", Currency::USD)
+            .for_group("vip")
+            .with_entry(PriceEntry::new(v.id.clone(), usd(1700)))
+            .unwrap();
+        let sale = PriceList::new("Summer Sale", Currency::USD)
+            .scheduled(Some(0), Some(1_000))
+            .unwrap()
+            .with_entry(PriceEntry::new(v.id.clone(), usd(1600)))
+            .unwrap();
+        let resolver = PriceResolver::new().with_list(vip).with_list(sale);
+
+        let during = resolver
+            .resolve(&v, Some("vip"), Currency::USD, 10)
+            .unwrap();
+        assert!(
+            matches!(during.rule, PriceRule::PriceList { ref name, .. } if name == "Summer Sale")
+        );
+
+        let after = resolver
+            .resolve(&v, Some("vip"), Currency::USD, 5_000)
+            .unwrap();
+        assert_eq!(after.price, usd(1700));
+    }
+
+    #[test]
+    fn test_add_entry_validation() {
+        let v = variant();
+        let mut list = PriceList::new("Retail", Currency::USD);
+        assert!(list
+            .add_entry(PriceEntry::new(
+                v.id.clone(),
+                Money::new(100, Currency::EUR)
+            ))
+            .is_err());
+        assert!(list
+            .add_entry(PriceEntry::new(v.id.clone(), usd(100)).scheduled(Some(10), Some(5)))
+            .is_err());
+        assert!(list.entries.is_empty());
+        assert!(PriceList::new("Sale", Currency::USD)
+            .scheduled(Some(10), Some(10))
+            .is_err());
+    }
+}
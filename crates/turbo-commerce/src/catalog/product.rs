@@ -1,6 +1,10 @@
 //! Product and variant types.
 
-use crate::catalog::InventoryLevel;
+use crate::catalog::options::option_matrix;
+use crate::catalog::{
+    AttributeSchema, AttributeValue, Attributes, InventoryLevel, ProductOption, VariantOverride,
+};
+use crate::error::CommerceError;
 use crate::ids::{CategoryId, MediaId, ProductId, VariantId};
 use crate::money::Money;
 use serde::{Deserialize, Serialize};
@@ -105,8 +109,15 @@ pub struct Product {
     pub tags: Vec<String>,
     /// ID of the default variant (for variable products).
     pub default_variant_id: Option<VariantId>,
-    /// Additional metadata as JSON.
-    pub metadata: serde_json::Value,
+    /// Configurable options (e.g., Size, Color).
+    #[serde(default)]
+    pub options: Vec<ProductOption>,
+    /// Variants of this product.
+    #[serde(default)]
+    pub variants: Vec<ProductVariant>,
+    /// Typed attributes, validated against an [`AttributeSchema`].
+    #[serde(default)]
+    pub attributes: Attributes,
     /// Unix timestamp of creation.
     pub created_at: i64,
     /// Unix timestamp of last update.
@@ -129,7 +140,9 @@ impl Product {
             category_ids: Vec::new(),
             tags: Vec::new(),
             default_variant_id: None,
-            metadata: serde_json::Value::Object(serde_json::Map::new()),
+            options: Vec::new(),
+            variants: Vec::new(),
+            attributes: Attributes::new(),
             created_at: now,
             updated_at: now,
         }
@@ -164,6 +177,167 @@ impl Product {
             self.tags.push(tag);
         }
     }
+
+    /// Set an attribute after checking it against the schema.
+    pub fn set_attribute(
+        &mut self,
+        schema: &AttributeSchema,
+        key: impl Into<String>,
+        value: AttributeValue,
+    ) -> Result<(), CommerceError> {
+        let key = key.into();
+        schema.check(&key, &value)?;
+        self.attributes.insert(key, value);
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    /// Get an attribute value.
+    pub fn attribute(&self, key: &str) -> Option<&AttributeValue> {
+        self.attributes.get(key)
+    }
+
+    /// Validate all attributes against a schema.
+    pub fn validate_attributes(&self, schema: &AttributeSchema) -> Result<(), CommerceError> {
+        schema.validate(&self.attributes)
+    }
+
+    /// Add an option, replacing any existing option with the same name.
+    pub fn set_option(&mut self, option: ProductOption) {
+        match self.options.iter_mut().find(|o| o.is_named(&option.name)) {
+            Some(existing) => *existing = option,
+            None => self.options.push(option),
+        }
+    }
+
+    /// Get an option by name (case-insensitive).
+    pub fn option(&self, name: &str) -> Option<&ProductOption> {
+        self.options.iter().find(|o| o.is_named(name))
+    }
+
+    /// Generate one variant per combination of option values.
+    ///
+    /// Variants start from `base_price` and a SKU derived from the product
+    /// SKU and option values (e.g., "TEE-M-RED"); `overrides` are applied on
+    /// top in order. Variants whose options are unchanged keep their ID, so
+    /// regenerating after adding a value doesn't invalidate existing carts.
+    ///
+    /// A simple product becomes [`ProductType::Variable`]; other types are kept.
+    pub fn generate_variants(
+        &mut self,
+        base_price: Money,
+        overrides: &[VariantOverride],
+    ) -> Result<&[ProductVariant], CommerceError> {
+        let matrix = option_matrix(&self.options)?;
+        let previous = std::mem::take(&mut self.variants);
+
+        for (position, options) in matrix.into_iter().enumerate() {
+            let sku = std::iter::once(self.sku.clone())
+                .chain(options.iter().map(|o| sku_segment(&o.value)))
+                .collect::<Vec<_>>()
+                .join("-");
+            let mut variant = ProductVariant::new(self.id.clone(), sku, base_price);
+            if let Some(existing) = previous.iter().find(|v| v.options == options) {
+                variant.id = existing.id.clone();
+                variant.created_at = existing.created_at;
+            }
+            variant.options = options;
+            variant.name = Some(variant.build_name());
+            variant.position = position as i32;
+
+            for o in overrides.iter().filter(|o| o.matches(&variant.options)) {
+                if let Some(ref sku) = o.sku {
+                    variant.sku = sku.clone();
+                }
+                if let Some(price) = o.price {
+                    variant.price = price;
+                }
+                if let Some(compare_at) = o.compare_at_price {
+                    variant.compare_at_price = Some(compare_at);
+                }
+                if let Some(ref inventory) = o.inventory {
+                    variant.inventory = inventory.clone();
+                }
+                if let Some(grams) = o.weight_grams {
+                    variant.weight_grams = Some(grams);
+                }
+            }
+
+            self.variants.push(variant);
+        }
+
+        // Digital and bundle products keep their type; only simple ones
+        // become variable.
+        if !self.variants.is_empty() && self.product_type == ProductType::Simple {
+            self.product_type = ProductType::Variable;
+        }
+        let default_still_exists = self
+            .default_variant_id
+            .as_ref()
+            .map(|id| self.variants.iter().any(|v| &v.id == id))
+            .unwrap_or(false);
+        if !default_still_exists {
+            self.default_variant_id = self.variants.first().map(|v| v.id.clone());
+        }
+        self.updated_at = current_timestamp();
+
+        Ok(&self.variants)
+    }
+
+    /// Find the variant matching an option selection.
+    ///
+    /// Names and values are compared case-insensitively, and every option
+    /// of the variant must be selected.
+    ///
+    /// ```rust,ignore
+    /// let variant = product.variant_for_options(&[("size", "M"), ("color", "red")]);
+    /// ```
+    pub fn variant_for_options(&self, selected: &[(&str, &str)]) -> Option<&ProductVariant> {
+        self.variants.iter().find(|v| {
+            v.options.len() == selected.len()
+                && v.options.iter().all(|o| {
+                    selected.iter().any(|(name, value)| {
+                        o.name.eq_ignore_ascii_case(name) && o.value.eq_ignore_ascii_case(value)
+                    })
+                })
+        })
+    }
+
+    /// Get a variant by ID.
+    pub fn variant(&self, id: &VariantId) -> Option<&ProductVariant> {
+        self.variants.iter().find(|v| &v.id == id)
+    }
+
+    /// Get the default variant.
+    pub fn default_variant(&self) -> Option<&ProductVariant> {
+        self.default_variant_id
+            .as_ref()
+            .and_then(|id| self.variant(id))
+            .or_else(|| self.variants.first())
+    }
+
+    /// Get variants that have a specific option value.
+    pub fn variants_with_option<'a>(
+        &'a self,
+        name: &'a str,
+        value: &'a str,
+    ) -> impl Iterator<Item = &'a ProductVariant> + 'a {
+        self.variants.iter().filter(move |v| {
+            v.options
+                .iter()
+                .any(|o| o.name.eq_ignore_ascii_case(name) && o.value.eq_ignore_ascii_case(value))
+        })
+    }
+}
+
+/// Turn an option value into an uppercase SKU segment (e.g., "Navy Blue" -> "NAVY-BLUE").
+fn sku_segment(value: &str) -> String {
+    value
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_ascii_uppercase())
+        .collect::<Vec<_>>()
+        .join("-")
 }
 
 /// A product variant (e.g., size/color combination).
@@ -400,4 +574,84 @@ mod tests {
 
         assert_eq!(variant.build_name(), "Large / Blue");
     }
+
+    #[test]
+    fn test_generate_variant_matrix() {
+        let mut product = Product::new("TEE", "T-Shirt", "t-shirt");
+        product.set_option(ProductOption::new("Size", ["S", "M"]));
+        product.set_option(ProductOption::new("Color", ["Red", "Navy Blue"]));
+
+        let overrides = [
+            VariantOverride::new(&[("Color", "Red")]).with_price(Money::new(2500, Currency::USD)),
+            VariantOverride::new(&[("Size", "M"), ("Color", "Red")])
+                .with_sku("TEE-SPECIAL")
+                .with_inventory(InventoryLevel::new(3)),
+        ];
+        let variants = product
+            .generate_variants(Money::new(2000, Currency::USD), &overrides)
+            .unwrap();
+
+        assert_eq!(variants.len(), 4);
+        assert_eq!(variants[1].sku, "TEE-S-NAVY-BLUE");
+        assert_eq!(variants[1].name.as_deref(), Some("S / Navy Blue"));
+        assert_eq!(product.product_type, ProductType::Variable);
+        assert_eq!(
+            product.default_variant_id,
+            Some(product.variants[0].id.clone())
+        );
+
+        let red_m = product
+            .variant_for_options(&[("size", "M"), ("color", "red")])
+            .unwrap();
+        assert_eq!(red_m.sku, "TEE-SPECIAL");
+        assert_eq!(red_m.price.amount_cents, 2500);
+        assert_eq!(red_m.inventory.available(), 3);
+
+        let blue_s = product
+            .variant_for_options(&[("Size", "S"), ("Color", "Navy Blue")])
+            .unwrap();
+        assert_eq!(blue_s.price.amount_cents, 2000);
+
+        assert!(product.variant_for_options(&[("Size", "S")]).is_none());
+        assert_eq!(product.variants_with_option("Color", "Red").count(), 2);
+    }
+
+    #[test]
+    fn test_regenerate_keeps_variant_ids() {
+        let mut product = Product::new("TEE", "T-Shirt", "t-shirt");
+        product.set_option(ProductOption::new("Size", ["S", "M"]));
+        product
+            .generate_variants(Money::new(2000, Currency::USD), &[])
+            .unwrap();
+        let small_id = product
+            .variant_for_options(&[("Size", "S")])
+            .unwrap()
+            .id
+            .clone();
+
+        product.set_option(ProductOption::new("Size", ["S", "M", "L"]));
+        product
+            .generate_variants(Money::new(2000, Currency::USD), &[])
+            .unwrap();
+
+        assert_eq!(product.variants.len(), 3);
+        assert_eq!(
+            product.variant_for_options(&[("Size", "S")]).unwrap().id,
+            small_id
+        );
+    }
+
+    #[test]
+    fn test_generate_variants_keeps_digital_type() {
+        let mut product = Product::new("EBOOK", "Rust eBook", "rust-ebook");
+        product.product_type = ProductType::Digital;
+        product.set_option(ProductOption::new("Format", ["PDF", "EPUB"]));
+        product
+            .generate_variants(Money::new(1500, Currency::USD), &[])
+            .unwrap();
+
+        assert_eq!(product.variants.len(), 2);
+        assert_eq!(product.product_type, ProductType::Digital);
+        assert!(product.is_digital());
+    }
 }
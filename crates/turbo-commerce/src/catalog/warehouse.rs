@@ -0,0 +1,615 @@
+//! Multi-location inventory and allocation.
+//!
+//! A variant can be stocked in several locations (warehouses, stores). An
+//! [`InventoryAllocator`] decides which locations fulfill an order and
+//! produces a [`FulfillmentPlan`] that checkout uses to split shipments.
+
+use crate::cart::Cart;
+use crate::catalog::InventoryLevel;
+use crate::checkout::Address;
+use crate::error::CommerceError;
+use crate::ids::{LocationId, VariantId};
+use serde::{Deserialize, Serialize};
+
+/// A location that holds stock.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StockLocation {
+    /// Unique location identifier.
+    pub id: LocationId,
+    /// Display name (e.g., "East Coast DC").
+    pub name: String,
+    /// Country code (e.g., "US").
+    pub country_code: String,
+    /// State/province code (e.g., "NJ").
+    pub province_code: Option<String>,
+    /// Priority for tie-breaking (lower ships first).
+    pub priority: i32,
+    /// Whether this location can currently fulfill orders.
+    pub active: bool,
+}
+
+impl StockLocation {
+    /// Create a new active location.
+    pub fn new(
+        id: impl Into<LocationId>,
+        name: impl Into<String>,
+        country_code: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            country_code: country_code.into(),
+            province_code: None,
+            priority: 0,
+            active: true,
+        }
+    }
+
+    /// Set the province code.
+    pub fn with_province(mut self, province_code: impl Into<String>) -> Self {
+        self.province_code = Some(province_code.into());
+        self
+    }
+
+    /// Set the priority.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Rank how close this location is to a destination (0 = same province,
+    /// 1 = same country, 2 = elsewhere).
+    pub fn proximity(&self, destination: &Address) -> u8 {
+        if !self
+            .country_code
+            .eq_ignore_ascii_case(&destination.country_code)
+        {
+            return 2;
+        }
+        match (&self.province_code, &destination.province_code) {
+            (Some(a), Some(b)) if a.eq_ignore_ascii_case(b) => 0,
+            _ => 1,
+        }
+    }
+}
+
+/// Inventory for one variant at one location.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LocationInventory {
+    /// Location holding the stock.
+    pub location_id: LocationId,
+    /// Inventory level at this location.
+    pub level: InventoryLevel,
+}
+
+/// Inventory for a variant across all of its locations.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VariantStock {
+    /// The variant.
+    pub variant_id: VariantId,
+    /// Per-location inventory levels.
+    pub locations: Vec<LocationInventory>,
+}
+
+impl VariantStock {
+    /// Create stock with no locations.
+    pub fn new(variant_id: VariantId) -> Self {
+        Self {
+            variant_id,
+            locations: Vec::new(),
+        }
+    }
+
+    /// Set the inventory level at a location.
+    pub fn set_level(&mut self, location_id: impl Into<LocationId>, level: InventoryLevel) {
+        let location_id = location_id.into();
+        match self
+            .locations
+            .iter_mut()
+            .find(|l| l.location_id == location_id)
+        {
+            Some(existing) => existing.level = level,
+            None => self
+                .locations
+                .push(LocationInventory { location_id, level }),
+        }
+    }
+
+    /// Builder-style variant of [`set_level`](Self::set_level).
+    pub fn with_level(mut self, location_id: impl Into<LocationId>, level: InventoryLevel) -> Self {
+        self.set_level(location_id, level);
+        self
+    }
+
+    /// Get the inventory level at a location.
+    pub fn level_at(&self, location_id: &LocationId) -> Option<&InventoryLevel> {
+        self.locations
+            .iter()
+            .find(|l| &l.location_id == location_id)
+            .map(|l| &l.level)
+    }
+
+    /// Get a mutable inventory level at a location.
+    pub fn level_at_mut(&mut self, location_id: &LocationId) -> Option<&mut InventoryLevel> {
+        self.locations
+            .iter_mut()
+            .find(|l| &l.location_id == location_id)
+            .map(|l| &mut l.level)
+    }
+
+    /// Total available quantity across tracked locations.
+    pub fn total_available(&self) -> i64 {
+        self.locations
+            .iter()
+            .filter(|l| l.level.track_inventory)
+            .map(|l| l.level.available().max(0))
+            .sum()
+    }
+
+    /// Check if any location can take orders for this variant.
+    pub fn is_available(&self) -> bool {
+        self.locations.iter().any(|l| l.level.is_available())
+    }
+}
+
+/// Strategy for choosing which locations fulfill an order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum AllocationStrategy {
+    /// Prefer locations closest to the shipping address.
+    #[default]
+    Nearest,
+    /// Prefer locations with the most available stock.
+    MostStock,
+    /// Use locations in the given order; unlisted locations are not used.
+    PriorityList(Vec<LocationId>),
+}
+
+/// A quantity of a variant allocated to a shipment.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AllocatedLine {
+    /// The variant.
+    pub variant_id: VariantId,
+    /// Quantity shipped from this location.
+    pub quantity: i64,
+    /// Whether this quantity is a backorder.
+    pub backordered: bool,
+}
+
+/// A shipment from a single location.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlannedShipment {
+    /// Location the shipment leaves from.
+    pub location_id: LocationId,
+    /// Lines in this shipment.
+    pub lines: Vec<AllocatedLine>,
+}
+
+impl PlannedShipment {
+    /// Total units in this shipment.
+    pub fn item_count(&self) -> i64 {
+        self.lines.iter().map(|l| l.quantity).sum()
+    }
+}
+
+/// Which locations fulfill which quantities of an order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct FulfillmentPlan {
+    /// One shipment per location used.
+    pub shipments: Vec<PlannedShipment>,
+}
+
+impl FulfillmentPlan {
+    /// Number of shipments.
+    pub fn shipment_count(&self) -> usize {
+        self.shipments.len()
+    }
+
+    /// Check if the order ships from more than one location.
+    pub fn is_split(&self) -> bool {
+        self.shipments.len() > 1
+    }
+
+    /// Check if any quantity is backordered.
+    pub fn has_backorders(&self) -> bool {
+        self.shipments
+            .iter()
+            .flat_map(|s| &s.lines)
+            .any(|l| l.backordered)
+    }
+
+    /// Total quantity planned for a variant across all shipments.
+    pub fn quantity_for(&self, variant_id: &VariantId) -> i64 {
+        self.shipments
+            .iter()
+            .flat_map(|s| &s.lines)
+            .filter(|l| &l.variant_id == variant_id)
+            .map(|l| l.quantity)
+            .sum()
+    }
+
+    /// Reserve the planned quantities at each location.
+    ///
+    /// Either every reservation succeeds or none are applied.
+    pub fn reserve(&self, stock: &mut [VariantStock]) -> Result<(), CommerceError> {
+        let mut updated = stock.to_vec();
+        for shipment in &self.shipments {
+            for line in &shipment.lines {
+                let level = updated
+                    .iter_mut()
+                    .find(|s| s.variant_id == line.variant_id)
+                    .and_then(|s| s.level_at_mut(&shipment.location_id))
+                    .ok_or_else(|| CommerceError::VariantNotFound(line.variant_id.to_string()))?;
+                if !level.reserve(line.quantity) {
+                    return Err(CommerceError::InsufficientInventory {
+                        product_id: line.variant_id.to_string(),
+                        requested: line.quantity,
+                        available: level.available(),
+                    });
+                }
+            }
+        }
+        stock.clone_from_slice(&updated);
+        Ok(())
+    }
+
+    fn add(&mut self, location_id: &LocationId, line: AllocatedLine) {
+        match self
+            .shipments
+            .iter_mut()
+            .find(|s| &s.location_id == location_id)
+        {
+            Some(shipment) => shipment.lines.push(line),
+            None => self.shipments.push(PlannedShipment {
+                location_id: location_id.clone(),
+                lines: vec![line],
+            }),
+        }
+    }
+}
+
+/// Allocates order lines to stock locations.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InventoryAllocator {
+    /// Known locations.
+    pub locations: Vec<StockLocation>,
+    /// Allocation strategy.
+    pub strategy: AllocationStrategy,
+}
+
+impl InventoryAllocator {
+    /// Create an allocator over the given locations.
+    pub fn new(locations: Vec<StockLocation>, strategy: AllocationStrategy) -> Self {
+        Self {
+            locations,
+            strategy,
+        }
+    }
+
+    /// Allocate a cart's items.
+    pub fn allocate_cart(
+        &self,
+        cart: &Cart,
+        stock: &[VariantStock],
+        destination: &Address,
+    ) -> Result<FulfillmentPlan, CommerceError> {
+        let lines: Vec<(VariantId, i64)> = cart
+            .items
+            .iter()
+            .map(|i| (i.variant_id.clone(), i.quantity))
+            .collect();
+        self.allocate(&lines, stock, destination)
+    }
+
+    /// Allocate `(variant, quantity)` lines to locations.
+    ///
+    /// If a single location can ship every line, the whole order ships from
+    /// the best-ranked such location. Otherwise each line is filled greedily
+    /// from ranked locations, with any shortfall backordered at the first
+    /// location that allows it. Lines for the same variant are combined
+    /// before checking stock.
+    pub fn allocate(
+        &self,
+        lines: &[(VariantId, i64)],
+        stock: &[VariantStock],
+        destination: &Address,
+    ) -> Result<FulfillmentPlan, CommerceError> {
+        let mut plan = FulfillmentPlan::default();
+
+        let mut combined: Vec<(VariantId, i64)> = Vec::with_capacity(lines.len());
+        for (variant_id, quantity) in lines {
+            if *quantity <= 0 {
+                return Err(CommerceError::InvalidQuantity(*quantity));
+            }
+            if !stock.iter().any(|s| &s.variant_id == variant_id) {
+                return Err(CommerceError::VariantNotFound(variant_id.to_string()));
+            }
+            match combined.iter_mut().find(|(id, _)| id == variant_id) {
+                Some((_, total)) => *total += quantity,
+                None => combined.push((variant_id.clone(), *quantity)),
+            }
+        }
+        let lines = combined.as_slice();
+
+        let single = self
+            .ranked(None, stock, destination)
+            .into_iter()
+            .find(|loc| {
+                lines.iter().all(|(variant_id, quantity)| {
+                    level_for(stock, variant_id, &loc.id)
+                        .map(|l| !l.track_inventory || l.available() >= *quantity)
+                        .unwrap_or(false)
+                })
+            });
+        if let Some(location) = single {
+            for (variant_id, quantity) in lines {
+                plan.add(
+                    &location.id,
+                    AllocatedLine {
+                        variant_id: variant_id.clone(),
+                        quantity: *quantity,
+                        backordered: false,
+                    },
+                );
+            }
+            return Ok(plan);
+        }
+
+        for (variant_id, quantity) in lines {
+            let ranked = self.ranked(Some(variant_id), stock, destination);
+            let mut remaining = *quantity;
+
+            for location in &ranked {
+                if remaining == 0 {
+                    break;
+                }
+                let Some(level) = level_for(stock, variant_id, &location.id) else {
+                    continue;
+                };
+                let take = if level.track_inventory {
+                    remaining.min(level.available().max(0))
+                } else {
+                    remaining
+                };
+                if take > 0 {
+                    plan.add(
+                        &location.id,
+                        AllocatedLine {
+                            variant_id: variant_id.clone(),
+                            quantity: take,
+                            backordered: false,
+                        },
+                    );
+                    remaining -= take;
+                }
+            }
+
+            if remaining > 0 {
+                let backorder = ranked.iter().find(|loc| {
+                    level_for(stock, variant_id, &loc.id)
+                        .map(|l| l.allow_backorder)
+                        .unwrap_or(false)
+                });
+                match backorder {
+                    Some(location) => plan.add(
+                        &location.id,
+                        AllocatedLine {
+                            variant_id: variant_id.clone(),
+                            quantity: remaining,
+                            backordered: true,
+                        },
+                    ),
+                    None => {
+                        return Err(CommerceError::InsufficientInventory {
+                            product_id: variant_id.to_string(),
+                            requested: *quantity,
+                            available: quantity - remaining,
+                        })
+                    }
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Active locations in strategy order. `MostStock` ranks by the given
+    /// variant's stock, or by total stock of all variants when `None`.
+    fn ranked(
+        &self,
+        variant_id: Option<&VariantId>,
+        stock: &[VariantStock],
+        destination: &Address,
+    ) -> Vec<&StockLocation> {
+        let active = self.locations.iter().filter(|l| l.active);
+        match &self.strategy {
+            AllocationStrategy::Nearest => {
+                let mut ranked: Vec<_> = active.collect();
+                ranked.sort_by_key(|l| (l.proximity(destination), l.priority));
+                ranked
+            }
+            AllocationStrategy::MostStock => {
+                let available_at = |location_id: &LocationId| -> i64 {
+                    stock
+                        .iter()
+                        .filter(|s| variant_id.map(|v| &s.variant_id == v).unwrap_or(true))
+                        .filter_map(|s| s.level_at(location_id))
+                        .map(|l| l.available().max(0))
+                        .sum()
+                };
+                let mut ranked: Vec<_> = active.collect();
+                ranked.sort_by_key(|l| (std::cmp::Reverse(available_at(&l.id)), l.priority));
+                ranked
+            }
+            AllocationStrategy::PriorityList(order) => {
+                let active: Vec<_> = active.collect();
+                order
+                    .iter()
+                    .filter_map(|id| active.iter().find(|l| &l.id == id).copied())
+                    .collect()
+            }
+        }
+    }
+}
+
+fn level_for<'a>(
+    stock: &'a [VariantStock],
+    variant_id: &VariantId,
+    location_id: &LocationId,
+) -> Option<&'a InventoryLevel> {
+    stock
+        .iter()
+        .find(|s| &s.variant_id == variant_id)
+        .and_then(|s| s.level_at(location_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn destination() -> Address {
+        let mut addr = Address::new("Jane", "Doe", "1 Main St", "Austin", "US", "US", "73301");
+        addr.province_code = Some("TX".to_string());
+        addr
+    }
+
+    fn locations() -> Vec<StockLocation> {
+        vec![
+            StockLocation::new("nj", "New Jersey", "US").with_province("NJ"),
+            StockLocation::new("tx", "Texas", "US").with_province("TX"),
+            StockLocation::new("de", "Berlin", "DE").with_priority(-1),
+        ]
+    }
+
+    #[test]
+    fn test_nearest_single_location() {
+        let stock = vec![VariantStock::new(VariantId::new("tee"))
+            .with_level("nj", InventoryLevel::new(10))
+            .with_level("tx", InventoryLevel::new(10))];
+        let allocator = InventoryAllocator::new(locations(), AllocationStrategy::Nearest);
+
+        let plan = allocator
+            .allocate(&[(VariantId::new("tee"), 2)], &stock, &destination())
+            .unwrap();
+        assert!(!plan.is_split());
+        assert_eq!(plan.shipments[0].location_id, LocationId::new("tx"));
+    }
+
+    #[test]
+    fn test_split_shipment() {
+        let stock = vec![
+            VariantStock::new(VariantId::new("tee"))
+                .with_level("tx", InventoryLevel::new(1))
+                .with_level("nj", InventoryLevel::new(5)),
+            VariantStock::new(VariantId::new("mug")).with_level("nj", InventoryLevel::new(5)),
+        ];
+        let allocator = InventoryAllocator::new(locations(), AllocationStrategy::Nearest);
+
+        let plan = allocator
+            .allocate(
+                &[(VariantId::new("tee"), 3), (VariantId::new("mug"), 1)],
+                &stock,
+                &destination(),
+            )
+            .unwrap();
+        // NJ can ship everything, so no split is needed.
+        assert_eq!(plan.shipment_count(), 1);
+        assert_eq!(plan.shipments[0].location_id, LocationId::new("nj"));
+
+        let plan = allocator
+            .allocate(&[(VariantId::new("tee"), 6)], &stock, &destination())
+            .unwrap();
+        assert!(plan.is_split());
+        assert_eq!(plan.shipments[0].location_id, LocationId::new("tx"));
+        assert_eq!(plan.shipments[0].item_count(), 1);
+        assert_eq!(plan.quantity_for(&VariantId::new("tee")), 6);
+    }
+
+    #[test]
+    fn test_repeated_variant_lines_are_combined() {
+        let stock = vec![VariantStock::new(VariantId::new("tee"))
+            .with_level("tx", InventoryLevel::new(3))
+            .with_level("nj", InventoryLevel::new(3))];
+        let allocator = InventoryAllocator::new(locations(), AllocationStrategy::Nearest);
+
+        let lines = [(VariantId::new("tee"), 2), (VariantId::new("tee"), 2)];
+        let plan = allocator.allocate(&lines, &stock, &destination()).unwrap();
+        // Neither location holds 4, so the order is split instead of
+        // over-allocating Texas.
+        assert!(plan.is_split());
+        assert_eq!(plan.quantity_for(&VariantId::new("tee")), 4);
+
+        let too_many = [(VariantId::new("tee"), 4), (VariantId::new("tee"), 3)];
+        assert!(allocator
+            .allocate(&too_many, &stock, &destination())
+            .is_err());
+    }
+
+    #[test]
+    fn test_most_stock_and_priority_list() {
+        let stock = vec![VariantStock::new(VariantId::new("tee"))
+            .with_level("tx", InventoryLevel::new(2))
+            .with_level("de", InventoryLevel::new(50))];
+
+        let most = InventoryAllocator::new(locations(), AllocationStrategy::MostStock);
+        let plan = most
+            .allocate(&[(VariantId::new("tee"), 1)], &stock, &destination())
+            .unwrap();
+        assert_eq!(plan.shipments[0].location_id, LocationId::new("de"));
+
+        let listed = InventoryAllocator::new(
+            locations(),
+            AllocationStrategy::PriorityList(vec![LocationId::new("nj"), LocationId::new("tx")]),
+        );
+        let plan = listed
+            .allocate(&[(VariantId::new("tee"), 2)], &stock, &destination())
+            .unwrap();
+        assert_eq!(plan.shipments[0].location_id, LocationId::new("tx"));
+        assert!(listed
+            .allocate(&[(VariantId::new("tee"), 3)], &stock, &destination())
+            .is_err());
+    }
+
+    #[test]
+    fn test_backorder_and_reserve() {
+        let mut backorderable = InventoryLevel::new(1);
+        backorderable.allow_backorder = true;
+        let mut stock =
+            vec![VariantStock::new(VariantId::new("tee")).with_level("tx", backorderable)];
+        let allocator = InventoryAllocator::new(locations(), AllocationStrategy::Nearest);
+
+        let plan = allocator
+            .allocate(&[(VariantId::new("tee"), 3)], &stock, &destination())
+            .unwrap();
+        assert!(plan.has_backorders());
+        assert_eq!(plan.quantity_for(&VariantId::new("tee")), 3);
+
+        plan.reserve(&mut stock).unwrap();
+        let level = stock[0].level_at(&LocationId::new("tx")).unwrap();
+        assert_eq!(level.reserved, 3);
+    }
+
+    #[test]
+    fn test_reserve_is_all_or_nothing() {
+        let mut stock = vec![
+            VariantStock::new(VariantId::new("a")).with_level("tx", InventoryLevel::new(5)),
+            VariantStock::new(VariantId::new("b")).with_level("tx", InventoryLevel::new(0)),
+        ];
+        let mut plan = FulfillmentPlan::default();
+        for id in ["a", "b"] {
+            plan.add(
+                &LocationId::new("tx"),
+                AllocatedLine {
+                    variant_id: VariantId::new(id),
+                    quantity: 1,
+                    backordered: false,
+                },
+            );
+        }
+
+        assert!(plan.reserve(&mut stock).is_err());
+        assert_eq!(
+            stock[0].level_at(&LocationId::new("tx")).unwrap().reserved,
+            0
+        );
+    }
+}
@@ -104,12 +104,23 @@ impl Address {
 
     /// Check if address is complete.
     pub fn is_complete(&self) -> bool {
-        !self.first_name.is_empty()
-            && !self.last_name.is_empty()
-            && !self.address1.is_empty()
-            && !self.city.is_empty()
-            && !self.country_code.is_empty()
-            && !self.zip.is_empty()
+        self.missing_fields().is_empty()
+    }
+
+    /// Names of required fields that are empty.
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        [
+            ("first_name", &self.first_name),
+            ("last_name", &self.last_name),
+            ("address1", &self.address1),
+            ("city", &self.city),
+            ("country_code", &self.country_code),
+            ("zip", &self.zip),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.trim().is_empty())
+        .map(|(name, _)| name)
+        .collect()
     }
 }
 
@@ -0,0 +1,256 @@
+//! Fulfillment of digital order lines.
+
+use crate::catalog::{DigitalAsset, DownloadEntitlement, LicenseKeyPool, Product};
+use crate::checkout::{Order, OrderLineItem};
+use crate::error::CommerceError;
+use crate::ids::{OrderLineItemId, ProductId, VariantId};
+use serde::{Deserialize, Serialize};
+
+/// A license key issued for an order line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssuedLicense {
+    /// Order line the key was issued for.
+    pub line_item_id: OrderLineItemId,
+    /// Purchased variant.
+    pub variant_id: VariantId,
+    /// The license key.
+    pub key: String,
+}
+
+/// What a paid order's digital lines delivered.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DigitalDelivery {
+    /// Download entitlements granted.
+    pub entitlements: Vec<DownloadEntitlement>,
+    /// License keys issued.
+    pub licenses: Vec<IssuedLicense>,
+    /// Digital lines left unfulfilled because nothing is registered to
+    /// deliver for them yet.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub awaiting_assets: Vec<OrderLineItemId>,
+}
+
+impl DigitalDelivery {
+    /// Check if nothing was delivered.
+    pub fn is_empty(&self) -> bool {
+        self.entitlements.is_empty() && self.licenses.is_empty()
+    }
+}
+
+/// Allocates download entitlements and license keys when an order is paid.
+///
+/// A line is digital if its product is [`ProductType::Digital`], or if its
+/// variant has an asset or a license key pool. Digital lines are marked
+/// fulfilled once something is delivered; physical lines are left alone.
+///
+/// [`ProductType::Digital`]: crate::catalog::ProductType::Digital
+#[derive(Debug, Clone, Default)]
+pub struct DigitalFulfiller {
+    /// Products whose type is digital.
+    pub digital_products: Vec<ProductId>,
+    /// Downloadable assets.
+    pub assets: Vec<DigitalAsset>,
+    /// License key pools, one per variant.
+    pub key_pools: Vec<LicenseKeyPool>,
+}
+
+impl DigitalFulfiller {
+    /// Create a fulfiller with no assets or keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a catalog product; digital products are never shipped.
+    pub fn with_product(mut self, product: &Product) -> Self {
+        if product.is_digital() && !self.digital_products.contains(&product.id) {
+            self.digital_products.push(product.id.clone());
+        }
+        self
+    }
+
+    /// Add a downloadable asset.
+    pub fn with_asset(mut self, asset: DigitalAsset) -> Self {
+        self.assets.push(asset);
+        self
+    }
+
+    /// Add a license key pool.
+    pub fn with_key_pool(mut self, pool: LicenseKeyPool) -> Self {
+        self.key_pools.push(pool);
+        self
+    }
+
+    /// Fulfill the digital lines of a paid order.
+    ///
+    /// Lines already fulfilled are skipped, so calling this again for the
+    /// same order (e.g., a retried payment webhook) delivers nothing new.
+    /// If any key pool runs out, no keys are issued for the order.
+    pub fn on_order_paid(
+        &mut self,
+        order: &mut Order,
+        now: i64,
+    ) -> Result<DigitalDelivery, CommerceError> {
+        if !order.is_paid() {
+            return Err(CommerceError::ValidationError(format!(
+                "order {} is not paid",
+                order.order_number
+            )));
+        }
+
+        let pending: Vec<usize> = order
+            .line_items
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !line.is_fulfilled() && self.is_digital(line))
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut pools = self.key_pools.clone();
+        let mut delivery = DigitalDelivery::default();
+        let mut delivered = Vec::with_capacity(pending.len());
+        for &i in &pending {
+            let line = &order.line_items[i];
+            let has_pool = pools.iter().any(|p| p.variant_id == line.variant_id);
+            let has_asset = self
+                .assets
+                .iter()
+                .any(|a| a.applies_to(&line.product_id, &line.variant_id));
+            if !has_pool && !has_asset {
+                delivery.awaiting_assets.push(line.id.clone());
+                continue;
+            }
+            delivered.push(i);
+            if let Some(pool) = pools.iter_mut().find(|p| p.variant_id == line.variant_id) {
+                for key in pool.allocate(&order.id, line.unfulfilled_quantity(), now)? {
+                    delivery.licenses.push(IssuedLicense {
+                        line_item_id: line.id.clone(),
+                        variant_id: line.variant_id.clone(),
+                        key,
+                    });
+                }
+            }
+            for asset in self
+                .assets
+                .iter()
+                .filter(|a| a.applies_to(&line.product_id, &line.variant_id))
+            {
+                delivery.entitlements.push(DownloadEntitlement::grant(
+                    order.id.clone(),
+                    line.id.clone(),
+                    asset,
+                    now,
+                ));
+            }
+        }
+        self.key_pools = pools;
+
+        if !delivered.is_empty() {
+            for &i in &delivered {
+                let line = &mut order.line_items[i];
+                line.fulfilled_quantity = line.quantity;
+            }
+            order.refresh_fulfillment_status();
+        }
+        Ok(delivery)
+    }
+
+    /// Check if a line is digital (and so must not be shipped).
+    pub fn is_digital(&self, line: &OrderLineItem) -> bool {
+        self.digital_products.contains(&line.product_id)
+            || self
+                .key_pools
+                .iter()
+                .any(|p| p.variant_id == line.variant_id)
+            || self
+                .assets
+                .iter()
+                .any(|a| a.applies_to(&line.product_id, &line.variant_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkout::{fixtures, FinancialStatus, FulfillmentStatus, OrderStatus};
+    use crate::ids::ProductId;
+
+    fn line(id: &str, variant: &str, quantity: i64) -> OrderLineItem {
+        OrderLineItem {
+            variant_id: VariantId::new(variant),
+            product_id: ProductId::new(format!("p-{}", variant)),
+            ..fixtures::line(id, quantity, 1000)
+        }
+    }
+
+    fn order(lines: Vec<OrderLineItem>) -> Order {
+        Order {
+            status: OrderStatus::Confirmed,
+            financial_status: FinancialStatus::Paid,
+            ..fixtures::order(lines)
+        }
+    }
+
+    fn fulfiller() -> DigitalFulfiller {
+        let mut pool = LicenseKeyPool::new(VariantId::new("app"));
+        pool.add_keys(["KEY-1", "KEY-2"]);
+        DigitalFulfiller::new()
+            .with_asset(DigitalAsset::new(
+                ProductId::new("p-ebook"),
+                "ebook.pdf",
+                "assets/ebook.pdf",
+            ))
+            .with_key_pool(pool)
+    }
+
+    #[test]
+    fn test_paid_order_delivers_digital_lines() {
+        let mut fulfiller = fulfiller();
+        let mut order = order(vec![
+            line("l1", "ebook", 1),
+            line("l2", "app", 2),
+            line("l3", "mug", 1),
+        ]);
+
+        let delivery = fulfiller.on_order_paid(&mut order, 100).unwrap();
+        assert_eq!(delivery.entitlements.len(), 1);
+        assert_eq!(delivery.licenses.len(), 2);
+        assert_eq!(
+            order.fulfillment_status,
+            FulfillmentStatus::PartiallyFulfilled
+        );
+        assert!(!order.line_items[2].is_fulfilled());
+
+        let again = fulfiller.on_order_paid(&mut order, 200).unwrap();
+        assert!(again.is_empty());
+        assert_eq!(fulfiller.key_pools[0].available(), 0);
+    }
+
+    #[test]
+    fn test_unpaid_or_exhausted() {
+        let mut fulfiller = fulfiller();
+        let mut unpaid = order(vec![line("l1", "ebook", 1)]);
+        unpaid.financial_status = FinancialStatus::Pending;
+        assert!(fulfiller.on_order_paid(&mut unpaid, 0).is_err());
+
+        let mut too_many = order(vec![line("l1", "ebook", 1), line("l2", "app", 3)]);
+        assert!(fulfiller.on_order_paid(&mut too_many, 0).is_err());
+        assert_eq!(fulfiller.key_pools[0].available(), 2);
+        assert!(!too_many.line_items[0].is_fulfilled());
+    }
+
+    #[test]
+    fn test_digital_product_without_assets_is_not_shipped() {
+        let mut manual = Product::new("MANUAL", "Manual", "manual");
+        manual.id = ProductId::new("p-manual");
+        manual.product_type = crate::catalog::ProductType::Digital;
+        let mut fulfiller = fulfiller().with_product(&manual);
+        let mut order = order(vec![line("l1", "manual", 1), line("l2", "mug", 1)]);
+
+        assert!(fulfiller.is_digital(&order.line_items[0]));
+        assert!(!fulfiller.is_digital(&order.line_items[1]));
+
+        let delivery = fulfiller.on_order_paid(&mut order, 100).unwrap();
+        assert_eq!(delivery.awaiting_assets, [OrderLineItemId::new("l1")]);
+        assert!(!order.line_items[0].is_fulfilled());
+    }
+}
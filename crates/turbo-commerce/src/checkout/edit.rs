@@ -0,0 +1,539 @@
+//! Post-purchase order editing.
+//!
+//! [`Order::apply_edit`] changes an unshipped order's lines, recomputes its
+//! totals, and returns an [`OrderAmendment`] recording what changed and the
+//! [`PaymentDelta`] owed either way. Settling the amendment charges or
+//! refunds the difference through a [`PaymentGateway`].
+
+use crate::checkout::order::pro_rata;
+use crate::checkout::{
+    FinancialStatus, FulfillmentStatus, Order, OrderLineItem, PaymentGateway, PaymentTransaction,
+};
+use crate::error::CommerceError;
+use crate::ids::{AmendmentId, OrderId, OrderLineItemId};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// A change to an order's lines.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum OrderEdit {
+    /// Add a new line.
+    AddLine(OrderLineItem),
+    /// Remove an unfulfilled line.
+    RemoveLine(OrderLineItemId),
+    /// Swap an unfulfilled line for another (e.g., a different size).
+    ReplaceLine {
+        line_id: OrderLineItemId,
+        replacement: OrderLineItem,
+    },
+    /// Change a line's quantity. Discount and tax scale with it.
+    SetQuantity {
+        line_id: OrderLineItemId,
+        quantity: i64,
+    },
+    /// Change a line's unit price. Tax scales with the new net amount.
+    AdjustPrice {
+        line_id: OrderLineItemId,
+        unit_price: Money,
+    },
+}
+
+impl OrderEdit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderEdit::AddLine(_) => "add_line",
+            OrderEdit::RemoveLine(_) => "remove_line",
+            OrderEdit::ReplaceLine { .. } => "replace_line",
+            OrderEdit::SetQuantity { .. } => "set_quantity",
+            OrderEdit::AdjustPrice { .. } => "adjust_price",
+        }
+    }
+}
+
+/// Money owed after an edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaymentDelta {
+    /// Total unchanged.
+    None,
+    /// Customer owes more.
+    Charge(Money),
+    /// Customer is owed money back.
+    Refund(Money),
+}
+
+impl PaymentDelta {
+    /// Delta for a total changing from `before` to `after`.
+    pub fn between(before: &Money, after: &Money) -> Result<Self, CommerceError> {
+        let difference = after.try_subtract(before).ok_or(CommerceError::Overflow)?;
+        Ok(if difference.is_positive() {
+            PaymentDelta::Charge(difference)
+        } else if difference.is_negative() {
+            PaymentDelta::Refund(difference.try_abs().ok_or(CommerceError::Overflow)?)
+        } else {
+            PaymentDelta::None
+        })
+    }
+
+    /// Amount to move, if any.
+    pub fn amount(&self) -> Option<Money> {
+        match self {
+            PaymentDelta::None => None,
+            PaymentDelta::Charge(amount) | PaymentDelta::Refund(amount) => Some(*amount),
+        }
+    }
+}
+
+/// Record of an order edit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderAmendment {
+    /// Unique amendment identifier.
+    pub id: AmendmentId,
+    /// Edited order.
+    pub order_id: OrderId,
+    /// What changed.
+    pub edit: OrderEdit,
+    /// Grand total before the edit.
+    pub previous_total: Money,
+    /// Grand total after the edit.
+    pub new_total: Money,
+    /// Money owed either way.
+    pub delta: PaymentDelta,
+    /// Staff note.
+    pub note: Option<String>,
+    /// Processor transaction that settled the delta.
+    pub transaction: Option<PaymentTransaction>,
+    /// Unix timestamp of the edit.
+    pub created_at: i64,
+    /// Unix timestamp the delta was settled.
+    pub settled_at: Option<i64>,
+}
+
+impl OrderAmendment {
+    /// Add a staff note.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Check if the delta has been settled.
+    pub fn is_settled(&self) -> bool {
+        self.settled_at.is_some()
+    }
+
+    /// Charge or refund the delta through the gateway.
+    ///
+    /// Orders that haven't been paid yet settle without a transaction: the
+    /// new total is what gets captured.
+    pub fn settle(
+        &mut self,
+        order: &mut Order,
+        gateway: &dyn PaymentGateway,
+    ) -> Result<(), CommerceError> {
+        if order.id != self.order_id {
+            return Err(CommerceError::ValidationError(format!(
+                "amendment {} belongs to order {}, not {}",
+                self.id, self.order_id, order.id
+            )));
+        }
+        if self.is_settled() {
+            return Err(CommerceError::ValidationError(format!(
+                "amendment {} is already settled",
+                self.id
+            )));
+        }
+        if order.is_paid() {
+            match self.delta {
+                PaymentDelta::None => {}
+                PaymentDelta::Charge(amount) => {
+                    self.transaction = Some(gateway.charge(order, &amount)?);
+                }
+                PaymentDelta::Refund(amount) => {
+                    self.transaction = Some(gateway.refund(order, &amount)?);
+                    order.set_financial_status(FinancialStatus::PartiallyRefunded);
+                }
+            }
+        }
+        self.settled_at = Some(current_timestamp());
+        Ok(())
+    }
+}
+
+impl Order {
+    /// Apply an edit and recompute totals.
+    ///
+    /// Only orders that haven't shipped can be edited, and fulfilled
+    /// quantities can't be removed. Order-level discounts and tax not
+    /// attributed to a line (e.g., shipping tax) carry over unchanged.
+    pub fn apply_edit(&mut self, edit: OrderEdit) -> Result<OrderAmendment, CommerceError> {
+        if !self.status.can_cancel() {
+            return Err(CommerceError::ValidationError(format!(
+                "order {} is {} and can no longer be edited",
+                self.order_number,
+                self.status.as_str()
+            )));
+        }
+
+        let order_discount = self
+            .discount_total
+            .try_subtract(&self.line_sum(|l| &l.discount_amount)?)
+            .ok_or(CommerceError::Overflow)?;
+        let unattributed_tax = self
+            .tax_total
+            .try_subtract(&self.line_sum(|l| &l.tax_amount)?)
+            .ok_or(CommerceError::Overflow)?;
+        let previous_total = self.grand_total;
+
+        let mut line_items = self.line_items.clone();
+        match &edit {
+            OrderEdit::AddLine(line) => {
+                let line = self.prepare_new_line(line, None)?;
+                line_items.push(line);
+            }
+            OrderEdit::RemoveLine(line_id) => {
+                let index = self.editable_line(line_id)?;
+                if line_items.len() == 1 {
+                    return Err(CommerceError::ValidationError(
+                        "cannot remove the only line; cancel the order instead".to_string(),
+                    ));
+                }
+                line_items.remove(index);
+            }
+            OrderEdit::ReplaceLine {
+                line_id,
+                replacement,
+            } => {
+                let index = self.editable_line(line_id)?;
+                line_items[index] = self.prepare_new_line(replacement, Some(line_id))?;
+            }
+            OrderEdit::SetQuantity { line_id, quantity } => {
+                let index = self.line_index(line_id)?;
+                let line = &mut line_items[index];
+                let minimum = line.fulfilled_quantity.max(1);
+                if *quantity < minimum {
+                    return Err(CommerceError::InvalidQuantity(*quantity));
+                }
+                line.discount_amount = pro_rata(&line.discount_amount, *quantity, line.quantity)?;
+                line.tax_amount = pro_rata(&line.tax_amount, *quantity, line.quantity)?;
+                line.total_price = line
+                    .unit_price
+                    .try_multiply(*quantity)
+                    .ok_or(CommerceError::Overflow)?;
+                line.quantity = *quantity;
+            }
+            OrderEdit::AdjustPrice {
+                line_id,
+                unit_price,
+            } => {
+                self.check_currency(unit_price)?;
+                if unit_price.is_negative() {
+                    return Err(CommerceError::ValidationError(
+                        "unit price cannot be negative".to_string(),
+                    ));
+                }
+                let index = self.line_index(line_id)?;
+                let line = &mut line_items[index];
+                let old_net = line
+                    .total_price
+                    .try_subtract(&line.discount_amount)
+                    .ok_or(CommerceError::Overflow)?;
+                line.unit_price = *unit_price;
+                line.total_price = unit_price
+                    .try_multiply(line.quantity)
+                    .ok_or(CommerceError::Overflow)?;
+                if line.discount_amount.amount_cents > line.total_price.amount_cents {
+                    line.discount_amount = line.total_price;
+                }
+                let new_net = line
+                    .total_price
+                    .try_subtract(&line.discount_amount)
+                    .ok_or(CommerceError::Overflow)?;
+                if old_net.is_positive() {
+                    line.tax_amount =
+                        pro_rata(&line.tax_amount, new_net.amount_cents, old_net.amount_cents)?;
+                }
+            }
+        }
+
+        let currency = self.currency;
+        let subtotal = Money::try_sum(line_items.iter().map(|l| &l.total_price), currency);
+        let line_discounts =
+            Money::try_sum(line_items.iter().map(|l| &l.discount_amount), currency);
+        let line_tax = Money::try_sum(line_items.iter().map(|l| &l.tax_amount), currency);
+        let (Some(subtotal), Some(line_discounts), Some(line_tax)) =
+            (subtotal, line_discounts, line_tax)
+        else {
+            return Err(CommerceError::Overflow);
+        };
+        let discount_total = line_discounts
+            .try_add(&order_discount)
+            .ok_or(CommerceError::Overflow)?;
+        let tax_total = line_tax
+            .try_add(&unattributed_tax)
+            .ok_or(CommerceError::Overflow)?;
+        let grand_total = subtotal
+            .try_subtract(&discount_total)
+            .and_then(|t| t.try_add(&self.shipping_total))
+            .and_then(|t| t.try_add(&tax_total))
+            .ok_or(CommerceError::Overflow)?;
+
+        self.line_items = line_items;
+        self.subtotal = subtotal;
+        self.discount_total = discount_total;
+        self.tax_total = tax_total;
+        self.grand_total = grand_total;
+        self.refresh_fulfillment_status();
+
+        let now = current_timestamp();
+        self.updated_at = now;
+        Ok(OrderAmendment {
+            id: AmendmentId::generate(),
+            order_id: self.id.clone(),
+            edit,
+            previous_total,
+            new_total: grand_total,
+            delta: PaymentDelta::between(&previous_total, &grand_total)?,
+            note: None,
+            transaction: None,
+            created_at: now,
+            settled_at: None,
+        })
+    }
+
+    fn line_sum(&self, field: impl Fn(&OrderLineItem) -> &Money) -> Result<Money, CommerceError> {
+        Money::try_sum(self.line_items.iter().map(field), self.currency)
+            .ok_or(CommerceError::Overflow)
+    }
+
+    fn line_index(&self, line_id: &OrderLineItemId) -> Result<usize, CommerceError> {
+        self.line_items
+            .iter()
+            .position(|l| &l.id == line_id)
+            .ok_or_else(|| {
+                CommerceError::ValidationError(format!(
+                    "line {} is not on order {}",
+                    line_id, self.order_number
+                ))
+            })
+    }
+
+    fn editable_line(&self, line_id: &OrderLineItemId) -> Result<usize, CommerceError> {
+        let index = self.line_index(line_id)?;
+        if self.line_items[index].fulfilled_quantity > 0 {
+            return Err(CommerceError::ValidationError(format!(
+                "line {} has already been fulfilled",
+                line_id
+            )));
+        }
+        Ok(index)
+    }
+
+    fn prepare_new_line(
+        &self,
+        line: &OrderLineItem,
+        replacing: Option<&OrderLineItemId>,
+    ) -> Result<OrderLineItem, CommerceError> {
+        if line.quantity <= 0 {
+            return Err(CommerceError::InvalidQuantity(line.quantity));
+        }
+        self.check_currency(&line.unit_price)?;
+        self.check_currency(&line.discount_amount)?;
+        self.check_currency(&line.tax_amount)?;
+        if Some(&line.id) != replacing && self.line_items.iter().any(|l| l.id == line.id) {
+            return Err(CommerceError::ValidationError(format!(
+                "line {} is already on order {}",
+                line.id, self.order_number
+            )));
+        }
+        let mut line = line.clone();
+        line.total_price = line
+            .unit_price
+            .try_multiply(line.quantity)
+            .ok_or(CommerceError::Overflow)?;
+        line.fulfilled_quantity = 0;
+        line.fulfillment_status = FulfillmentStatus::Unfulfilled;
+        Ok(line)
+    }
+
+    fn check_currency(&self, amount: &Money) -> Result<(), CommerceError> {
+        if amount.currency != self.currency {
+            return Err(CommerceError::CurrencyMismatch {
+                expected: self.currency.code().to_string(),
+                got: amount.currency.code().to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkout::fixtures::{self, taxed_line, usd};
+    use crate::checkout::{OrderStatus, PaymentAuthorization, PaymentTransactionKind};
+    use std::cell::RefCell;
+
+    fn order() -> Order {
+        let mut order = Order {
+            status: OrderStatus::Confirmed,
+            financial_status: FinancialStatus::Paid,
+            subtotal: usd(5000),
+            discount_total: usd(900),
+            shipping_total: usd(500),
+            tax_total: usd(408),
+            grand_total: usd(5008),
+            ..fixtures::order(vec![
+                taxed_line("shirt", 2, 2000, 400, 288),
+                taxed_line("mug", 1, 1000, 0, 80),
+            ])
+        };
+        order.shipping_method.rate = usd(500);
+        order
+    }
+
+    #[derive(Default)]
+    struct RecordingGateway {
+        calls: RefCell<Vec<(PaymentTransactionKind, Money)>>,
+    }
+
+    impl RecordingGateway {
+        fn record(&self, kind: PaymentTransactionKind, amount: &Money) -> PaymentTransaction {
+            self.calls.borrow_mut().push((kind, *amount));
+            PaymentTransaction {
+                reference: format!("txn_{}", self.calls.borrow().len()),
+                kind,
+                amount: *amount,
+                processed_at: 0,
+            }
+        }
+    }
+
+    impl PaymentGateway for RecordingGateway {
+        fn capture(
+            &self,
+            _authorization: &PaymentAuthorization,
+            amount: &Money,
+        ) -> Result<PaymentTransaction, CommerceError> {
+            Ok(self.record(PaymentTransactionKind::Capture, amount))
+        }
+
+        fn void(
+            &self,
+            authorization: &PaymentAuthorization,
+        ) -> Result<PaymentTransaction, CommerceError> {
+            Ok(self.record(PaymentTransactionKind::Void, &authorization.amount))
+        }
+
+        fn charge(
+            &self,
+            _order: &Order,
+            amount: &Money,
+        ) -> Result<PaymentTransaction, CommerceError> {
+            Ok(self.record(PaymentTransactionKind::Charge, amount))
+        }
+
+        fn refund(
+            &self,
+            _order: &Order,
+            amount: &Money,
+        ) -> Result<PaymentTransaction, CommerceError> {
+            Ok(self.record(PaymentTransactionKind::Refund, amount))
+        }
+    }
+
+    #[test]
+    fn test_quantity_change_scales_and_refunds() {
+        let mut order = order();
+        let mut amendment = order
+            .apply_edit(OrderEdit::SetQuantity {
+                line_id: OrderLineItemId::new("shirt"),
+                quantity: 1,
+            })
+            .unwrap();
+        // Shirt: 2000 - 200 discount + 144 tax; the 500 order discount and
+        // 40 unattributed tax carry over.
+        assert_eq!(order.subtotal, usd(3000));
+        assert_eq!(order.discount_total, usd(700));
+        assert_eq!(order.tax_total, usd(264));
+        assert_eq!(order.grand_total, usd(3064));
+        assert_eq!(amendment.delta, PaymentDelta::Refund(usd(1944)));
+
+        let gateway = RecordingGateway::default();
+        amendment.settle(&mut order, &gateway).unwrap();
+        assert_eq!(
+            gateway.calls.borrow().as_slice(),
+            &[(PaymentTransactionKind::Refund, usd(1944))]
+        );
+        assert_eq!(order.financial_status, FinancialStatus::PartiallyRefunded);
+        assert!(amendment.settle(&mut order, &gateway).is_err());
+    }
+
+    #[test]
+    fn test_add_replace_and_adjust() {
+        let mut order = order();
+        let mut added = order
+            .apply_edit(OrderEdit::AddLine(taxed_line("hat", 1, 1500, 0, 120)))
+            .unwrap();
+        assert_eq!(added.delta, PaymentDelta::Charge(usd(1620)));
+        let gateway = RecordingGateway::default();
+        added.settle(&mut order, &gateway).unwrap();
+        assert_eq!(added.transaction.as_ref().unwrap().amount, usd(1620));
+
+        assert!(order
+            .apply_edit(OrderEdit::AddLine(taxed_line("hat", 1, 1500, 0, 120)))
+            .is_err());
+        let replaced = order
+            .apply_edit(OrderEdit::ReplaceLine {
+                line_id: OrderLineItemId::new("hat"),
+                replacement: taxed_line("cap", 1, 1500, 0, 120),
+            })
+            .unwrap();
+        assert_eq!(replaced.delta, PaymentDelta::None);
+
+        let adjusted = order
+            .apply_edit(OrderEdit::AdjustPrice {
+                line_id: OrderLineItemId::new("mug"),
+                unit_price: usd(500),
+            })
+            .unwrap();
+        assert_eq!(order.line_items[1].tax_amount, usd(40));
+        assert_eq!(adjusted.delta, PaymentDelta::Refund(usd(540)));
+    }
+
+    #[test]
+    fn test_fulfilled_and_shipped_lines_are_locked() {
+        let mut order = order();
+        order.line_items[0].fulfilled_quantity = 1;
+        assert!(order
+            .apply_edit(OrderEdit::RemoveLine(OrderLineItemId::new("shirt")))
+            .is_err());
+        assert!(order
+            .apply_edit(OrderEdit::SetQuantity {
+                line_id: OrderLineItemId::new("shirt"),
+                quantity: 0,
+            })
+            .is_err());
+        order
+            .apply_edit(OrderEdit::RemoveLine(OrderLineItemId::new("mug")))
+            .unwrap();
+        assert!(order
+            .apply_edit(OrderEdit::RemoveLine(OrderLineItemId::new("shirt")))
+            .is_err());
+
+        order.status = OrderStatus::Shipped;
+        assert!(order
+            .apply_edit(OrderEdit::SetQuantity {
+                line_id: OrderLineItemId::new("shirt"),
+                quantity: 2,
+            })
+            .is_err());
+    }
+}
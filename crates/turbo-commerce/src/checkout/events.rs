@@ -0,0 +1,83 @@
+//! Order lifecycle events.
+//!
+//! Order state transitions append [`OrderEvent`]s to the order's outbox
+//! (`Order::pending_events`). Persist the order with its events, then drain
+//! them with `Order::take_events` and hand them to a dispatcher.
+
+use crate::checkout::{FinancialStatus, FulfillmentStatus, Order, OrderStatus};
+use crate::ids::{OrderId, UserId};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// What happened to an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OrderEventKind {
+    /// Order placed.
+    Created,
+    /// Payment captured.
+    Paid,
+    /// Every line fulfilled.
+    Fulfilled,
+    /// Order cancelled.
+    Cancelled,
+    /// Payment fully or partially refunded.
+    Refunded,
+}
+
+impl OrderEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderEventKind::Created => "order.created",
+            OrderEventKind::Paid => "order.paid",
+            OrderEventKind::Fulfilled => "order.fulfilled",
+            OrderEventKind::Cancelled => "order.cancelled",
+            OrderEventKind::Refunded => "order.refunded",
+        }
+    }
+}
+
+/// An order lifecycle event, with a snapshot of the order's state.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderEvent {
+    /// Unique event identifier (for subscriber deduplication).
+    pub id: String,
+    /// What happened.
+    pub kind: OrderEventKind,
+    /// The order.
+    pub order_id: OrderId,
+    /// Human-readable order number.
+    pub order_number: String,
+    /// Customer user ID (None for guest).
+    pub user_id: Option<UserId>,
+    /// Customer email.
+    pub email: String,
+    /// Order status after the transition.
+    pub status: OrderStatus,
+    /// Payment status after the transition.
+    pub financial_status: FinancialStatus,
+    /// Fulfillment status after the transition.
+    pub fulfillment_status: FulfillmentStatus,
+    /// Order total.
+    pub grand_total: Money,
+    /// Unix timestamp of the transition.
+    pub occurred_at: i64,
+}
+
+impl OrderEvent {
+    /// Build an event from an order's current state.
+    pub fn new(kind: OrderEventKind, order: &Order, occurred_at: i64) -> Self {
+        Self {
+            id: format!("evt_{}", OrderId::generate()),
+            kind,
+            order_id: order.id.clone(),
+            order_number: order.order_number.clone(),
+            user_id: order.user_id.clone(),
+            email: order.email.clone(),
+            status: order.status,
+            financial_status: order.financial_status,
+            fulfillment_status: order.fulfillment_status,
+            grand_total: order.grand_total,
+            occurred_at,
+        }
+    }
+}
@@ -1,7 +1,16 @@
 //! Checkout flow state machine.
-
-use crate::checkout::{Address, ShippingSelection};
-use crate::ids::{CartId, CheckoutId};
+//!
+//! A checkout moves through [`CheckoutStep`]s in order. Entering a step
+//! requires the data the earlier steps collect; a rejected move reports every
+//! unmet [`CheckoutRequirement`]. The flow serializes for KV storage and
+//! re-validates when resumed.
+
+use crate::catalog::FulfillmentPlan;
+use crate::checkout::{
+    Address, Parcel, ShipmentRequest, ShippingMethod, ShippingRateProvider, ShippingSelection,
+};
+use crate::ids::{CartId, CheckoutId, ShippingMethodId};
+use crate::money::Money;
 use crate::CommerceError;
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +32,16 @@ pub enum CheckoutStep {
 }
 
 impl CheckoutStep {
+    /// All steps, in order.
+    pub const ALL: [CheckoutStep; 6] = [
+        CheckoutStep::Cart,
+        CheckoutStep::Information,
+        CheckoutStep::Shipping,
+        CheckoutStep::Payment,
+        CheckoutStep::Review,
+        CheckoutStep::Complete,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             CheckoutStep::Cart => "cart",
@@ -56,6 +75,74 @@ impl CheckoutStep {
             CheckoutStep::Complete => 6,
         }
     }
+
+    /// The step after this one.
+    pub fn next(&self) -> Option<CheckoutStep> {
+        Self::ALL.get(self.number() as usize).copied()
+    }
+
+    /// The step before this one.
+    pub fn previous(&self) -> Option<CheckoutStep> {
+        (self.number() as usize)
+            .checked_sub(2)
+            .and_then(|i| Self::ALL.get(i).copied())
+    }
+}
+
+/// Something a checkout needs before it can enter a step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckoutRequirement {
+    /// No email given.
+    Email,
+    /// Email doesn't look like an address.
+    ValidEmail,
+    /// No shipping address given.
+    ShippingAddress,
+    /// Shipping address is missing these fields.
+    ShippingAddressFields(Vec<String>),
+    /// No shipping method selected.
+    ShippingMethod,
+    /// Separate billing address requested but not given.
+    BillingAddress,
+    /// Billing address is missing these fields.
+    BillingAddressFields(Vec<String>),
+    /// No payment method given.
+    PaymentMethod,
+    /// Payment not authorized.
+    PaymentAuthorization,
+}
+
+impl std::fmt::Display for CheckoutRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckoutRequirement::Email => write!(f, "email is required"),
+            CheckoutRequirement::ValidEmail => write!(f, "email is not valid"),
+            CheckoutRequirement::ShippingAddress => write!(f, "shipping address is required"),
+            CheckoutRequirement::ShippingAddressFields(fields) => {
+                write!(f, "shipping address is missing {}", fields.join(", "))
+            }
+            CheckoutRequirement::ShippingMethod => write!(f, "shipping method is required"),
+            CheckoutRequirement::BillingAddress => write!(f, "billing address is required"),
+            CheckoutRequirement::BillingAddressFields(fields) => {
+                write!(f, "billing address is missing {}", fields.join(", "))
+            }
+            CheckoutRequirement::PaymentMethod => write!(f, "payment method is required"),
+            CheckoutRequirement::PaymentAuthorization => {
+                write!(f, "payment has not been authorized")
+            }
+        }
+    }
+}
+
+/// A successful payment authorization.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PaymentAuthorization {
+    /// Processor reference for the authorization.
+    pub reference: String,
+    /// Amount authorized.
+    pub amount: Money,
+    /// Unix timestamp of the authorization.
+    pub authorized_at: i64,
 }
 
 /// Checkout flow state.
@@ -79,8 +166,17 @@ pub struct CheckoutFlow {
     pub billing_same_as_shipping: bool,
     /// Selected shipping method.
     pub shipping_method: Option<ShippingSelection>,
+    /// Shipping methods quoted for the current address and cart.
+    #[serde(default)]
+    pub shipping_quotes: Vec<ShippingMethod>,
+    /// How the order is split across stock locations.
+    #[serde(default)]
+    pub fulfillment_plan: Option<FulfillmentPlan>,
     /// Payment method identifier/token.
     pub payment_token: Option<String>,
+    /// Authorization for the payment method.
+    #[serde(default)]
+    pub payment_authorization: Option<PaymentAuthorization>,
     /// Unix timestamp of creation.
     pub created_at: i64,
     /// Unix timestamp of last update.
@@ -103,135 +199,193 @@ impl CheckoutFlow {
             billing_address: None,
             billing_same_as_shipping: true,
             shipping_method: None,
+            shipping_quotes: Vec::new(),
+            fulfillment_plan: None,
             payment_token: None,
+            payment_authorization: None,
             created_at: now,
             updated_at: now,
             expires_at: now + 3600, // 1 hour default expiry
         }
     }
 
-    /// Check if checkout can advance to a step.
-    pub fn can_advance_to(&self, step: CheckoutStep) -> bool {
-        match step {
-            CheckoutStep::Cart => true,
-            CheckoutStep::Information => true,
-            CheckoutStep::Shipping => self.email.is_some(),
-            CheckoutStep::Payment => {
-                self.email.is_some()
-                    && self
-                        .shipping_address
-                        .as_ref()
-                        .map(|a| a.is_complete())
-                        .unwrap_or(false)
-                    && self.shipping_method.is_some()
+    /// Requirements for entering a step that aren't met yet.
+    ///
+    /// Each step requires everything the steps before it required.
+    pub fn requirements_for(&self, step: CheckoutStep) -> Vec<CheckoutRequirement> {
+        let mut unmet = Vec::new();
+        if step.number() >= CheckoutStep::Shipping.number() {
+            match self.email.as_deref() {
+                None => unmet.push(CheckoutRequirement::Email),
+                Some(email) if !is_valid_email(email) => {
+                    unmet.push(CheckoutRequirement::ValidEmail)
+                }
+                Some(_) => {}
+            }
+        }
+        if step.number() >= CheckoutStep::Payment.number() {
+            match self.shipping_address {
+                None => unmet.push(CheckoutRequirement::ShippingAddress),
+                Some(ref address) if !address.is_complete() => {
+                    unmet.push(CheckoutRequirement::ShippingAddressFields(
+                        address
+                            .missing_fields()
+                            .into_iter()
+                            .map(String::from)
+                            .collect(),
+                    ))
+                }
+                Some(_) => {}
             }
-            CheckoutStep::Review => {
-                self.can_advance_to(CheckoutStep::Payment) && self.payment_token.is_some()
+            if self.shipping_method.is_none() {
+                unmet.push(CheckoutRequirement::ShippingMethod);
             }
-            CheckoutStep::Complete => self.can_advance_to(CheckoutStep::Review),
         }
+        if step.number() >= CheckoutStep::Review.number() {
+            if !self.billing_same_as_shipping {
+                match self.billing_address {
+                    None => unmet.push(CheckoutRequirement::BillingAddress),
+                    Some(ref address) if !address.is_complete() => {
+                        unmet.push(CheckoutRequirement::BillingAddressFields(
+                            address
+                                .missing_fields()
+                                .into_iter()
+                                .map(String::from)
+                                .collect(),
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+            if self.payment_token.is_none() {
+                unmet.push(CheckoutRequirement::PaymentMethod);
+            }
+        }
+        if step == CheckoutStep::Complete && self.payment_authorization.is_none() {
+            unmet.push(CheckoutRequirement::PaymentAuthorization);
+        }
+        unmet
     }
 
-    /// Advance to the next step.
-    pub fn advance(&mut self) -> Result<CheckoutStep, CommerceError> {
-        let next = match self.step {
-            CheckoutStep::Cart => CheckoutStep::Information,
-            CheckoutStep::Information => CheckoutStep::Shipping,
-            CheckoutStep::Shipping => CheckoutStep::Payment,
-            CheckoutStep::Payment => CheckoutStep::Review,
-            CheckoutStep::Review => CheckoutStep::Complete,
-            CheckoutStep::Complete => {
-                return Err(CommerceError::InvalidCheckoutTransition {
-                    from: "complete".to_string(),
-                    to: "none".to_string(),
-                })
-            }
-        };
+    /// Check if checkout can advance to a step.
+    pub fn can_advance_to(&self, step: CheckoutStep) -> bool {
+        self.requirements_for(step).is_empty()
+    }
 
-        if !self.can_advance_to(next) {
-            return Err(CommerceError::CheckoutIncomplete(
-                self.missing_for_step(next).join(", "),
-            ));
+    /// Move to a step.
+    ///
+    /// Allowed moves are: back to any earlier step, forward one step, or
+    /// forward to a step reached before (when every step in between was
+    /// completed). Moving forward checks the target's requirements. A
+    /// completed or expired checkout can't move.
+    pub fn transition_to(&mut self, step: CheckoutStep) -> Result<(), CommerceError> {
+        let now = current_timestamp();
+        if self.step == CheckoutStep::Complete && step != CheckoutStep::Complete {
+            return Err(self.invalid_transition(step));
+        }
+        if self.is_expired_at(now) {
+            return Err(CommerceError::CheckoutExpired(self.id.to_string()));
+        }
+        if step == self.step {
+            return Ok(());
         }
 
-        if !self.completed_steps.contains(&self.step) {
-            self.completed_steps.push(self.step);
+        if step.number() > self.step.number() {
+            let skipped = CheckoutStep::ALL
+                .iter()
+                .filter(|s| s.number() > self.step.number() && s.number() < step.number())
+                .any(|s| !self.completed_steps.contains(s));
+            if skipped {
+                return Err(self.invalid_transition(step));
+            }
+            let requirements = self.requirements_for(step);
+            if !requirements.is_empty() {
+                return Err(CommerceError::CheckoutRequirementsUnmet {
+                    step: step.as_str().to_string(),
+                    requirements,
+                });
+            }
+            for s in CheckoutStep::ALL {
+                if s.number() >= self.step.number()
+                    && s.number() < step.number()
+                    && !self.completed_steps.contains(&s)
+                {
+                    self.completed_steps.push(s);
+                }
+            }
         }
-        self.step = next;
-        self.updated_at = current_timestamp();
 
+        self.step = step;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Advance to the next step.
+    pub fn advance(&mut self) -> Result<CheckoutStep, CommerceError> {
+        let next = self
+            .step
+            .next()
+            .ok_or_else(|| self.invalid_transition(self.step))?;
+        self.transition_to(next)?;
         Ok(next)
     }
 
     /// Go back to a previous step.
     pub fn go_back(&mut self) -> Result<CheckoutStep, CommerceError> {
-        let prev = match self.step {
-            CheckoutStep::Cart => {
-                return Err(CommerceError::InvalidCheckoutTransition {
-                    from: "cart".to_string(),
-                    to: "none".to_string(),
-                })
-            }
-            CheckoutStep::Information => CheckoutStep::Cart,
-            CheckoutStep::Shipping => CheckoutStep::Information,
-            CheckoutStep::Payment => CheckoutStep::Shipping,
-            CheckoutStep::Review => CheckoutStep::Payment,
-            CheckoutStep::Complete => CheckoutStep::Review,
-        };
-
-        self.step = prev;
-        self.updated_at = current_timestamp();
-
+        let prev = self
+            .step
+            .previous()
+            .ok_or_else(|| self.invalid_transition(self.step))?;
+        self.transition_to(prev)?;
         Ok(prev)
     }
 
     /// Go to a specific step (if allowed).
     pub fn go_to(&mut self, step: CheckoutStep) -> Result<(), CommerceError> {
-        // Can go back to any completed step or the current step
-        if step == self.step || self.completed_steps.contains(&step) {
-            self.step = step;
-            self.updated_at = current_timestamp();
-            Ok(())
-        } else if self.can_advance_to(step) && step.number() == self.step.number() + 1 {
-            self.advance()?;
-            Ok(())
-        } else {
-            Err(CommerceError::InvalidCheckoutTransition {
-                from: self.step.as_str().to_string(),
-                to: step.as_str().to_string(),
-            })
+        self.transition_to(step)
+    }
+
+    fn invalid_transition(&self, to: CheckoutStep) -> CommerceError {
+        CommerceError::InvalidCheckoutTransition {
+            from: self.step.as_str().to_string(),
+            to: to.as_str().to_string(),
         }
     }
 
-    /// Get what's missing to advance to a step.
-    fn missing_for_step(&self, step: CheckoutStep) -> Vec<&'static str> {
-        let mut missing = Vec::new();
-        match step {
-            CheckoutStep::Shipping => {
-                if self.email.is_none() {
-                    missing.push("email");
-                }
-            }
-            CheckoutStep::Payment => {
-                if self.email.is_none() {
-                    missing.push("email");
-                }
-                if self.shipping_address.is_none() {
-                    missing.push("shipping address");
-                }
-                if self.shipping_method.is_none() {
-                    missing.push("shipping method");
-                }
-            }
-            CheckoutStep::Review => {
-                missing.extend(self.missing_for_step(CheckoutStep::Payment));
-                if self.payment_token.is_none() {
-                    missing.push("payment method");
+    /// Serialize the checkout for storage (e.g., in KV under [`CheckoutFlow::state_key`]).
+    pub fn save_state(&self) -> Result<String, CommerceError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Restore a saved checkout.
+    ///
+    /// Fails if the checkout has expired. If the saved step's requirements
+    /// no longer hold (e.g., an older version saved an unvalidated step),
+    /// the checkout resumes at the furthest step that is still valid.
+    pub fn resume(state: &str, now: i64) -> Result<Self, CommerceError> {
+        serde_json::from_str::<CheckoutFlow>(state)?.revalidate(now)
+    }
+
+    fn revalidate(mut self, now: i64) -> Result<Self, CommerceError> {
+        if self.is_expired_at(now) {
+            return Err(CommerceError::CheckoutExpired(self.id.to_string()));
+        }
+        if self.step != CheckoutStep::Complete {
+            while !self.can_advance_to(self.step) {
+                match self.step.previous() {
+                    Some(prev) => self.step = prev,
+                    None => break,
                 }
             }
-            _ => {}
+            let step = self.step;
+            self.completed_steps.retain(|s| s.number() < step.number());
         }
-        missing
+        Ok(self)
+    }
+
+    /// Storage key for a checkout.
+    pub fn state_key(id: &CheckoutId) -> String {
+        format!("checkout:{}", id)
     }
 
     /// Set the customer email.
@@ -268,12 +422,100 @@ impl CheckoutFlow {
         self.updated_at = current_timestamp();
     }
 
+    /// Quote shipping for the current address.
+    ///
+    /// Replaces any earlier quotes. A selected method that is no longer
+    /// quoted is cleared, and a still-quoted one picks up its new rate.
+    pub fn quote_shipping(
+        &mut self,
+        provider: &dyn ShippingRateProvider,
+        subtotal: Money,
+        parcels: Vec<Parcel>,
+    ) -> Result<&[ShippingMethod], CommerceError> {
+        let destination = self.shipping_address.clone().ok_or_else(|| {
+            CommerceError::ValidationError("shipping address is required".to_string())
+        })?;
+        let request = ShipmentRequest {
+            destination,
+            subtotal,
+            parcels,
+        };
+        self.shipping_quotes = provider.quote(&request)?;
+        self.shipping_method = self.shipping_method.take().and_then(|selected| {
+            self.shipping_quotes
+                .iter()
+                .find(|m| m.id == selected.method_id)
+                .map(ShippingSelection::from_method)
+        });
+        self.updated_at = current_timestamp();
+        Ok(&self.shipping_quotes)
+    }
+
+    /// Select one of the quoted shipping methods.
+    pub fn select_shipping_quote(
+        &mut self,
+        method_id: &ShippingMethodId,
+    ) -> Result<(), CommerceError> {
+        let method = self
+            .shipping_quotes
+            .iter()
+            .find(|m| &m.id == method_id)
+            .ok_or_else(|| {
+                CommerceError::ValidationError(format!(
+                    "shipping method {} was not quoted",
+                    method_id
+                ))
+            })?;
+        self.shipping_method = Some(ShippingSelection::from_method(method));
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    /// Set the fulfillment plan (which locations ship which items).
+    pub fn set_fulfillment_plan(&mut self, plan: FulfillmentPlan) {
+        self.fulfillment_plan = Some(plan);
+        self.updated_at = current_timestamp();
+    }
+
+    /// Number of shipments the order will arrive in (1 if no plan is set).
+    pub fn shipment_count(&self) -> usize {
+        self.fulfillment_plan
+            .as_ref()
+            .map(|p| p.shipment_count().max(1))
+            .unwrap_or(1)
+    }
+
     /// Set the payment token.
+    ///
+    /// Clears any authorization, which belonged to the previous token.
     pub fn set_payment_token(&mut self, token: impl Into<String>) {
         self.payment_token = Some(token.into());
+        self.payment_authorization = None;
         self.updated_at = current_timestamp();
     }
 
+    /// Record that the payment processor authorized the payment.
+    pub fn authorize_payment(
+        &mut self,
+        reference: impl Into<String>,
+        amount: Money,
+    ) -> Result<(), CommerceError> {
+        if self.payment_token.is_none() {
+            return Err(CommerceError::CheckoutRequirementsUnmet {
+                step: self.step.as_str().to_string(),
+                requirements: vec![CheckoutRequirement::PaymentMethod],
+            });
+        }
+        let now = current_timestamp();
+        self.payment_authorization = Some(PaymentAuthorization {
+            reference: reference.into(),
+            amount,
+            authorized_at: now,
+        });
+        self.updated_at = now;
+        Ok(())
+    }
+
     /// Get the effective billing address.
     pub fn effective_billing_address(&self) -> Option<&Address> {
         if self.billing_same_as_shipping {
@@ -290,7 +532,12 @@ impl CheckoutFlow {
 
     /// Check if checkout has expired.
     pub fn is_expired(&self) -> bool {
-        current_timestamp() > self.expires_at
+        self.is_expired_at(current_timestamp())
+    }
+
+    /// Check if checkout has expired at a timestamp.
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        now > self.expires_at
     }
 
     /// Get progress percentage.
@@ -299,6 +546,47 @@ impl CheckoutFlow {
     }
 }
 
+/// Loose email check: something before and after an `@`, and a dot in the domain.
+fn is_valid_email(email: &str) -> bool {
+    match email.trim().split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain
+                    .split_once('.')
+                    .map(|(host, tld)| !host.is_empty() && !tld.is_empty())
+                    .unwrap_or(false)
+        }
+        None => false,
+    }
+}
+
+#[cfg(feature = "storage")]
+mod storage {
+    use super::*;
+    use turbo_cache::Cache;
+
+    impl CheckoutFlow {
+        /// Save the checkout to the cache.
+        pub fn save(&self, cache: &Cache) -> Result<(), CommerceError> {
+            cache.set(&Self::state_key(&self.id), self)?;
+            Ok(())
+        }
+
+        /// Load and resume a checkout from the cache.
+        pub fn load(
+            cache: &Cache,
+            id: &CheckoutId,
+            now: i64,
+        ) -> Result<Option<Self>, CommerceError> {
+            cache
+                .get::<CheckoutFlow>(&Self::state_key(id))?
+                .map(|flow| flow.revalidate(now))
+                .transpose()
+        }
+    }
+}
+
 /// Get current Unix timestamp.
 fn current_timestamp() -> i64 {
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -354,4 +642,157 @@ mod tests {
         assert!(flow.go_back().is_ok());
         assert_eq!(flow.step, CheckoutStep::Information);
     }
+
+    #[test]
+    fn test_quote_and_select_shipping() {
+        use crate::checkout::{Carrier, RateBasis, RateTable, ShippingZone, TableRateProvider};
+        use crate::money::Currency;
+
+        let usd = |cents| Money::new(cents, Currency::USD);
+        let provider = TableRateProvider::new().with_carrier(
+            Carrier::new("UPS").with_rate_table(
+                RateTable::new(
+                    "ground",
+                    "Ground",
+                    ShippingZone::new("US", ["US"]),
+                    RateBasis::Weight,
+                )
+                .with_tier(0, None, usd(599))
+                .with_free_over(usd(10000)),
+            ),
+        );
+        let mut flow = CheckoutFlow::new(CartId::new("cart-123"));
+        assert!(flow
+            .quote_shipping(&provider, usd(5000), vec![Parcel::new(500)])
+            .is_err());
+
+        flow.set_shipping_address(Address::new(
+            "A",
+            "B",
+            "1 Main St",
+            "City",
+            "US",
+            "US",
+            "12345",
+        ));
+        flow.quote_shipping(&provider, usd(5000), vec![Parcel::new(500)])
+            .unwrap();
+        let ground = ShippingMethodId::new("ups:ground");
+        flow.select_shipping_quote(&ground).unwrap();
+        assert_eq!(flow.shipping_method.as_ref().unwrap().rate, usd(599));
+        assert!(flow
+            .select_shipping_quote(&ShippingMethodId::new("fedex:air"))
+            .is_err());
+
+        // Requoting refreshes the selected rate.
+        flow.quote_shipping(&provider, usd(12000), vec![Parcel::new(500)])
+            .unwrap();
+        assert!(flow.shipping_method.as_ref().unwrap().rate.is_zero());
+    }
+
+    fn ready_for_review() -> CheckoutFlow {
+        let mut flow = CheckoutFlow::new(CartId::new("cart-123"));
+        flow.set_email("test@example.com");
+        flow.set_shipping_address(Address::new(
+            "A",
+            "B",
+            "1 Main St",
+            "City",
+            "US",
+            "US",
+            "12345",
+        ));
+        flow.set_shipping_method(ShippingSelection {
+            method_id: ShippingMethodId::new("ground"),
+            method_name: "Ground".to_string(),
+            rate: Money::new(599, crate::money::Currency::USD),
+            carrier: None,
+            delivery_estimate: None,
+        });
+        flow.set_payment_token("tok_123");
+        flow
+    }
+
+    #[test]
+    fn test_rejection_lists_requirements() {
+        let mut flow = CheckoutFlow::new(CartId::new("cart-123"));
+        flow.set_email("not-an-email");
+        flow.set_shipping_address(Address::new("A", "", "1 Main St", "City", "US", "US", ""));
+        flow.step = CheckoutStep::Shipping;
+        flow.completed_steps = vec![CheckoutStep::Cart, CheckoutStep::Information];
+
+        match flow.advance() {
+            Err(CommerceError::CheckoutRequirementsUnmet { step, requirements }) => {
+                assert_eq!(step, "payment");
+                assert_eq!(
+                    requirements,
+                    vec![
+                        CheckoutRequirement::ValidEmail,
+                        CheckoutRequirement::ShippingAddressFields(vec![
+                            "last_name".to_string(),
+                            "zip".to_string()
+                        ]),
+                        CheckoutRequirement::ShippingMethod,
+                    ]
+                );
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(flow.step, CheckoutStep::Shipping);
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut flow = ready_for_review();
+        assert!(matches!(
+            flow.go_to(CheckoutStep::Payment),
+            Err(CommerceError::InvalidCheckoutTransition { .. })
+        ));
+        for _ in 0..4 {
+            flow.advance().unwrap();
+        }
+        assert_eq!(flow.step, CheckoutStep::Review);
+
+        // Jump back, then forward again over completed steps.
+        flow.go_to(CheckoutStep::Information).unwrap();
+        flow.go_to(CheckoutStep::Review).unwrap();
+
+        assert!(matches!(
+            flow.advance(),
+            Err(CommerceError::CheckoutRequirementsUnmet { .. })
+        ));
+        flow.authorize_payment("auth_1", Money::new(5599, crate::money::Currency::USD))
+            .unwrap();
+        flow.advance().unwrap();
+        assert!(flow.is_complete());
+        assert!(flow.go_back().is_err());
+
+        let mut expired = ready_for_review();
+        expired.expires_at = 0;
+        assert!(matches!(
+            expired.advance(),
+            Err(CommerceError::CheckoutExpired(_))
+        ));
+    }
+
+    #[test]
+    fn test_resume_revalidates() {
+        let mut flow = ready_for_review();
+        for _ in 0..4 {
+            flow.advance().unwrap();
+        }
+        let state = flow.save_state().unwrap();
+        let now = flow.updated_at;
+        assert_eq!(CheckoutFlow::resume(&state, now).unwrap(), flow);
+
+        flow.shipping_method = None;
+        let resumed = CheckoutFlow::resume(&flow.save_state().unwrap(), now).unwrap();
+        assert_eq!(resumed.step, CheckoutStep::Shipping);
+        assert!(!resumed.completed_steps.contains(&CheckoutStep::Payment));
+
+        assert!(matches!(
+            CheckoutFlow::resume(&state, flow.expires_at + 1),
+            Err(CommerceError::CheckoutExpired(_))
+        ));
+    }
 }
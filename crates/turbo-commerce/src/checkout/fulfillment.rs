@@ -0,0 +1,491 @@
+//! Shipments and partial fulfillment.
+//!
+//! An order can ship in several [`Fulfillment`]s, each covering some
+//! quantity of some lines and optionally carrying a [`Shipment`] with the
+//! carrier and tracking number. Creating or cancelling a fulfillment keeps
+//! the order's fulfilled quantities and `FulfillmentStatus` in step.
+
+use crate::checkout::{FinancialStatus, Order, OrderStatus};
+use crate::error::CommerceError;
+use crate::ids::{FulfillmentId, LocationId, OrderId, OrderLineItemId, VariantId};
+use serde::{Deserialize, Serialize};
+
+/// Carrier tracking status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum ShipmentStatus {
+    /// Label printed, not yet with the carrier.
+    #[default]
+    LabelCreated,
+    /// Carrier has the parcel.
+    InTransit,
+    /// On the delivery vehicle.
+    OutForDelivery,
+    /// Delivered to the recipient.
+    Delivered,
+    /// Delivery problem (failed attempt, damage, address issue).
+    Exception,
+}
+
+impl ShipmentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShipmentStatus::LabelCreated => "label_created",
+            ShipmentStatus::InTransit => "in_transit",
+            ShipmentStatus::OutForDelivery => "out_for_delivery",
+            ShipmentStatus::Delivered => "delivered",
+            ShipmentStatus::Exception => "exception",
+        }
+    }
+}
+
+/// A carrier scan or status update.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrackingEvent {
+    /// Status after the update.
+    pub status: ShipmentStatus,
+    /// Carrier description (e.g., "Arrived at facility").
+    pub description: Option<String>,
+    /// Unix timestamp of the update.
+    pub occurred_at: i64,
+}
+
+/// A tracked parcel.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Shipment {
+    /// Carrier name.
+    pub carrier: String,
+    /// Carrier service level (e.g., "Ground").
+    pub service: Option<String>,
+    /// Tracking number.
+    pub tracking_number: String,
+    /// Public tracking page.
+    pub tracking_url: Option<String>,
+    /// Latest status.
+    pub status: ShipmentStatus,
+    /// Tracking history, oldest first.
+    pub events: Vec<TrackingEvent>,
+    /// Unix timestamp the carrier picked it up.
+    pub shipped_at: Option<i64>,
+    /// Unix timestamp of delivery.
+    pub delivered_at: Option<i64>,
+}
+
+impl Shipment {
+    /// Create a shipment with a tracking number.
+    pub fn new(carrier: impl Into<String>, tracking_number: impl Into<String>) -> Self {
+        Self {
+            carrier: carrier.into(),
+            service: None,
+            tracking_number: tracking_number.into(),
+            tracking_url: None,
+            status: ShipmentStatus::LabelCreated,
+            events: Vec::new(),
+            shipped_at: None,
+            delivered_at: None,
+        }
+    }
+
+    /// Set the service level.
+    pub fn with_service(mut self, service: impl Into<String>) -> Self {
+        self.service = Some(service.into());
+        self
+    }
+
+    /// Set the tracking page URL.
+    pub fn with_tracking_url(mut self, url: impl Into<String>) -> Self {
+        self.tracking_url = Some(url.into());
+        self
+    }
+
+    /// Check if the carrier has picked it up.
+    pub fn is_shipped(&self) -> bool {
+        self.status != ShipmentStatus::LabelCreated
+    }
+
+    /// Check if delivered.
+    pub fn is_delivered(&self) -> bool {
+        self.status == ShipmentStatus::Delivered
+    }
+
+    /// Record a tracking update. Delivered shipments accept no further updates.
+    pub fn record(
+        &mut self,
+        status: ShipmentStatus,
+        description: Option<String>,
+        occurred_at: i64,
+    ) -> Result<(), CommerceError> {
+        if self.is_delivered() {
+            return Err(CommerceError::ValidationError(format!(
+                "shipment {} is already delivered",
+                self.tracking_number
+            )));
+        }
+        if status != ShipmentStatus::LabelCreated && self.shipped_at.is_none() {
+            self.shipped_at = Some(occurred_at);
+        }
+        if status == ShipmentStatus::Delivered {
+            self.delivered_at = Some(occurred_at);
+        }
+        self.status = status;
+        self.events.push(TrackingEvent {
+            status,
+            description,
+            occurred_at,
+        });
+        Ok(())
+    }
+}
+
+/// Quantity of an order line included in a fulfillment.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FulfillmentLine {
+    /// Order line.
+    pub order_line_item_id: OrderLineItemId,
+    /// Variant shipped.
+    pub variant_id: VariantId,
+    /// Quantity shipped.
+    pub quantity: i64,
+}
+
+/// A set of order lines shipped together.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Fulfillment {
+    /// Unique fulfillment identifier.
+    pub id: FulfillmentId,
+    /// Order being fulfilled.
+    pub order_id: OrderId,
+    /// Location shipped from.
+    pub location_id: Option<LocationId>,
+    /// Lines and quantities included.
+    pub lines: Vec<FulfillmentLine>,
+    /// Carrier tracking (None for untracked or hand delivery).
+    pub shipment: Option<Shipment>,
+    /// Unix timestamp of creation.
+    pub created_at: i64,
+    /// Unix timestamp of last update.
+    pub updated_at: i64,
+    /// Unix timestamp when cancelled (if applicable).
+    pub cancelled_at: Option<i64>,
+}
+
+impl Fulfillment {
+    /// Fulfill `(order line, quantity)` items.
+    ///
+    /// The order must be paid or its payment authorized, and each quantity
+    /// must fit in the line's remaining-to-fulfill quantity. Updates the order's fulfilled quantities and fulfillment status, and
+    /// marks the order shipped once every line is fulfilled.
+    pub fn create(
+        order: &mut Order,
+        items: Vec<(OrderLineItemId, i64)>,
+    ) -> Result<Self, CommerceError> {
+        if matches!(order.status, OrderStatus::Cancelled | OrderStatus::Refunded) {
+            return Err(CommerceError::ValidationError(format!(
+                "order {} is {}",
+                order.order_number,
+                order.status.as_str()
+            )));
+        }
+        if !matches!(
+            order.financial_status,
+            FinancialStatus::Authorized
+                | FinancialStatus::Paid
+                | FinancialStatus::PartiallyRefunded
+        ) {
+            return Err(CommerceError::ValidationError(format!(
+                "order {} payment is {}",
+                order.order_number,
+                order.financial_status.as_str()
+            )));
+        }
+        if items.is_empty() {
+            return Err(CommerceError::ValidationError(
+                "a fulfillment needs at least one item".to_string(),
+            ));
+        }
+
+        let mut lines: Vec<FulfillmentLine> = Vec::new();
+        for (line_id, quantity) in items {
+            if quantity <= 0 {
+                return Err(CommerceError::InvalidQuantity(quantity));
+            }
+            let line = order
+                .line_items
+                .iter()
+                .find(|l| l.id == line_id)
+                .ok_or_else(|| {
+                    CommerceError::ValidationError(format!(
+                        "line {} is not on order {}",
+                        line_id, order.order_number
+                    ))
+                })?;
+            match lines.iter_mut().find(|l| l.order_line_item_id == line_id) {
+                Some(existing) => existing.quantity += quantity,
+                None => lines.push(FulfillmentLine {
+                    order_line_item_id: line_id,
+                    variant_id: line.variant_id.clone(),
+                    quantity,
+                }),
+            }
+        }
+        for fulfillment_line in &lines {
+            let remaining = order
+                .line_items
+                .iter()
+                .find(|l| l.id == fulfillment_line.order_line_item_id)
+                .map(|l| l.unfulfilled_quantity())
+                .unwrap_or(0);
+            if fulfillment_line.quantity > remaining {
+                return Err(CommerceError::QuantityExceedsLimit(
+                    fulfillment_line.quantity,
+                    remaining,
+                ));
+            }
+        }
+
+        for fulfillment_line in &lines {
+            if let Some(line) = order
+                .line_items
+                .iter_mut()
+                .find(|l| l.id == fulfillment_line.order_line_item_id)
+            {
+                line.fulfilled_quantity += fulfillment_line.quantity;
+            }
+        }
+        order.refresh_fulfillment_status();
+        if order.is_fulfilled() && order.status.can_cancel() {
+            order.set_status(OrderStatus::Shipped);
+        }
+
+        let now = current_timestamp();
+        Ok(Self {
+            id: FulfillmentId::generate(),
+            order_id: order.id.clone(),
+            location_id: None,
+            lines,
+            shipment: None,
+            created_at: now,
+            updated_at: now,
+            cancelled_at: None,
+        })
+    }
+
+    /// Fulfill everything the order still has to ship.
+    pub fn create_remaining(order: &mut Order) -> Result<Self, CommerceError> {
+        let items = order.remaining_to_fulfill();
+        Self::create(order, items)
+    }
+
+    /// Set the location shipped from.
+    pub fn with_location(mut self, location_id: LocationId) -> Self {
+        self.location_id = Some(location_id);
+        self
+    }
+
+    /// Attach carrier tracking.
+    pub fn with_shipment(mut self, shipment: Shipment) -> Self {
+        self.shipment = Some(shipment);
+        self
+    }
+
+    /// Check if cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled_at.is_some()
+    }
+
+    /// Total quantity in this fulfillment.
+    pub fn item_count(&self) -> i64 {
+        self.lines.iter().map(|l| l.quantity).sum()
+    }
+
+    /// Record a tracking update on the shipment.
+    ///
+    /// Once every active fulfillment in `others` (the order's other
+    /// fulfillments) is delivered too, the order is marked delivered.
+    pub fn record_tracking(
+        &mut self,
+        order: &mut Order,
+        others: &[Fulfillment],
+        status: ShipmentStatus,
+        description: Option<String>,
+        occurred_at: i64,
+    ) -> Result<(), CommerceError> {
+        self.check_order(order)?;
+        if self.is_cancelled() {
+            return Err(CommerceError::ValidationError(format!(
+                "fulfillment {} is cancelled",
+                self.id
+            )));
+        }
+        let shipment = self.shipment.as_mut().ok_or_else(|| {
+            CommerceError::ValidationError(format!("fulfillment {} has no shipment", self.id))
+        })?;
+        shipment.record(status, description, occurred_at)?;
+        self.updated_at = current_timestamp();
+
+        let all_delivered = others
+            .iter()
+            .filter(|f| f.id != self.id && f.order_id == self.order_id && !f.is_cancelled())
+            .chain(std::iter::once(&*self))
+            .all(|f| f.shipment.as_ref().is_some_and(|s| s.is_delivered()));
+        if all_delivered && order.is_fulfilled() && order.status == OrderStatus::Shipped {
+            order.set_status(OrderStatus::Delivered);
+        }
+        Ok(())
+    }
+
+    /// Cancel the fulfillment, returning its quantities to the order.
+    ///
+    /// Only possible before the carrier picks the shipment up.
+    pub fn cancel(&mut self, order: &mut Order) -> Result<(), CommerceError> {
+        self.check_order(order)?;
+        if self.is_cancelled() {
+            return Err(CommerceError::ValidationError(format!(
+                "fulfillment {} is already cancelled",
+                self.id
+            )));
+        }
+        if self.shipment.as_ref().is_some_and(|s| s.is_shipped()) {
+            return Err(CommerceError::ValidationError(format!(
+                "fulfillment {} has already shipped",
+                self.id
+            )));
+        }
+        for fulfillment_line in &self.lines {
+            if let Some(line) = order
+                .line_items
+                .iter_mut()
+                .find(|l| l.id == fulfillment_line.order_line_item_id)
+            {
+                line.fulfilled_quantity =
+                    (line.fulfilled_quantity - fulfillment_line.quantity).max(0);
+            }
+        }
+        order.refresh_fulfillment_status();
+        if order.status == OrderStatus::Shipped && !order.is_fulfilled() {
+            order.set_status(OrderStatus::Processing);
+        }
+        let now = current_timestamp();
+        self.cancelled_at = Some(now);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    fn check_order(&self, order: &Order) -> Result<(), CommerceError> {
+        if order.id != self.order_id {
+            return Err(CommerceError::ValidationError(format!(
+                "fulfillment {} belongs to order {}, not {}",
+                self.id, self.order_id, order.id
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkout::fixtures::{self, line};
+    use crate::checkout::{FulfillmentStatus, OrderEventKind};
+
+    fn order() -> Order {
+        Order {
+            status: OrderStatus::Processing,
+            financial_status: FinancialStatus::Paid,
+            ..fixtures::order(vec![line("shirt", 3, 1000), line("mug", 1, 1000)])
+        }
+    }
+
+    fn shirt() -> OrderLineItemId {
+        OrderLineItemId::new("shirt")
+    }
+
+    #[test]
+    fn test_partial_then_full_fulfillment() {
+        let mut order = order();
+        let mut first = Fulfillment::create(&mut order, vec![(shirt(), 2)])
+            .unwrap()
+            .with_shipment(Shipment::new("UPS", "1Z999"));
+        assert_eq!(
+            order.fulfillment_status,
+            FulfillmentStatus::PartiallyFulfilled
+        );
+        assert_eq!(
+            order.line_items[0].fulfillment_status,
+            FulfillmentStatus::PartiallyFulfilled
+        );
+        assert_eq!(
+            order.remaining_to_fulfill(),
+            vec![(shirt(), 1), (OrderLineItemId::new("mug"), 1)]
+        );
+        assert!(Fulfillment::create(&mut order, vec![(shirt(), 2)]).is_err());
+
+        let mut unpaid = self::order();
+        unpaid.financial_status = FinancialStatus::Pending;
+        assert!(Fulfillment::create(&mut unpaid, vec![(shirt(), 1)]).is_err());
+        assert_eq!(unpaid.line_items[0].fulfilled_quantity, 0);
+
+        let mut second = Fulfillment::create_remaining(&mut order)
+            .unwrap()
+            .with_shipment(Shipment::new("USPS", "9400"));
+        assert_eq!(second.item_count(), 2);
+        assert!(order.is_fulfilled());
+        assert!(order.remaining_to_fulfill().is_empty());
+        assert_eq!(order.status, OrderStatus::Shipped);
+        assert_eq!(order.take_events()[0].kind, OrderEventKind::Fulfilled);
+
+        first
+            .record_tracking(
+                &mut order,
+                std::slice::from_ref(&second),
+                ShipmentStatus::Delivered,
+                None,
+                100,
+            )
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Shipped);
+        second
+            .record_tracking(
+                &mut order,
+                std::slice::from_ref(&first),
+                ShipmentStatus::Delivered,
+                Some("Left at door".to_string()),
+                200,
+            )
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Delivered);
+        assert_eq!(second.shipment.as_ref().unwrap().delivered_at, Some(200));
+    }
+
+    #[test]
+    fn test_cancel_before_pickup() {
+        let mut order = order();
+        let mut fulfillment = Fulfillment::create_remaining(&mut order)
+            .unwrap()
+            .with_shipment(Shipment::new("UPS", "1Z999"));
+        assert_eq!(order.status, OrderStatus::Shipped);
+
+        fulfillment.cancel(&mut order).unwrap();
+        assert_eq!(order.fulfillment_status, FulfillmentStatus::Unfulfilled);
+        assert_eq!(order.status, OrderStatus::Processing);
+        assert_eq!(order.remaining_to_fulfill().len(), 2);
+        assert!(fulfillment.cancel(&mut order).is_err());
+
+        let mut shipped = Fulfillment::create(&mut order, vec![(shirt(), 1)])
+            .unwrap()
+            .with_shipment(Shipment::new("UPS", "1Z998"));
+        shipped
+            .record_tracking(&mut order, &[], ShipmentStatus::InTransit, None, 50)
+            .unwrap();
+        assert!(shipped.cancel(&mut order).is_err());
+        assert_eq!(order.line_items[0].fulfilled_quantity, 1);
+    }
+}
@@ -1,13 +1,41 @@
 //! Checkout module.
 //!
-//! Contains types for checkout flow, addresses, shipping, and orders.
+//! Contains types for checkout flow, addresses, shipping and shipping rates,
+//! order placement, orders and their lifecycle events, post-purchase edits,
+//! payments, shipments, returns, and digital fulfillment.
 
 mod address;
+mod digital;
+mod edit;
+mod events;
 mod flow;
+mod fulfillment;
 mod order;
+mod payment;
+mod placement;
+mod rates;
+mod returns;
 mod shipping;
 
 pub use address::Address;
-pub use flow::{CheckoutFlow, CheckoutStep};
+pub use digital::{DigitalDelivery, DigitalFulfiller, IssuedLicense};
+pub use edit::{OrderAmendment, OrderEdit, PaymentDelta};
+pub use events::{OrderEvent, OrderEventKind};
+pub use flow::{CheckoutFlow, CheckoutRequirement, CheckoutStep, PaymentAuthorization};
+pub use fulfillment::{Fulfillment, FulfillmentLine, Shipment, ShipmentStatus, TrackingEvent};
+#[cfg(test)]
+pub(crate) use order::fixtures;
 pub use order::{FinancialStatus, FulfillmentStatus, Order, OrderLineItem, OrderStatus};
+pub use payment::{PaymentGateway, PaymentTransaction, PaymentTransactionKind};
+pub use placement::{OrderPlacement, PlacedOrder, StockMode, StockMovement};
+#[cfg(feature = "storage")]
+pub use placement::{INVENTORY_LEVELS_SCHEMA, ORDERS_SCHEMA};
+pub use rates::{
+    Carrier, Parcel, RateBasis, RateTable, RateTier, ShipmentRequest, ShippingRateProvider,
+    ShippingZone, TableRateProvider, DEFAULT_DIM_DIVISOR,
+};
+pub use returns::{
+    RefundBreakdown, RefundPolicy, RestockAdjustment, RestockDecision, ReturnLineItem,
+    ReturnReason, ReturnRequest, ReturnStatus, ShippingRefundRule,
+};
 pub use shipping::{ShippingMethod, ShippingSelection};
@@ -1,9 +1,10 @@
 //! Order types.
 
 use crate::cart::LineItemProperty;
-use crate::checkout::{Address, ShippingSelection};
+use crate::checkout::{Address, OrderEvent, OrderEventKind, ShippingSelection};
+use crate::error::CommerceError;
 use crate::ids::{OrderId, OrderLineItemId, ProductId, UserId, VariantId};
-use crate::money::{Currency, Money};
+use crate::money::{Currency, Money, RoundingMode};
 use serde::{Deserialize, Serialize};
 
 /// Order status.
@@ -170,6 +171,9 @@ pub struct Order {
     pub updated_at: i64,
     /// Unix timestamp when cancelled (if applicable).
     pub cancelled_at: Option<i64>,
+    /// Lifecycle events not yet handed to a dispatcher (the outbox).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_events: Vec<OrderEvent>,
 }
 
 impl Order {
@@ -201,6 +205,11 @@ impl Order {
         self.fulfillment_status == FulfillmentStatus::Fulfilled
     }
 
+    /// Record that the order was placed. Call once for a new order.
+    pub fn record_created(&mut self) {
+        self.emit(OrderEventKind::Created);
+    }
+
     /// Cancel the order.
     pub fn cancel(&mut self) -> bool {
         if !self.status.can_cancel() {
@@ -209,25 +218,87 @@ impl Order {
         self.status = OrderStatus::Cancelled;
         self.cancelled_at = Some(current_timestamp());
         self.updated_at = current_timestamp();
+        self.emit(OrderEventKind::Cancelled);
         true
     }
 
     /// Update order status.
     pub fn set_status(&mut self, status: OrderStatus) {
+        let previous = self.status;
         self.status = status;
         self.updated_at = current_timestamp();
+        if previous != status && status == OrderStatus::Cancelled {
+            self.emit(OrderEventKind::Cancelled);
+        }
     }
 
     /// Update financial status.
     pub fn set_financial_status(&mut self, status: FinancialStatus) {
+        let previous = self.financial_status;
         self.financial_status = status;
         self.updated_at = current_timestamp();
+        if previous == status {
+            return;
+        }
+        match status {
+            FinancialStatus::Paid if previous != FinancialStatus::PartiallyRefunded => {
+                self.emit(OrderEventKind::Paid)
+            }
+            FinancialStatus::PartiallyRefunded | FinancialStatus::Refunded => {
+                self.emit(OrderEventKind::Refunded)
+            }
+            _ => {}
+        }
     }
 
     /// Update fulfillment status.
     pub fn set_fulfillment_status(&mut self, status: FulfillmentStatus) {
+        let previous = self.fulfillment_status;
         self.fulfillment_status = status;
         self.updated_at = current_timestamp();
+        if previous != status && status == FulfillmentStatus::Fulfilled {
+            self.emit(OrderEventKind::Fulfilled);
+        }
+    }
+
+    /// Quantity still to ship for each line that isn't fully fulfilled.
+    pub fn remaining_to_fulfill(&self) -> Vec<(OrderLineItemId, i64)> {
+        self.line_items
+            .iter()
+            .filter(|line| !line.is_fulfilled())
+            .map(|line| (line.id.clone(), line.unfulfilled_quantity()))
+            .collect()
+    }
+
+    /// Recompute line and order fulfillment status from fulfilled quantities.
+    pub fn refresh_fulfillment_status(&mut self) {
+        for line in &mut self.line_items {
+            line.fulfillment_status = if line.is_fulfilled() {
+                FulfillmentStatus::Fulfilled
+            } else if line.fulfilled_quantity > 0 {
+                FulfillmentStatus::PartiallyFulfilled
+            } else {
+                FulfillmentStatus::Unfulfilled
+            };
+        }
+        let status = if self.line_items.iter().all(|l| l.is_fulfilled()) {
+            FulfillmentStatus::Fulfilled
+        } else if self.line_items.iter().any(|l| l.fulfilled_quantity > 0) {
+            FulfillmentStatus::PartiallyFulfilled
+        } else {
+            FulfillmentStatus::Unfulfilled
+        };
+        self.set_fulfillment_status(status);
+    }
+
+    /// Drain the outbox.
+    pub fn take_events(&mut self) -> Vec<OrderEvent> {
+        std::mem::take(&mut self.pending_events)
+    }
+
+    fn emit(&mut self, kind: OrderEventKind) {
+        let event = OrderEvent::new(kind, self, self.updated_at);
+        self.pending_events.push(event);
     }
 }
 
@@ -240,7 +311,7 @@ pub struct OrderLineItem {
     pub variant_id: VariantId,
     /// Product ID.
     pub product_id: ProductId,
-    /// SKU at time of order.
+    /// SKU at time of order (empty if the cart line had none).
     pub sku: String,
     /// Product name at time of order.
     pub name: String,
@@ -276,6 +347,21 @@ impl OrderLineItem {
     }
 }
 
+/// `amount * part / whole`, rounded half up to the cent.
+pub(crate) fn pro_rata(amount: &Money, part: i64, whole: i64) -> Result<Money, CommerceError> {
+    if whole <= 0 {
+        return Err(CommerceError::InvalidQuantity(whole));
+    }
+    let cents = RoundingMode::HalfUp
+        .divide(
+            i128::from(amount.amount_cents) * i128::from(part),
+            i128::from(whole),
+        )
+        .and_then(|cents| i64::try_from(cents).ok())
+        .ok_or(CommerceError::Overflow)?;
+    Ok(Money::new(cents, amount.currency))
+}
+
 /// Get current Unix timestamp.
 fn current_timestamp() -> i64 {
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -285,6 +371,131 @@ fn current_timestamp() -> i64 {
         .unwrap_or(0)
 }
 
+/// Order builders shared by the checkout tests.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+    use crate::cart::Cart;
+    use crate::ids::ShippingMethodId;
+
+    pub(crate) fn usd(cents: i64) -> Money {
+        Money::new(cents, Currency::USD)
+    }
+
+    /// A complete address in `country_code`.
+    pub(crate) fn address_in(country_code: &str) -> Address {
+        Address::new(
+            "Ada",
+            "Lovelace",
+            "1 Main St",
+            "Springfield",
+            country_code,
+            country_code,
+            "12345",
+        )
+    }
+
+    /// A complete US address.
+    pub(crate) fn address() -> Address {
+        address_in("US")
+    }
+
+    /// A USD cart with two shirts at $20 and a mug at $10.
+    pub(crate) fn cart() -> Cart {
+        let mut cart = Cart::new("session");
+        cart.add_item(
+            VariantId::new("v-shirt"),
+            ProductId::new("p-shirt"),
+            "Shirt",
+            2,
+            usd(2000),
+        )
+        .unwrap();
+        cart.add_item(
+            VariantId::new("v-mug"),
+            ProductId::new("p-mug"),
+            "Mug",
+            1,
+            usd(1000),
+        )
+        .unwrap();
+        cart
+    }
+
+    /// An unfulfilled line of `quantity` units at `unit` cents, without
+    /// discount or tax.
+    pub(crate) fn line(id: &str, quantity: i64, unit: i64) -> OrderLineItem {
+        OrderLineItem {
+            id: OrderLineItemId::new(id),
+            variant_id: VariantId::new(format!("v-{}", id)),
+            product_id: ProductId::new(format!("p-{}", id)),
+            sku: id.to_uppercase(),
+            name: id.to_string(),
+            variant_title: None,
+            quantity,
+            unit_price: usd(unit),
+            total_price: usd(unit * quantity),
+            discount_amount: usd(0),
+            tax_amount: usd(0),
+            fulfillment_status: FulfillmentStatus::Unfulfilled,
+            fulfilled_quantity: 0,
+            properties: Vec::new(),
+        }
+    }
+
+    /// Like [`line`], with a line discount and tax.
+    pub(crate) fn taxed_line(
+        id: &str,
+        quantity: i64,
+        unit: i64,
+        discount: i64,
+        tax: i64,
+    ) -> OrderLineItem {
+        OrderLineItem {
+            discount_amount: usd(discount),
+            tax_amount: usd(tax),
+            ..line(id, quantity, unit)
+        }
+    }
+
+    /// A pending USD order "o1" with zero totals and free ground shipping.
+    pub(crate) fn order(line_items: Vec<OrderLineItem>) -> Order {
+        let zero = usd(0);
+        Order {
+            id: OrderId::new("o1"),
+            order_number: "ORD-1".to_string(),
+            user_id: None,
+            email: "a@example.com".to_string(),
+            status: OrderStatus::Pending,
+            financial_status: FinancialStatus::Pending,
+            fulfillment_status: FulfillmentStatus::Unfulfilled,
+            line_items,
+            shipping_address: Address::default(),
+            billing_address: Address::default(),
+            shipping_method: ShippingSelection {
+                method_id: ShippingMethodId::new("ground"),
+                method_name: "Ground".to_string(),
+                rate: zero,
+                carrier: None,
+                delivery_estimate: None,
+            },
+            subtotal: zero,
+            discount_total: zero,
+            shipping_total: zero,
+            tax_total: zero,
+            grand_total: zero,
+            currency: Currency::USD,
+            note: None,
+            tags: Vec::new(),
+            metadata: serde_json::Value::Null,
+            created_at: 0,
+            updated_at: 0,
+            cancelled_at: None,
+            pending_events: Vec::new(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -304,4 +515,46 @@ mod tests {
         assert!(num1.starts_with("ORD-"));
         // Note: num2 generated to verify function can be called multiple times
     }
+
+    #[test]
+    fn test_pro_rata_rounds_half_up() {
+        let tax = fixtures::usd(100);
+        assert_eq!(pro_rata(&tax, 2, 3).unwrap(), fixtures::usd(67));
+        assert_eq!(pro_rata(&tax, 1, 3).unwrap(), fixtures::usd(33));
+        assert_eq!(
+            pro_rata(&fixtures::usd(-5), 1, 2).unwrap(),
+            fixtures::usd(-3)
+        );
+        assert!(pro_rata(&tax, 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_transitions_emit_events() {
+        let mut order = fixtures::order(vec![fixtures::line("shirt", 2, 1000)]);
+        order.record_created();
+        order.set_financial_status(FinancialStatus::Authorized);
+        order.set_financial_status(FinancialStatus::Paid);
+        order.set_financial_status(FinancialStatus::Paid);
+        order.set_fulfillment_status(FulfillmentStatus::PartiallyFulfilled);
+        order.set_fulfillment_status(FulfillmentStatus::Fulfilled);
+        order.set_financial_status(FinancialStatus::PartiallyRefunded);
+
+        let kinds: Vec<OrderEventKind> = order.take_events().iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                OrderEventKind::Created,
+                OrderEventKind::Paid,
+                OrderEventKind::Fulfilled,
+                OrderEventKind::Refunded,
+            ]
+        );
+        assert!(order.pending_events.is_empty());
+
+        let mut cancelled = fixtures::order(Vec::new());
+        assert!(cancelled.cancel());
+        let events = cancelled.take_events();
+        assert_eq!(events[0].kind, OrderEventKind::Cancelled);
+        assert_eq!(events[0].status, OrderStatus::Cancelled);
+    }
 }
@@ -0,0 +1,72 @@
+//! Payment processor abstraction.
+//!
+//! Checkout authorizes payment up front ([`PaymentAuthorization`]); anything
+//! that moves money afterwards (capture, order edits, refunds) goes through
+//! a [`PaymentGateway`] implemented by the store's processor integration.
+
+use crate::checkout::{Order, PaymentAuthorization};
+use crate::error::CommerceError;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// Kind of money movement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PaymentTransactionKind {
+    /// Authorized funds captured.
+    Capture,
+    /// Additional charge to the order's payment method.
+    Charge,
+    /// Money returned to the customer.
+    Refund,
+    /// Authorization released without capture.
+    Void,
+}
+
+impl PaymentTransactionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentTransactionKind::Capture => "capture",
+            PaymentTransactionKind::Charge => "charge",
+            PaymentTransactionKind::Refund => "refund",
+            PaymentTransactionKind::Void => "void",
+        }
+    }
+}
+
+/// A completed processor transaction.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PaymentTransaction {
+    /// Processor reference.
+    pub reference: String,
+    /// What kind of movement it was.
+    pub kind: PaymentTransactionKind,
+    /// Amount moved (zero for voids).
+    pub amount: Money,
+    /// Unix timestamp of the transaction.
+    pub processed_at: i64,
+}
+
+/// A payment processor.
+///
+/// Implementations return [`CommerceError::PaymentFailed`] when the
+/// processor declines.
+pub trait PaymentGateway {
+    /// Capture an authorization.
+    fn capture(
+        &self,
+        authorization: &PaymentAuthorization,
+        amount: &Money,
+    ) -> Result<PaymentTransaction, CommerceError>;
+
+    /// Release an authorization without capturing it.
+    fn void(
+        &self,
+        authorization: &PaymentAuthorization,
+    ) -> Result<PaymentTransaction, CommerceError>;
+
+    /// Charge an additional amount to the order's payment method.
+    fn charge(&self, order: &Order, amount: &Money) -> Result<PaymentTransaction, CommerceError>;
+
+    /// Refund part of the order's payment.
+    fn refund(&self, order: &Order, amount: &Money) -> Result<PaymentTransaction, CommerceError>;
+}
@@ -0,0 +1,710 @@
+//! Order placement.
+//!
+//! [`OrderPlacement`] turns a completed checkout into an [`Order`]: it takes
+//! the stock, writes the order, and clears the cart, all-or-nothing. Payment
+//! is captured afterwards; if capture fails the placement is compensated by
+//! returning the stock, cancelling the order, voiding the authorization and
+//! restoring the cart.
+
+use crate::cart::{Cart, CartPricing};
+use crate::catalog::InventoryLevel;
+use crate::checkout::{
+    CheckoutFlow, CheckoutStep, FinancialStatus, FulfillmentStatus, Order, OrderLineItem,
+    OrderStatus, PaymentAuthorization, PaymentGateway, PaymentTransaction,
+};
+use crate::error::CommerceError;
+use crate::ids::{OrderId, OrderLineItemId, VariantId};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// How placing an order takes stock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum StockMode {
+    /// Decrement on-hand stock, checking availability.
+    #[default]
+    Decrement,
+    /// Consume quantities reserved earlier in checkout.
+    CommitReservation,
+}
+
+impl StockMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockMode::Decrement => "decrement",
+            StockMode::CommitReservation => "commit_reservation",
+        }
+    }
+}
+
+/// Stock taken for one variant when an order was placed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StockMovement {
+    /// Variant.
+    pub variant_id: VariantId,
+    /// Quantity ordered.
+    pub quantity: i64,
+    /// How it was taken.
+    pub mode: StockMode,
+    /// Quantity actually removed from stock, set by [`StockMovement::apply`].
+    /// Less than `quantity` when a backorder emptied the stock.
+    #[serde(default)]
+    pub taken: i64,
+}
+
+impl StockMovement {
+    /// Take the stock from a level, recording how much was taken.
+    pub fn apply(&mut self, level: &mut InventoryLevel) -> Result<(), CommerceError> {
+        self.taken = 0;
+        if !level.track_inventory {
+            return Ok(());
+        }
+        let before = level.quantity;
+        match self.mode {
+            StockMode::Decrement => {
+                if !level.can_fulfill(self.quantity) {
+                    return Err(self.insufficient(level.available()));
+                }
+                level.adjust(-self.quantity);
+            }
+            StockMode::CommitReservation => {
+                if level.quantity < self.quantity && !level.allow_backorder {
+                    return Err(self.insufficient(level.quantity));
+                }
+                level.commit(self.quantity);
+            }
+        }
+        self.taken = before - level.quantity;
+        Ok(())
+    }
+
+    /// Put the taken stock back (compensation). Reservations are not
+    /// recreated.
+    pub fn revert(&self, level: &mut InventoryLevel) {
+        if level.track_inventory {
+            level.restock(self.taken);
+        }
+    }
+
+    fn insufficient(&self, available: i64) -> CommerceError {
+        CommerceError::InsufficientInventory {
+            product_id: self.variant_id.to_string(),
+            requested: self.quantity,
+            available,
+        }
+    }
+}
+
+/// An order placed but not yet paid for.
+///
+/// Holds what's needed to compensate if payment capture fails.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlacedOrder {
+    /// The new order.
+    pub order: Order,
+    /// Authorization to capture.
+    pub authorization: PaymentAuthorization,
+    /// Stock taken.
+    pub movements: Vec<StockMovement>,
+    /// The cart the order was placed from.
+    pub cart: Cart,
+}
+
+impl Order {
+    /// Build an order from a checkout ready to complete.
+    ///
+    /// `pricing` is the cart's pricing as shown at review, with shipping and
+    /// tax applied. The checkout must be for `cart`, unexpired, and its
+    /// payment authorization must match `pricing.grand_total` exactly.
+    pub fn from_checkout(
+        flow: &CheckoutFlow,
+        cart: &Cart,
+        pricing: &CartPricing,
+    ) -> Result<Self, CommerceError> {
+        let requirements = flow.requirements_for(CheckoutStep::Complete);
+        if !requirements.is_empty() {
+            return Err(CommerceError::CheckoutRequirementsUnmet {
+                step: CheckoutStep::Complete.as_str().to_string(),
+                requirements,
+            });
+        }
+        if flow.cart_id != cart.id {
+            return Err(CommerceError::CheckoutCartMismatch {
+                checkout: flow.id.to_string(),
+                cart: cart.id.to_string(),
+            });
+        }
+        if flow.is_expired() {
+            return Err(CommerceError::CheckoutExpired(flow.id.to_string()));
+        }
+        if cart.is_empty() {
+            return Err(CommerceError::ValidationError("cart is empty".to_string()));
+        }
+        if let Some(ref authorization) = flow.payment_authorization {
+            if authorization.amount.currency != pricing.grand_total.currency {
+                return Err(CommerceError::CurrencyMismatch {
+                    expected: pricing.grand_total.currency.code().to_string(),
+                    got: authorization.amount.currency.code().to_string(),
+                });
+            }
+            if authorization.amount != pricing.grand_total {
+                return Err(CommerceError::AuthorizationMismatch {
+                    authorized: authorization.amount.to_string(),
+                    total: pricing.grand_total.to_string(),
+                });
+            }
+        }
+        let (Some(email), Some(shipping_address), Some(billing_address), Some(shipping_method)) = (
+            flow.email.clone(),
+            flow.shipping_address.clone(),
+            flow.effective_billing_address().cloned(),
+            flow.shipping_method.clone(),
+        ) else {
+            return Err(CommerceError::CheckoutIncomplete(
+                "checkout details".to_string(),
+            ));
+        };
+
+        let zero = Money::zero(cart.currency);
+        let line_items = cart
+            .items
+            .iter()
+            .map(|item| {
+                let line_pricing = pricing
+                    .line_items
+                    .iter()
+                    .find(|p| p.line_item_id == item.id);
+                OrderLineItem {
+                    id: OrderLineItemId::generate(),
+                    variant_id: item.variant_id.clone(),
+                    product_id: item.product_id.clone(),
+                    sku: item.sku.clone().unwrap_or_default(),
+                    name: item.product_name.clone(),
+                    variant_title: item.variant_name.clone(),
+                    quantity: item.quantity,
+                    unit_price: item.unit_price,
+                    total_price: item.total_price,
+                    discount_amount: line_pricing.map(|p| p.discount_amount).unwrap_or(zero),
+                    tax_amount: line_pricing.map(|p| p.tax_amount).unwrap_or(zero),
+                    fulfillment_status: FulfillmentStatus::Unfulfilled,
+                    fulfilled_quantity: 0,
+                    properties: item.properties.clone(),
+                }
+            })
+            .collect();
+
+        let now = current_timestamp();
+        Ok(Order {
+            id: OrderId::generate(),
+            order_number: Order::generate_order_number(),
+            user_id: cart.user_id.clone(),
+            email,
+            status: OrderStatus::Pending,
+            financial_status: FinancialStatus::Authorized,
+            fulfillment_status: FulfillmentStatus::Unfulfilled,
+            line_items,
+            shipping_address,
+            billing_address,
+            shipping_method,
+            subtotal: pricing.subtotal,
+            discount_total: pricing.discount_total,
+            shipping_total: pricing.shipping_total,
+            tax_total: pricing.tax_total,
+            grand_total: pricing.grand_total,
+            currency: cart.currency,
+            note: cart.note.clone(),
+            tags: Vec::new(),
+            metadata: serde_json::Value::Null,
+            created_at: now,
+            updated_at: now,
+            cancelled_at: None,
+            pending_events: Vec::new(),
+        })
+    }
+}
+
+/// Places orders from completed checkouts.
+#[derive(Debug, Clone, Default)]
+pub struct OrderPlacement {
+    /// How stock is taken.
+    pub stock_mode: StockMode,
+}
+
+impl OrderPlacement {
+    /// Create a placement service that decrements stock.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how stock is taken.
+    pub fn with_stock_mode(mut self, mode: StockMode) -> Self {
+        self.stock_mode = mode;
+        self
+    }
+
+    /// Stock to take for an order, one movement per variant.
+    pub fn stock_movements(&self, order: &Order) -> Vec<StockMovement> {
+        let mut movements: Vec<StockMovement> = Vec::new();
+        for line in &order.line_items {
+            match movements
+                .iter_mut()
+                .find(|m| m.variant_id == line.variant_id)
+            {
+                Some(existing) => existing.quantity += line.quantity,
+                None => movements.push(StockMovement {
+                    variant_id: line.variant_id.clone(),
+                    quantity: line.quantity,
+                    mode: self.stock_mode,
+                    taken: 0,
+                }),
+            }
+        }
+        movements
+    }
+
+    /// Build the order and its stock movements for a checkout.
+    pub fn prepare(
+        &self,
+        flow: &CheckoutFlow,
+        cart: &Cart,
+        pricing: &CartPricing,
+    ) -> Result<PlacedOrder, CommerceError> {
+        let mut order = Order::from_checkout(flow, cart, pricing)?;
+        let authorization = flow.payment_authorization.clone().ok_or_else(|| {
+            CommerceError::CheckoutIncomplete("payment authorization".to_string())
+        })?;
+        order.record_created();
+        Ok(PlacedOrder {
+            movements: self.stock_movements(&order),
+            order,
+            authorization,
+            cart: cart.clone(),
+        })
+    }
+
+    /// Capture payment for a placed order.
+    ///
+    /// On failure the order is cancelled and the authorization voided; the
+    /// caller must also revert `placed.movements` and restore `placed.cart`
+    /// (the storage-backed `capture_stored` does both).
+    pub fn capture(
+        &self,
+        placed: &mut PlacedOrder,
+        gateway: &dyn PaymentGateway,
+    ) -> Result<PaymentTransaction, CommerceError> {
+        match gateway.capture(&placed.authorization, &placed.order.grand_total) {
+            Ok(transaction) => {
+                placed.order.set_financial_status(FinancialStatus::Paid);
+                placed.order.set_status(OrderStatus::Confirmed);
+                Ok(transaction)
+            }
+            Err(e) => {
+                Self::cancel_unpaid(placed, gateway);
+                Err(e)
+            }
+        }
+    }
+
+    fn cancel_unpaid(placed: &mut PlacedOrder, gateway: &dyn PaymentGateway) {
+        // Voiding is best effort: an unvoided authorization lapses on its own.
+        let _ = gateway.void(&placed.authorization);
+        placed.order.cancel();
+        placed.order.set_financial_status(FinancialStatus::Voided);
+    }
+}
+
+#[cfg(feature = "storage")]
+mod storage {
+    use super::*;
+    use turbo_cache::Cache;
+    use turbo_db::{params, Backend, Db};
+
+    /// SQL to create the inventory table.
+    pub const INVENTORY_LEVELS_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS inventory_levels (
+        variant_id TEXT PRIMARY KEY,
+        quantity INTEGER NOT NULL DEFAULT 0,
+        reserved INTEGER NOT NULL DEFAULT 0,
+        track_inventory INTEGER NOT NULL DEFAULT 1,
+        allow_backorder INTEGER NOT NULL DEFAULT 0
+    )";
+
+    /// SQL to create the orders table.
+    pub const ORDERS_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS orders (
+        id TEXT PRIMARY KEY,
+        order_number TEXT NOT NULL UNIQUE,
+        email TEXT NOT NULL,
+        status TEXT NOT NULL,
+        financial_status TEXT NOT NULL,
+        grand_total_cents INTEGER NOT NULL,
+        currency TEXT NOT NULL,
+        data TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    )";
+
+    impl OrderPlacement {
+        /// Place an order for the cart stored under `cart_key`.
+        ///
+        /// Stock checks, stock updates and the order insert run in one
+        /// [`Db::transaction`], with each inventory row locked while it is
+        /// read and updated, so concurrent placements can't oversell. The
+        /// cart is deleted once the transaction commits.
+        pub fn place(
+            &self,
+            db: &Db,
+            cache: &Cache,
+            cart_key: &str,
+            flow: &CheckoutFlow,
+            pricing: &CartPricing,
+        ) -> Result<PlacedOrder, CommerceError> {
+            let cart: Cart = cache
+                .get(cart_key)?
+                .ok_or_else(|| CommerceError::CartNotFound(cart_key.to_string()))?;
+            let mut placed = self.prepare(flow, &cart, pricing)?;
+
+            db.transaction(|db| {
+                placed
+                    .movements
+                    .iter_mut()
+                    .try_for_each(|m| take_stock(db, m))?;
+                insert_order(db, &placed.order)
+            })?;
+            cache.delete(cart_key)?;
+            Ok(placed)
+        }
+
+        /// Capture payment for an order placed with [`OrderPlacement::place`].
+        ///
+        /// On success the stored order is marked paid. On failure the stock
+        /// is returned and the order cancelled in one transaction, the
+        /// authorization is voided, and the cart is written back under
+        /// `cart_key`; the capture error is returned.
+        pub fn capture_stored(
+            &self,
+            db: &Db,
+            cache: &Cache,
+            cart_key: &str,
+            placed: &mut PlacedOrder,
+            gateway: &dyn PaymentGateway,
+        ) -> Result<PaymentTransaction, CommerceError> {
+            match self.capture(placed, gateway) {
+                Ok(transaction) => {
+                    update_order(db, &placed.order)?;
+                    Ok(transaction)
+                }
+                Err(e) => {
+                    db.transaction(|db| {
+                        placed
+                            .movements
+                            .iter()
+                            .try_for_each(|m| return_stock(db, m))?;
+                        update_order(db, &placed.order)
+                    })?;
+                    cache.set(cart_key, &placed.cart)?;
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    fn take_stock(db: &Db, movement: &mut StockMovement) -> Result<(), CommerceError> {
+        #[derive(Deserialize)]
+        struct LevelRow {
+            quantity: i64,
+            reserved: i64,
+            track_inventory: i64,
+            allow_backorder: i64,
+        }
+        // SQLite already holds the write lock (`BEGIN IMMEDIATE`).
+        let lock = match db.backend() {
+            Backend::Postgres => " FOR UPDATE",
+            Backend::Sqlite => "",
+        };
+        let variant_id = movement.variant_id.as_str();
+        let row: LevelRow = db
+            .query_optional(
+                &format!(
+                    "SELECT quantity, reserved, track_inventory, allow_backorder
+                     FROM inventory_levels WHERE variant_id = ?{}",
+                    lock
+                ),
+                params![variant_id],
+            )?
+            .ok_or_else(|| CommerceError::VariantNotFound(variant_id.to_string()))?;
+        let mut level = InventoryLevel {
+            quantity: row.quantity,
+            reserved: row.reserved,
+            track_inventory: row.track_inventory != 0,
+            allow_backorder: row.allow_backorder != 0,
+            low_stock_threshold: None,
+        };
+        movement.apply(&mut level)?;
+        if movement.taken == 0 && level.reserved == row.reserved {
+            return Ok(());
+        }
+        db.execute(
+            "UPDATE inventory_levels SET quantity = ?, reserved = ? WHERE variant_id = ?",
+            params![level.quantity, level.reserved, movement.variant_id.as_str()],
+        )?;
+        Ok(())
+    }
+
+    fn return_stock(db: &Db, movement: &StockMovement) -> Result<(), CommerceError> {
+        db.execute(
+            "UPDATE inventory_levels SET quantity = quantity + ?
+             WHERE variant_id = ? AND track_inventory = 1",
+            params![movement.taken, movement.variant_id.as_str()],
+        )?;
+        Ok(())
+    }
+
+    fn insert_order(db: &Db, order: &Order) -> Result<(), CommerceError> {
+        db.execute(
+            "INSERT INTO orders
+             (id, order_number, email, status, financial_status, grand_total_cents,
+              currency, data, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                order.id.as_str(),
+                order.order_number.as_str(),
+                order.email.as_str(),
+                order.status.as_str(),
+                order.financial_status.as_str(),
+                order.grand_total.amount_cents,
+                order.currency.code(),
+                serde_json::to_string(order)?,
+                order.created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn update_order(db: &Db, order: &Order) -> Result<(), CommerceError> {
+        db.execute(
+            "UPDATE orders SET status = ?, financial_status = ?, grand_total_cents = ?, data = ?
+             WHERE id = ?",
+            params![
+                order.status.as_str(),
+                order.financial_status.as_str(),
+                order.grand_total.amount_cents,
+                serde_json::to_string(order)?,
+                order.id.as_str()
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage")]
+pub use storage::{INVENTORY_LEVELS_SCHEMA, ORDERS_SCHEMA};
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkout::fixtures::{address, cart, usd};
+    use crate::checkout::{OrderEventKind, PaymentTransactionKind, ShippingSelection};
+    use crate::ids::ShippingMethodId;
+    use crate::money::Currency;
+
+    fn ready_checkout(cart: &Cart, total: Money) -> CheckoutFlow {
+        let mut flow = CheckoutFlow::new(cart.id.clone());
+        flow.set_email("ada@example.com");
+        flow.set_shipping_address(address());
+        flow.set_shipping_method(ShippingSelection {
+            method_id: ShippingMethodId::new("ground"),
+            method_name: "Ground".to_string(),
+            rate: usd(0),
+            carrier: None,
+            delivery_estimate: None,
+        });
+        flow.set_payment_token("tok_visa");
+        flow.authorize_payment("auth_1", total).unwrap();
+        flow
+    }
+
+    struct Gateway {
+        decline: bool,
+        voided: std::cell::Cell<bool>,
+    }
+
+    impl PaymentGateway for Gateway {
+        fn capture(
+            &self,
+            authorization: &PaymentAuthorization,
+            amount: &Money,
+        ) -> Result<PaymentTransaction, CommerceError> {
+            if self.decline {
+                return Err(CommerceError::PaymentFailed("card declined".to_string()));
+            }
+            Ok(PaymentTransaction {
+                reference: authorization.reference.clone(),
+                kind: PaymentTransactionKind::Capture,
+                amount: *amount,
+                processed_at: 0,
+            })
+        }
+
+        fn void(
+            &self,
+            authorization: &PaymentAuthorization,
+        ) -> Result<PaymentTransaction, CommerceError> {
+            self.voided.set(true);
+            Ok(PaymentTransaction {
+                reference: authorization.reference.clone(),
+                kind: PaymentTransactionKind::Void,
+                amount: Money::zero(authorization.amount.currency),
+                processed_at: 0,
+            })
+        }
+
+        fn charge(&self, _: &Order, _: &Money) -> Result<PaymentTransaction, CommerceError> {
+            unreachable!()
+        }
+
+        fn refund(&self, _: &Order, _: &Money) -> Result<PaymentTransaction, CommerceError> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_order_from_checkout() {
+        let mut cart = cart();
+        cart.items[0].sku = Some("SHIRT-M".to_string());
+        let pricing = cart.calculate_pricing().unwrap();
+        assert!(
+            Order::from_checkout(&CheckoutFlow::new(cart.id.clone()), &cart, &pricing).is_err()
+        );
+
+        let flow = ready_checkout(&cart, pricing.grand_total);
+        let order = Order::from_checkout(&flow, &cart, &pricing).unwrap();
+        assert_eq!(order.line_items.len(), 2);
+        assert_eq!(order.grand_total, usd(5000));
+        assert_eq!(order.financial_status, FinancialStatus::Authorized);
+        assert_eq!(order.billing_address, address());
+        assert_eq!(order.line_items[0].sku, "SHIRT-M");
+        assert_eq!(order.line_items[1].sku, "");
+    }
+
+    #[test]
+    fn test_checkout_must_match_cart() {
+        let cart = cart();
+        let pricing = cart.calculate_pricing().unwrap();
+        let flow = ready_checkout(&Cart::new("other"), pricing.grand_total);
+        assert!(matches!(
+            Order::from_checkout(&flow, &cart, &pricing),
+            Err(CommerceError::CheckoutCartMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_expired_checkout_is_rejected() {
+        let cart = cart();
+        let pricing = cart.calculate_pricing().unwrap();
+        let mut flow = ready_checkout(&cart, pricing.grand_total);
+        flow.expires_at = 0;
+        assert!(matches!(
+            OrderPlacement::new().prepare(&flow, &cart, &pricing),
+            Err(CommerceError::CheckoutExpired(_))
+        ));
+    }
+
+    #[test]
+    fn test_authorization_must_cover_grand_total() {
+        let cart = cart();
+        let pricing = cart.calculate_pricing().unwrap();
+        let short = ready_checkout(&cart, usd(4999));
+        assert!(matches!(
+            OrderPlacement::new().prepare(&short, &cart, &pricing),
+            Err(CommerceError::AuthorizationMismatch { .. })
+        ));
+
+        let euros = ready_checkout(&cart, Money::new(5000, Currency::EUR));
+        assert!(matches!(
+            Order::from_checkout(&euros, &cart, &pricing),
+            Err(CommerceError::CurrencyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_stock_movements() {
+        let mut level = InventoryLevel::new(3);
+        level.reserve(2);
+        let mut decrement = StockMovement {
+            variant_id: VariantId::new("v"),
+            quantity: 2,
+            mode: StockMode::Decrement,
+            taken: 0,
+        };
+        assert!(decrement.apply(&mut level).is_err());
+
+        let mut commit = StockMovement {
+            mode: StockMode::CommitReservation,
+            ..decrement.clone()
+        };
+        commit.apply(&mut level).unwrap();
+        assert_eq!((level.quantity, level.reserved), (1, 0));
+        commit.revert(&mut level);
+        assert_eq!(level.quantity, 3);
+
+        let mut untracked = InventoryLevel::untracked();
+        decrement.apply(&mut untracked).unwrap();
+        assert_eq!((untracked.quantity, decrement.taken), (0, 0));
+
+        // A backorder past zero only takes what was in stock, and reverting
+        // puts back only that.
+        let mut backorder = InventoryLevel::new(1);
+        backorder.allow_backorder = true;
+        decrement.apply(&mut backorder).unwrap();
+        assert_eq!((backorder.quantity, decrement.taken), (0, 1));
+        decrement.revert(&mut backorder);
+        assert_eq!(backorder.quantity, 1);
+    }
+
+    #[test]
+    fn test_capture_and_compensation() {
+        let cart = cart();
+        let pricing = cart.calculate_pricing().unwrap();
+        let flow = ready_checkout(&cart, pricing.grand_total);
+        let placement = OrderPlacement::new();
+
+        let mut paid = placement.prepare(&flow, &cart, &pricing).unwrap();
+        assert_eq!(paid.movements.len(), 2);
+        let gateway = Gateway {
+            decline: false,
+            voided: Default::default(),
+        };
+        placement.capture(&mut paid, &gateway).unwrap();
+        assert_eq!(paid.order.financial_status, FinancialStatus::Paid);
+        assert_eq!(paid.order.status, OrderStatus::Confirmed);
+
+        let mut declined = placement.prepare(&flow, &cart, &pricing).unwrap();
+        let gateway = Gateway {
+            decline: true,
+            voided: Default::default(),
+        };
+        assert!(matches!(
+            placement.capture(&mut declined, &gateway),
+            Err(CommerceError::PaymentFailed(_))
+        ));
+        assert!(gateway.voided.get());
+        assert_eq!(declined.order.status, OrderStatus::Cancelled);
+        assert_eq!(declined.order.financial_status, FinancialStatus::Voided);
+        let kinds: Vec<OrderEventKind> = declined
+            .order
+            .take_events()
+            .iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![OrderEventKind::Created, OrderEventKind::Cancelled]
+        );
+    }
+}
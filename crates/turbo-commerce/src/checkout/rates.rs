@@ -0,0 +1,452 @@
+//! Shipping rate calculation.
+//!
+//! A [`ShippingRateProvider`] turns a [`ShipmentRequest`] into concrete
+//! [`ShippingMethod`] quotes. [`TableRateProvider`] implements it with
+//! per-carrier rate tables; live carrier APIs can implement it too.
+
+use crate::cart::CartPricing;
+use crate::checkout::{Address, ShippingMethod};
+use crate::error::CommerceError;
+use crate::ids::ShippingMethodId;
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// Common dimensional-weight divisor (cm³ per kg).
+pub const DEFAULT_DIM_DIVISOR: i64 = 5000;
+
+/// A package to ship.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct Parcel {
+    /// Actual weight in grams.
+    pub weight_grams: i64,
+    /// Length in centimeters.
+    pub length_cm: i64,
+    /// Width in centimeters.
+    pub width_cm: i64,
+    /// Height in centimeters.
+    pub height_cm: i64,
+}
+
+impl Parcel {
+    /// Create a parcel with weight only.
+    pub fn new(weight_grams: i64) -> Self {
+        Self {
+            weight_grams,
+            ..Self::default()
+        }
+    }
+
+    /// Set the dimensions in centimeters.
+    pub fn with_dimensions(mut self, length_cm: i64, width_cm: i64, height_cm: i64) -> Self {
+        self.length_cm = length_cm;
+        self.width_cm = width_cm;
+        self.height_cm = height_cm;
+        self
+    }
+
+    /// Dimensional weight in grams for a divisor (cm³ per kg).
+    pub fn dimensional_weight_grams(&self, divisor: i64) -> i64 {
+        if divisor <= 0 {
+            return 0;
+        }
+        let volume = self.length_cm.max(0) * self.width_cm.max(0) * self.height_cm.max(0);
+        // Round up to the next gram.
+        (volume * 1000 + divisor - 1) / divisor
+    }
+
+    /// Weight the carrier bills for: the greater of actual and dimensional.
+    pub fn billable_weight_grams(&self, divisor: Option<i64>) -> i64 {
+        let dimensional = divisor
+            .map(|d| self.dimensional_weight_grams(d))
+            .unwrap_or(0);
+        self.weight_grams.max(dimensional)
+    }
+}
+
+/// What a shipment contains and where it goes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShipmentRequest {
+    /// Destination address.
+    pub destination: Address,
+    /// Value of the goods (for price-based rates and free-shipping thresholds).
+    pub subtotal: Money,
+    /// Packages in the shipment.
+    pub parcels: Vec<Parcel>,
+}
+
+impl ShipmentRequest {
+    /// Create a request with no parcels.
+    pub fn new(destination: Address, subtotal: Money) -> Self {
+        Self {
+            destination,
+            subtotal,
+            parcels: Vec::new(),
+        }
+    }
+
+    /// Add a parcel.
+    pub fn with_parcel(mut self, parcel: Parcel) -> Self {
+        self.parcels.push(parcel);
+        self
+    }
+
+    /// Total actual weight in grams.
+    pub fn total_weight_grams(&self) -> i64 {
+        self.parcels.iter().map(|p| p.weight_grams).sum()
+    }
+}
+
+/// A set of destinations sharing rates.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShippingZone {
+    /// Zone name (e.g., "Domestic").
+    pub name: String,
+    /// Country codes in the zone (empty = everywhere).
+    pub country_codes: Vec<String>,
+}
+
+impl ShippingZone {
+    /// Create a zone for the given countries.
+    pub fn new<C: Into<String>>(
+        name: impl Into<String>,
+        country_codes: impl IntoIterator<Item = C>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            country_codes: country_codes.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Create a zone that matches every destination.
+    pub fn everywhere(name: impl Into<String>) -> Self {
+        Self::new(name, Vec::<String>::new())
+    }
+
+    /// Check if an address is in the zone.
+    pub fn contains(&self, address: &Address) -> bool {
+        self.country_codes.is_empty()
+            || self
+                .country_codes
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(&address.country_code))
+    }
+}
+
+/// What a rate table's tiers are keyed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum RateBasis {
+    /// Billable weight in grams.
+    #[default]
+    Weight,
+    /// Order subtotal in cents.
+    Price,
+}
+
+/// A tier in a rate table: `min <= value < max` costs `rate`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RateTier {
+    /// Inclusive lower bound (grams or cents).
+    pub min: i64,
+    /// Exclusive upper bound (None = no limit).
+    pub max: Option<i64>,
+    /// Price for this tier.
+    pub rate: Money,
+}
+
+/// Rates for one service level in one zone (e.g., "Ground, Domestic").
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RateTable {
+    /// Short code, unique within the carrier (e.g., "ground-us").
+    pub code: String,
+    /// Service name shown to customers (e.g., "Ground").
+    pub name: String,
+    /// Destinations served.
+    pub zone: ShippingZone,
+    /// What tiers are keyed on.
+    pub basis: RateBasis,
+    /// Tiers, checked in order.
+    pub tiers: Vec<RateTier>,
+    /// Subtotal at or above which the rate is free.
+    pub free_over: Option<Money>,
+    /// Minimum delivery days.
+    pub min_delivery_days: Option<i32>,
+    /// Maximum delivery days.
+    pub max_delivery_days: Option<i32>,
+}
+
+impl RateTable {
+    /// Create a table with no tiers.
+    pub fn new(
+        code: impl Into<String>,
+        name: impl Into<String>,
+        zone: ShippingZone,
+        basis: RateBasis,
+    ) -> Self {
+        Self {
+            code: code.into(),
+            name: name.into(),
+            zone,
+            basis,
+            tiers: Vec::new(),
+            free_over: None,
+            min_delivery_days: None,
+            max_delivery_days: None,
+        }
+    }
+
+    /// Add a tier.
+    pub fn with_tier(mut self, min: i64, max: Option<i64>, rate: Money) -> Self {
+        self.tiers.push(RateTier { min, max, rate });
+        self
+    }
+
+    /// Make the rate free at or above a subtotal.
+    pub fn with_free_over(mut self, threshold: Money) -> Self {
+        self.free_over = Some(threshold);
+        self
+    }
+
+    /// Set the delivery estimate.
+    pub fn with_delivery_days(mut self, min: i32, max: i32) -> Self {
+        self.min_delivery_days = Some(min);
+        self.max_delivery_days = Some(max);
+        self
+    }
+
+    /// Price a shipment, or None if the table doesn't serve it.
+    pub fn rate_for(
+        &self,
+        request: &ShipmentRequest,
+        dim_divisor: Option<i64>,
+    ) -> Result<Option<Money>, CommerceError> {
+        if !self.zone.contains(&request.destination) {
+            return Ok(None);
+        }
+        let value = match self.basis {
+            RateBasis::Weight => request
+                .parcels
+                .iter()
+                .map(|p| p.billable_weight_grams(dim_divisor))
+                .sum(),
+            RateBasis::Price => request.subtotal.amount_cents,
+        };
+        let Some(tier) = self
+            .tiers
+            .iter()
+            .find(|t| value >= t.min && t.max.map(|m| value < m).unwrap_or(true))
+        else {
+            return Ok(None);
+        };
+        if tier.rate.currency != request.subtotal.currency {
+            return Err(CommerceError::CurrencyMismatch {
+                expected: request.subtotal.currency.code().to_string(),
+                got: tier.rate.currency.code().to_string(),
+            });
+        }
+        let free = self
+            .free_over
+            .map(|t| {
+                t.currency == request.subtotal.currency
+                    && request.subtotal.amount_cents >= t.amount_cents
+            })
+            .unwrap_or(false);
+        Ok(Some(if free {
+            Money::zero(tier.rate.currency)
+        } else {
+            tier.rate
+        }))
+    }
+}
+
+/// A carrier and its rate tables.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Carrier {
+    /// Carrier name (e.g., "UPS").
+    pub name: String,
+    /// Dimensional-weight divisor (None = bill actual weight only).
+    pub dim_divisor: Option<i64>,
+    /// Rate tables.
+    pub rate_tables: Vec<RateTable>,
+}
+
+impl Carrier {
+    /// Create a carrier that bills dimensional weight with the default divisor.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            dim_divisor: Some(DEFAULT_DIM_DIVISOR),
+            rate_tables: Vec::new(),
+        }
+    }
+
+    /// Set the dimensional-weight divisor.
+    pub fn with_dim_divisor(mut self, divisor: Option<i64>) -> Self {
+        self.dim_divisor = divisor;
+        self
+    }
+
+    /// Add a rate table.
+    pub fn with_rate_table(mut self, table: RateTable) -> Self {
+        self.rate_tables.push(table);
+        self
+    }
+}
+
+/// Produces shipping quotes for a shipment.
+pub trait ShippingRateProvider {
+    /// Quote every available shipping method, cheapest first.
+    fn quote(&self, request: &ShipmentRequest) -> Result<Vec<ShippingMethod>, CommerceError>;
+}
+
+/// A rate provider backed by carrier rate tables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableRateProvider {
+    /// Configured carriers.
+    pub carriers: Vec<Carrier>,
+}
+
+impl TableRateProvider {
+    /// Create a provider with no carriers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a carrier.
+    pub fn with_carrier(mut self, carrier: Carrier) -> Self {
+        self.carriers.push(carrier);
+        self
+    }
+}
+
+impl ShippingRateProvider for TableRateProvider {
+    fn quote(&self, request: &ShipmentRequest) -> Result<Vec<ShippingMethod>, CommerceError> {
+        let mut quotes = Vec::new();
+        for carrier in &self.carriers {
+            for table in &carrier.rate_tables {
+                if let Some(rate) = table.rate_for(request, carrier.dim_divisor)? {
+                    let mut method = ShippingMethod::new(table.name.clone(), rate);
+                    method.id = ShippingMethodId::new(format!(
+                        "{}:{}",
+                        carrier.name.to_lowercase(),
+                        table.code
+                    ));
+                    method.carrier = Some(carrier.name.clone());
+                    method.min_delivery_days = table.min_delivery_days;
+                    method.max_delivery_days = table.max_delivery_days;
+                    quotes.push(method);
+                }
+            }
+        }
+        quotes.sort_by_key(|m| m.price.amount_cents);
+        Ok(quotes)
+    }
+}
+
+impl CartPricing {
+    /// Set the shipping cost and update the grand total.
+    pub fn apply_shipping(&mut self, rate: Money) -> Result<(), CommerceError> {
+        let previous = self.shipping_total;
+        self.grand_total = self
+            .grand_total
+            .try_subtract(&previous)
+            .and_then(|g| g.try_add(&rate))
+            .ok_or(CommerceError::Overflow)?;
+        self.shipping_total = rate;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkout::fixtures::{address_in, usd};
+    use crate::money::Currency;
+
+    fn provider() -> TableRateProvider {
+        let domestic = ShippingZone::new("Domestic", ["US"]);
+        TableRateProvider::new().with_carrier(
+            Carrier::new("UPS")
+                .with_rate_table(
+                    RateTable::new("ground", "Ground", domestic.clone(), RateBasis::Weight)
+                        .with_tier(0, Some(1000), usd(599))
+                        .with_tier(1000, Some(5000), usd(999))
+                        .with_free_over(usd(10000))
+                        .with_delivery_days(3, 5),
+                )
+                .with_rate_table(
+                    RateTable::new("express", "Express", domestic, RateBasis::Price).with_tier(
+                        0,
+                        None,
+                        usd(2499),
+                    ),
+                )
+                .with_rate_table(
+                    RateTable::new(
+                        "intl",
+                        "International",
+                        ShippingZone::everywhere("World"),
+                        RateBasis::Weight,
+                    )
+                    .with_tier(0, Some(2000), usd(3500)),
+                ),
+        )
+    }
+
+    #[test]
+    fn test_dimensional_weight() {
+        let parcel = Parcel::new(500).with_dimensions(30, 20, 10);
+        assert_eq!(parcel.dimensional_weight_grams(5000), 1200);
+        assert_eq!(parcel.billable_weight_grams(Some(5000)), 1200);
+        assert_eq!(parcel.billable_weight_grams(None), 500);
+    }
+
+    #[test]
+    fn test_quotes_sorted_and_zoned() {
+        let request =
+            ShipmentRequest::new(address_in("US"), usd(5000)).with_parcel(Parcel::new(800));
+        let quotes = provider().quote(&request).unwrap();
+        let names: Vec<&str> = quotes.iter().map(|q| q.name.as_str()).collect();
+        assert_eq!(names, vec!["Ground", "Express", "International"]);
+        assert_eq!(quotes[0].price, usd(599));
+        assert_eq!(quotes[0].id.as_str(), "ups:ground");
+        assert_eq!(quotes[0].delivery_estimate(), Some("3-5 days".to_string()));
+
+        let abroad =
+            ShipmentRequest::new(address_in("FR"), usd(5000)).with_parcel(Parcel::new(800));
+        assert_eq!(provider().quote(&abroad).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_free_shipping_threshold_and_heavy_parcels() {
+        let bulky = Parcel::new(800).with_dimensions(30, 20, 10);
+        let request = ShipmentRequest::new(address_in("US"), usd(5000)).with_parcel(bulky);
+        let ground = &provider().quote(&request).unwrap()[0];
+        assert_eq!(ground.price, usd(999));
+
+        let big_order = ShipmentRequest::new(address_in("US"), usd(10000)).with_parcel(bulky);
+        assert!(provider().quote(&big_order).unwrap()[0].is_free());
+
+        let too_heavy =
+            ShipmentRequest::new(address_in("FR"), usd(100)).with_parcel(Parcel::new(9000));
+        assert!(provider().quote(&too_heavy).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply_shipping_to_pricing() {
+        let mut pricing = CartPricing {
+            subtotal: usd(5000),
+            discount_total: usd(0),
+            shipping_total: usd(0),
+            tax_total: usd(0),
+            grand_total: usd(5000),
+            line_items: Vec::new(),
+        };
+        pricing.apply_shipping(usd(599)).unwrap();
+        pricing.apply_shipping(usd(999)).unwrap();
+        assert_eq!(pricing.shipping_total, usd(999));
+        assert_eq!(pricing.grand_total, usd(5999));
+        assert!(pricing
+            .apply_shipping(Money::new(100, Currency::EUR))
+            .is_err());
+    }
+}
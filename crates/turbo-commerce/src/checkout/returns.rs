@@ -0,0 +1,662 @@
+//! Returns and RMAs (return merchandise authorizations).
+//!
+//! A [`ReturnRequest`] moves through requested → approved → received →
+//! refunded (or is rejected/cancelled). Receiving decides per line whether
+//! the goods go back into stock, and the refund is calculated from the
+//! originating [`Order`]'s line totals under a [`RefundPolicy`].
+
+use crate::catalog::InventoryLevel;
+use crate::checkout::order::pro_rata;
+use crate::checkout::{FinancialStatus, Order, OrderLineItem};
+use crate::error::CommerceError;
+use crate::ids::{OrderId, OrderLineItemId, ReturnId, VariantId};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// Why an item is being returned.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReturnReason {
+    /// Arrived damaged.
+    Damaged,
+    /// Doesn't work.
+    Defective,
+    /// Wrong item shipped.
+    WrongItem,
+    /// Doesn't match the listing.
+    NotAsDescribed,
+    /// Doesn't fit.
+    SizeOrFit,
+    /// Customer changed their mind.
+    ChangedMind,
+    /// Other reason.
+    Other(String),
+}
+
+impl ReturnReason {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ReturnReason::Damaged => "damaged",
+            ReturnReason::Defective => "defective",
+            ReturnReason::WrongItem => "wrong_item",
+            ReturnReason::NotAsDescribed => "not_as_described",
+            ReturnReason::SizeOrFit => "size_or_fit",
+            ReturnReason::ChangedMind => "changed_mind",
+            ReturnReason::Other(reason) => reason,
+        }
+    }
+
+    /// Check if the merchant is at fault (no restocking fee, shipping refundable).
+    pub fn is_merchant_fault(&self) -> bool {
+        matches!(
+            self,
+            ReturnReason::Damaged
+                | ReturnReason::Defective
+                | ReturnReason::WrongItem
+                | ReturnReason::NotAsDescribed
+        )
+    }
+}
+
+/// What happens to a returned item once received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RestockDecision {
+    /// Put back into sellable stock.
+    Restock,
+    /// Written off (damaged, opened, etc.).
+    Discard,
+}
+
+impl RestockDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestockDecision::Restock => "restock",
+            RestockDecision::Discard => "discard",
+        }
+    }
+}
+
+/// Return status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum ReturnStatus {
+    /// Customer asked to return items.
+    #[default]
+    Requested,
+    /// Merchant accepted; awaiting the goods.
+    Approved,
+    /// Merchant declined.
+    Rejected,
+    /// Goods arrived back.
+    Received,
+    /// Money returned.
+    Refunded,
+    /// Withdrawn before the goods arrived.
+    Cancelled,
+}
+
+impl ReturnStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReturnStatus::Requested => "requested",
+            ReturnStatus::Approved => "approved",
+            ReturnStatus::Rejected => "rejected",
+            ReturnStatus::Received => "received",
+            ReturnStatus::Refunded => "refunded",
+            ReturnStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Check if the return still counts against returnable quantities.
+    pub fn is_open_or_done(&self) -> bool {
+        !matches!(self, ReturnStatus::Rejected | ReturnStatus::Cancelled)
+    }
+}
+
+/// A line being returned.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReturnLineItem {
+    /// Order line being returned.
+    pub order_line_item_id: OrderLineItemId,
+    /// Variant being returned.
+    pub variant_id: VariantId,
+    /// Quantity returned.
+    pub quantity: i64,
+    /// Why.
+    pub reason: ReturnReason,
+    /// Restock decision (set when received).
+    pub restock: Option<RestockDecision>,
+}
+
+/// Who pays for the original shipping on a return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum ShippingRefundRule {
+    /// Never refund shipping.
+    Never,
+    /// Refund shipping when the whole order has been returned.
+    #[default]
+    FullReturn,
+    /// Refund shipping when any returned item is the merchant's fault.
+    MerchantFault,
+}
+
+/// Rules for calculating refunds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub struct RefundPolicy {
+    /// When shipping is refunded (at most once per order).
+    pub shipping: ShippingRefundRule,
+    /// Fee withheld on items returned for non-merchant-fault reasons (percent).
+    pub restocking_fee_percent: f64,
+}
+
+impl RefundPolicy {
+    /// Create a policy that refunds shipping on full returns with no fee.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the shipping rule.
+    pub fn with_shipping(mut self, rule: ShippingRefundRule) -> Self {
+        self.shipping = rule;
+        self
+    }
+
+    /// Set the restocking fee.
+    pub fn with_restocking_fee(mut self, percent: f64) -> Self {
+        self.restocking_fee_percent = percent;
+        self
+    }
+}
+
+/// How a refund is made up.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RefundBreakdown {
+    /// Item value after discounts.
+    pub items: Money,
+    /// Tax on the items.
+    pub tax: Money,
+    /// Shipping refunded.
+    pub shipping: Money,
+    /// Fee withheld.
+    pub restocking_fee: Money,
+    /// Amount to refund.
+    pub total: Money,
+    /// Whether every item on the order has now been returned.
+    pub full_return: bool,
+}
+
+/// Stock to add back for a received return.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestockAdjustment {
+    /// Variant to restock.
+    pub variant_id: VariantId,
+    /// Quantity to add.
+    pub quantity: i64,
+}
+
+impl RestockAdjustment {
+    /// Add the quantity to an inventory level.
+    pub fn apply_to(&self, level: &mut InventoryLevel) {
+        level.restock(self.quantity);
+    }
+}
+
+/// A return request against an order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReturnRequest {
+    /// Unique return identifier.
+    pub id: ReturnId,
+    /// Originating order.
+    pub order_id: OrderId,
+    /// Current status.
+    pub status: ReturnStatus,
+    /// Lines being returned.
+    pub line_items: Vec<ReturnLineItem>,
+    /// Customer note.
+    pub note: Option<String>,
+    /// Why the merchant rejected the return.
+    pub rejection_reason: Option<String>,
+    /// Refund issued.
+    pub refund: Option<RefundBreakdown>,
+    /// Unix timestamp of creation.
+    pub created_at: i64,
+    /// Unix timestamp of last update.
+    pub updated_at: i64,
+}
+
+impl ReturnRequest {
+    /// Request a return of `(order line, quantity, reason)` items.
+    ///
+    /// `previous` are the order's earlier returns; quantities already
+    /// returned (or awaiting return) can't be returned again.
+    pub fn new(
+        order: &Order,
+        previous: &[ReturnRequest],
+        items: Vec<(OrderLineItemId, i64, ReturnReason)>,
+    ) -> Result<Self, CommerceError> {
+        if items.is_empty() {
+            return Err(CommerceError::ValidationError(
+                "a return needs at least one item".to_string(),
+            ));
+        }
+        let mut line_items: Vec<ReturnLineItem> = Vec::new();
+        for (line_id, quantity, reason) in items {
+            if quantity <= 0 {
+                return Err(CommerceError::InvalidQuantity(quantity));
+            }
+            let line = order_line(order, &line_id)?;
+            let requested: i64 = quantity
+                + line_items
+                    .iter()
+                    .filter(|l| l.order_line_item_id == line_id)
+                    .map(|l| l.quantity)
+                    .sum::<i64>();
+            let returnable = line.quantity - returned_quantity(previous, &order.id, &line_id);
+            if requested > returnable {
+                return Err(CommerceError::QuantityExceedsLimit(requested, returnable));
+            }
+            line_items.push(ReturnLineItem {
+                order_line_item_id: line_id,
+                variant_id: line.variant_id.clone(),
+                quantity,
+                reason,
+                restock: None,
+            });
+        }
+
+        let now = current_timestamp();
+        Ok(Self {
+            id: ReturnId::generate(),
+            order_id: order.id.clone(),
+            status: ReturnStatus::Requested,
+            line_items,
+            note: None,
+            rejection_reason: None,
+            refund: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Add a customer note.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Accept the return.
+    pub fn approve(&mut self) -> Result<(), CommerceError> {
+        self.transition(&[ReturnStatus::Requested], ReturnStatus::Approved)
+    }
+
+    /// Decline the return.
+    pub fn reject(&mut self, reason: impl Into<String>) -> Result<(), CommerceError> {
+        self.transition(&[ReturnStatus::Requested], ReturnStatus::Rejected)?;
+        self.rejection_reason = Some(reason.into());
+        Ok(())
+    }
+
+    /// Withdraw the return.
+    pub fn cancel(&mut self) -> Result<(), CommerceError> {
+        self.transition(
+            &[ReturnStatus::Requested, ReturnStatus::Approved],
+            ReturnStatus::Cancelled,
+        )
+    }
+
+    /// Record the goods arriving, with a restock decision per order line.
+    ///
+    /// Lines without a decision are discarded. Returns the stock to add back.
+    pub fn receive(
+        &mut self,
+        decisions: &[(OrderLineItemId, RestockDecision)],
+    ) -> Result<Vec<RestockAdjustment>, CommerceError> {
+        self.transition(&[ReturnStatus::Approved], ReturnStatus::Received)?;
+        let mut adjustments: Vec<RestockAdjustment> = Vec::new();
+        for line in &mut self.line_items {
+            let decision = decisions
+                .iter()
+                .find(|(id, _)| id == &line.order_line_item_id)
+                .map(|(_, d)| *d)
+                .unwrap_or(RestockDecision::Discard);
+            line.restock = Some(decision);
+            if decision == RestockDecision::Restock {
+                match adjustments
+                    .iter_mut()
+                    .find(|a| a.variant_id == line.variant_id)
+                {
+                    Some(existing) => existing.quantity += line.quantity,
+                    None => adjustments.push(RestockAdjustment {
+                        variant_id: line.variant_id.clone(),
+                        quantity: line.quantity,
+                    }),
+                }
+            }
+        }
+        Ok(adjustments)
+    }
+
+    /// Calculate the refund for this return.
+    ///
+    /// Item value and tax are refunded pro rata from the order line, after
+    /// the line's discount; the return that completes a line gets the
+    /// rounding remainder. `previous` are the order's earlier returns.
+    pub fn calculate_refund(
+        &self,
+        order: &Order,
+        previous: &[ReturnRequest],
+        policy: &RefundPolicy,
+    ) -> Result<RefundBreakdown, CommerceError> {
+        self.check_order(order)?;
+        let currency = order.currency;
+        let mut items = Money::zero(currency);
+        let mut tax = Money::zero(currency);
+        let mut fee_base = Money::zero(currency);
+        let others: Vec<&ReturnRequest> = previous
+            .iter()
+            .filter(|r| r.id != self.id && r.order_id == order.id && r.status.is_open_or_done())
+            .collect();
+        for (i, line) in self.line_items.iter().enumerate() {
+            let order_line = order_line(order, &line.order_line_item_id)?;
+            let earlier: i64 = others
+                .iter()
+                .flat_map(|r| r.line_items.iter())
+                .chain(&self.line_items[..i])
+                .filter(|l| l.order_line_item_id == line.order_line_item_id)
+                .map(|l| l.quantity)
+                .sum();
+            let net = order_line
+                .total_price
+                .try_subtract(&order_line.discount_amount)
+                .ok_or(CommerceError::Overflow)?;
+            let value = share(&net, earlier, line.quantity, order_line.quantity)?;
+            items = items.try_add(&value).ok_or(CommerceError::Overflow)?;
+            let line_tax = share(
+                &order_line.tax_amount,
+                earlier,
+                line.quantity,
+                order_line.quantity,
+            )?;
+            tax = tax.try_add(&line_tax).ok_or(CommerceError::Overflow)?;
+            if !line.reason.is_merchant_fault() {
+                fee_base = fee_base.try_add(&value).ok_or(CommerceError::Overflow)?;
+            }
+        }
+        let restocking_fee = fee_base.percentage(policy.restocking_fee_percent);
+
+        let full_return = order.line_items.iter().all(|line| {
+            let earlier: i64 = others
+                .iter()
+                .flat_map(|r| r.line_items.iter())
+                .filter(|l| l.order_line_item_id == line.id)
+                .map(|l| l.quantity)
+                .sum();
+            let now: i64 = self
+                .line_items
+                .iter()
+                .filter(|l| l.order_line_item_id == line.id)
+                .map(|l| l.quantity)
+                .sum();
+            earlier + now >= line.quantity
+        });
+        let shipping_refunded = others
+            .iter()
+            .any(|r| r.refund.as_ref().is_some_and(|b| !b.shipping.is_zero()));
+        let refund_shipping = !shipping_refunded
+            && match policy.shipping {
+                ShippingRefundRule::Never => false,
+                ShippingRefundRule::FullReturn => full_return,
+                ShippingRefundRule::MerchantFault => {
+                    self.line_items.iter().any(|l| l.reason.is_merchant_fault())
+                }
+            };
+        let shipping = if refund_shipping {
+            order.shipping_total
+        } else {
+            Money::zero(currency)
+        };
+
+        let total = Money::try_sum([items, tax, shipping].iter(), currency)
+            .and_then(|t| t.try_subtract(&restocking_fee))
+            .ok_or(CommerceError::Overflow)?;
+        Ok(RefundBreakdown {
+            items,
+            tax,
+            shipping,
+            restocking_fee,
+            total,
+            full_return,
+        })
+    }
+
+    /// Record the refund and update the order's payment status.
+    pub fn refund(
+        &mut self,
+        order: &mut Order,
+        breakdown: RefundBreakdown,
+    ) -> Result<(), CommerceError> {
+        self.check_order(order)?;
+        self.transition(&[ReturnStatus::Received], ReturnStatus::Refunded)?;
+        order.set_financial_status(if breakdown.full_return {
+            FinancialStatus::Refunded
+        } else {
+            FinancialStatus::PartiallyRefunded
+        });
+        self.refund = Some(breakdown);
+        Ok(())
+    }
+
+    fn check_order(&self, order: &Order) -> Result<(), CommerceError> {
+        if order.id != self.order_id {
+            return Err(CommerceError::ValidationError(format!(
+                "return {} belongs to order {}, not {}",
+                self.id, self.order_id, order.id
+            )));
+        }
+        Ok(())
+    }
+
+    fn transition(&mut self, from: &[ReturnStatus], to: ReturnStatus) -> Result<(), CommerceError> {
+        if !from.contains(&self.status) {
+            return Err(CommerceError::InvalidReturnTransition {
+                from: self.status.as_str().to_string(),
+                to: to.as_str().to_string(),
+            });
+        }
+        self.status = to;
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+}
+
+fn order_line<'a>(
+    order: &'a Order,
+    line_id: &OrderLineItemId,
+) -> Result<&'a OrderLineItem, CommerceError> {
+    order
+        .line_items
+        .iter()
+        .find(|l| &l.id == line_id)
+        .ok_or_else(|| {
+            CommerceError::ValidationError(format!(
+                "line {} is not on order {}",
+                line_id, order.order_number
+            ))
+        })
+}
+
+fn returned_quantity(
+    previous: &[ReturnRequest],
+    order_id: &OrderId,
+    line_id: &OrderLineItemId,
+) -> i64 {
+    previous
+        .iter()
+        .filter(|r| &r.order_id == order_id && r.status.is_open_or_done())
+        .flat_map(|r| r.line_items.iter())
+        .filter(|l| &l.order_line_item_id == line_id)
+        .map(|l| l.quantity)
+        .sum()
+}
+
+/// The share of `amount` for `quantity` of `whole` units after `earlier`
+/// units were returned.
+///
+/// Taken as the difference of the cumulative shares, so the return that
+/// completes a line gets the rounding remainder and the shares add up to
+/// `amount`.
+fn share(amount: &Money, earlier: i64, quantity: i64, whole: i64) -> Result<Money, CommerceError> {
+    let total = earlier
+        .checked_add(quantity)
+        .ok_or(CommerceError::Overflow)?;
+    pro_rata(amount, total, whole)?
+        .try_subtract(&pro_rata(amount, earlier, whole)?)
+        .ok_or(CommerceError::Overflow)
+}
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkout::fixtures::{self, usd};
+    use crate::checkout::{FulfillmentStatus, OrderEventKind, OrderStatus};
+
+    fn line(id: &str, quantity: i64, unit: i64, discount: i64, tax: i64) -> OrderLineItem {
+        OrderLineItem {
+            fulfillment_status: FulfillmentStatus::Fulfilled,
+            fulfilled_quantity: quantity,
+            ..fixtures::taxed_line(id, quantity, unit, discount, tax)
+        }
+    }
+
+    fn order() -> Order {
+        let mut order = Order {
+            status: OrderStatus::Delivered,
+            financial_status: FinancialStatus::Paid,
+            fulfillment_status: FulfillmentStatus::Fulfilled,
+            subtotal: usd(5000),
+            discount_total: usd(400),
+            shipping_total: usd(500),
+            tax_total: usd(368),
+            grand_total: usd(5468),
+            ..fixtures::order(vec![
+                line("shirt", 2, 2000, 400, 288),
+                line("mug", 1, 1000, 0, 80),
+            ])
+        };
+        order.shipping_method.rate = usd(500);
+        order
+    }
+
+    fn shirt() -> OrderLineItemId {
+        OrderLineItemId::new("shirt")
+    }
+
+    #[test]
+    fn test_returnable_quantities() {
+        let order = order();
+        let first =
+            ReturnRequest::new(&order, &[], vec![(shirt(), 1, ReturnReason::SizeOrFit)]).unwrap();
+        assert!(ReturnRequest::new(
+            &order,
+            std::slice::from_ref(&first),
+            vec![(shirt(), 2, ReturnReason::SizeOrFit)],
+        )
+        .is_err());
+        assert!(ReturnRequest::new(
+            &order,
+            &[],
+            vec![(OrderLineItemId::new("hat"), 1, ReturnReason::Damaged)],
+        )
+        .is_err());
+
+        let mut rejected = first.clone();
+        rejected.reject("worn").unwrap();
+        assert!(ReturnRequest::new(
+            &order,
+            &[rejected],
+            vec![(shirt(), 2, ReturnReason::Damaged)]
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_workflow_restock_and_partial_refund() {
+        let mut order = order();
+        let mut rma =
+            ReturnRequest::new(&order, &[], vec![(shirt(), 1, ReturnReason::ChangedMind)]).unwrap();
+        assert!(rma.receive(&[]).is_err());
+        rma.approve().unwrap();
+
+        let adjustments = rma.receive(&[(shirt(), RestockDecision::Restock)]).unwrap();
+        let mut level = InventoryLevel::new(5);
+        adjustments[0].apply_to(&mut level);
+        assert_eq!(level.quantity, 6);
+
+        let policy = RefundPolicy::new().with_restocking_fee(10.0);
+        let refund = rma.calculate_refund(&order, &[], &policy).unwrap();
+        assert_eq!(refund.items, usd(1800));
+        assert_eq!(refund.tax, usd(144));
+        assert_eq!(refund.restocking_fee, usd(180));
+        assert_eq!(refund.shipping, usd(0));
+        assert_eq!(refund.total, usd(1764));
+        assert!(!refund.full_return);
+
+        rma.refund(&mut order, refund).unwrap();
+        assert_eq!(order.financial_status, FinancialStatus::PartiallyRefunded);
+        assert_eq!(order.take_events()[0].kind, OrderEventKind::Refunded);
+        assert!(rma.cancel().is_err());
+    }
+
+    #[test]
+    fn test_full_return_refunds_shipping_once() {
+        let order = order();
+        let mut first =
+            ReturnRequest::new(&order, &[], vec![(shirt(), 2, ReturnReason::Damaged)]).unwrap();
+        let policy = RefundPolicy::new()
+            .with_shipping(ShippingRefundRule::MerchantFault)
+            .with_restocking_fee(10.0);
+        let breakdown = first.calculate_refund(&order, &[], &policy).unwrap();
+        assert_eq!(breakdown.shipping, usd(500));
+        assert!(breakdown.restocking_fee.is_zero());
+        first.status = ReturnStatus::Refunded;
+        first.refund = Some(breakdown);
+
+        let second = ReturnRequest::new(
+            &order,
+            std::slice::from_ref(&first),
+            vec![(OrderLineItemId::new("mug"), 1, ReturnReason::Defective)],
+        )
+        .unwrap();
+        let breakdown = second.calculate_refund(&order, &[first], &policy).unwrap();
+        assert!(breakdown.full_return);
+        assert!(breakdown.shipping.is_zero());
+        assert_eq!(breakdown.total, usd(1080));
+    }
+
+    #[test]
+    fn test_partial_returns_refund_the_whole_line() {
+        let mut order = order();
+        order.line_items[0] = line("shirt", 3, 1000, 1, 100);
+        let policy = RefundPolicy::new();
+        let mut previous: Vec<ReturnRequest> = Vec::new();
+        let mut refunds = Vec::new();
+        for _ in 0..3 {
+            let mut rma =
+                ReturnRequest::new(&order, &previous, vec![(shirt(), 1, ReturnReason::Damaged)])
+                    .unwrap();
+            let breakdown = rma.calculate_refund(&order, &previous, &policy).unwrap();
+            refunds.push((breakdown.items.amount_cents, breakdown.tax.amount_cents));
+            rma.status = ReturnStatus::Refunded;
+            rma.refund = Some(breakdown);
+            previous.push(rma);
+        }
+        assert_eq!(refunds, [(1000, 33), (999, 34), (1000, 33)]);
+    }
+}
@@ -0,0 +1,149 @@
+//! Saved customer addresses.
+
+use crate::checkout::Address;
+use crate::error::CommerceError;
+use crate::ids::AddressId;
+use serde::{Deserialize, Serialize};
+
+/// A customer's saved addresses with default shipping and billing choices.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct AddressBook {
+    /// Saved addresses (each has an ID).
+    pub addresses: Vec<Address>,
+    /// Default shipping address.
+    pub default_shipping: Option<AddressId>,
+    /// Default billing address.
+    pub default_billing: Option<AddressId>,
+}
+
+impl AddressBook {
+    /// Create an empty address book.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Save an address, assigning an ID if it has none.
+    ///
+    /// The first address saved becomes the default for both shipping and
+    /// billing.
+    pub fn add(&mut self, mut address: Address) -> AddressId {
+        let id = address.id.clone().unwrap_or_else(AddressId::generate);
+        address.id = Some(id.clone());
+        self.addresses.retain(|a| a.id.as_ref() != Some(&id));
+        self.addresses.push(address);
+        if self.default_shipping.is_none() {
+            self.default_shipping = Some(id.clone());
+        }
+        if self.default_billing.is_none() {
+            self.default_billing = Some(id.clone());
+        }
+        id
+    }
+
+    /// Replace a saved address, keeping its ID.
+    pub fn update(&mut self, id: &AddressId, mut address: Address) -> Result<(), CommerceError> {
+        let slot = self
+            .addresses
+            .iter_mut()
+            .find(|a| a.id.as_ref() == Some(id))
+            .ok_or_else(|| not_found(id))?;
+        address.id = Some(id.clone());
+        *slot = address;
+        Ok(())
+    }
+
+    /// Remove an address, clearing any default that pointed at it.
+    pub fn remove(&mut self, id: &AddressId) -> bool {
+        let len = self.addresses.len();
+        self.addresses.retain(|a| a.id.as_ref() != Some(id));
+        if self.default_shipping.as_ref() == Some(id) {
+            self.default_shipping = None;
+        }
+        if self.default_billing.as_ref() == Some(id) {
+            self.default_billing = None;
+        }
+        self.addresses.len() != len
+    }
+
+    /// Get a saved address.
+    pub fn get(&self, id: &AddressId) -> Option<&Address> {
+        self.addresses.iter().find(|a| a.id.as_ref() == Some(id))
+    }
+
+    /// Set the default shipping address.
+    pub fn set_default_shipping(&mut self, id: &AddressId) -> Result<(), CommerceError> {
+        self.get(id).ok_or_else(|| not_found(id))?;
+        self.default_shipping = Some(id.clone());
+        Ok(())
+    }
+
+    /// Set the default billing address.
+    pub fn set_default_billing(&mut self, id: &AddressId) -> Result<(), CommerceError> {
+        self.get(id).ok_or_else(|| not_found(id))?;
+        self.default_billing = Some(id.clone());
+        Ok(())
+    }
+
+    /// The default shipping address.
+    pub fn shipping(&self) -> Option<&Address> {
+        self.default_shipping.as_ref().and_then(|id| self.get(id))
+    }
+
+    /// The default billing address.
+    pub fn billing(&self) -> Option<&Address> {
+        self.default_billing.as_ref().and_then(|id| self.get(id))
+    }
+
+    /// Number of saved addresses.
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Check if no addresses are saved.
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+fn not_found(id: &AddressId) -> CommerceError {
+    CommerceError::ValidationError(format!("address {} is not in the address book", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(city: &str) -> Address {
+        Address::new(
+            "Ada",
+            "Lovelace",
+            "1 Main St",
+            city,
+            "United States",
+            "US",
+            "90001",
+        )
+    }
+
+    #[test]
+    fn test_defaults() {
+        let mut book = AddressBook::new();
+        let home = book.add(address("Los Angeles"));
+        let work = book.add(address("San Francisco"));
+        assert_eq!(book.shipping().unwrap().city, "Los Angeles");
+
+        book.set_default_shipping(&work).unwrap();
+        assert_eq!(book.shipping().unwrap().city, "San Francisco");
+        assert_eq!(book.billing().unwrap().city, "Los Angeles");
+        assert!(book
+            .set_default_billing(&AddressId::new("missing"))
+            .is_err());
+
+        book.update(&home, address("Pasadena")).unwrap();
+        assert_eq!(book.billing().unwrap().city, "Pasadena");
+
+        assert!(book.remove(&work));
+        assert!(book.shipping().is_none());
+        assert_eq!(book.len(), 1);
+    }
+}
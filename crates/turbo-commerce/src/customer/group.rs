@@ -0,0 +1,45 @@
+//! Customer groups.
+
+use serde::{Deserialize, Serialize};
+
+/// A segment of customers with shared pricing and tax treatment.
+///
+/// The `handle` is what [`PriceList::for_group`] and promotion rules refer
+/// to.
+///
+/// [`PriceList::for_group`]: crate::catalog::PriceList::for_group
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomerGroup {
+    /// Stable key (e.g., "wholesale").
+    pub handle: String,
+    /// Display name.
+    pub name: String,
+    /// Description for staff.
+    pub description: Option<String>,
+    /// Whether members are exempt from sales tax.
+    pub tax_exempt: bool,
+}
+
+impl CustomerGroup {
+    /// Create a group.
+    pub fn new(handle: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            handle: handle.into(),
+            name: name.into(),
+            description: None,
+            tax_exempt: false,
+        }
+    }
+
+    /// Set the description.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Mark members as tax exempt.
+    pub fn with_tax_exempt(mut self) -> Self {
+        self.tax_exempt = true;
+        self
+    }
+}
@@ -0,0 +1,13 @@
+//! Customer module.
+//!
+//! Contains customer profiles with an address book, customer groups (the
+//! handles price lists and promotions target), order history, and
+//! GDPR-style data export and anonymization.
+
+mod address_book;
+mod group;
+mod profile;
+
+pub use address_book::AddressBook;
+pub use group::CustomerGroup;
+pub use profile::{Customer, CustomerExport, OrderSummary};
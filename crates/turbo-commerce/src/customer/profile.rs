@@ -0,0 +1,432 @@
+//! Customer profiles.
+
+use crate::checkout::{Address, Order};
+use crate::customer::AddressBook;
+use crate::error::CommerceError;
+use crate::ids::{CustomerId, OrderId, UserId};
+use crate::money::{Currency, Money};
+use serde::{Deserialize, Serialize};
+
+/// A customer of the store, with or without a login.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Customer {
+    /// Unique customer identifier.
+    pub id: CustomerId,
+    /// Login account (None for guests).
+    pub user_id: Option<UserId>,
+    /// Contact email.
+    pub email: String,
+    /// First name.
+    pub first_name: Option<String>,
+    /// Last name.
+    pub last_name: Option<String>,
+    /// Phone number.
+    pub phone: Option<String>,
+    /// Whether the customer opted in to marketing email.
+    pub accepts_marketing: bool,
+    /// Customer group handles, most specific first.
+    pub groups: Vec<String>,
+    /// Saved addresses.
+    pub address_book: AddressBook,
+    /// Staff tags.
+    pub tags: Vec<String>,
+    /// Staff note.
+    pub note: Option<String>,
+    /// Orders placed, oldest first.
+    pub order_ids: Vec<OrderId>,
+    /// Lifetime spend, one entry per currency.
+    pub total_spent: Vec<Money>,
+    /// Unix timestamp of the latest order.
+    pub last_order_at: Option<i64>,
+    /// Unix timestamp personal data was erased.
+    pub anonymized_at: Option<i64>,
+    /// Unix timestamp of creation.
+    pub created_at: i64,
+    /// Unix timestamp of last update.
+    pub updated_at: i64,
+}
+
+impl Customer {
+    /// Create a guest customer.
+    pub fn new(email: impl Into<String>) -> Self {
+        let now = current_timestamp();
+        Self {
+            id: CustomerId::generate(),
+            user_id: None,
+            email: email.into(),
+            first_name: None,
+            last_name: None,
+            phone: None,
+            accepts_marketing: false,
+            groups: Vec::new(),
+            address_book: AddressBook::new(),
+            tags: Vec::new(),
+            note: None,
+            order_ids: Vec::new(),
+            total_spent: Vec::new(),
+            last_order_at: None,
+            anonymized_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Create a customer for a login account.
+    pub fn for_user(user_id: UserId, email: impl Into<String>) -> Self {
+        let mut customer = Self::new(email);
+        customer.user_id = Some(user_id);
+        customer
+    }
+
+    /// Set the name.
+    pub fn with_name(
+        mut self,
+        first_name: impl Into<String>,
+        last_name: impl Into<String>,
+    ) -> Self {
+        self.first_name = Some(first_name.into());
+        self.last_name = Some(last_name.into());
+        self
+    }
+
+    /// Set the phone number.
+    pub fn with_phone(mut self, phone: impl Into<String>) -> Self {
+        self.phone = Some(phone.into());
+        self
+    }
+
+    /// Get full name, if known.
+    pub fn full_name(&self) -> Option<String> {
+        match (&self.first_name, &self.last_name) {
+            (Some(first), Some(last)) => Some(format!("{} {}", first, last)),
+            (Some(name), None) | (None, Some(name)) => Some(name.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// Add the customer to a group.
+    pub fn add_group(&mut self, handle: impl Into<String>) {
+        let handle = handle.into();
+        if !self.in_group(&handle) {
+            self.groups.push(handle);
+            self.updated_at = current_timestamp();
+        }
+    }
+
+    /// Remove the customer from a group.
+    pub fn remove_group(&mut self, handle: &str) -> bool {
+        let len = self.groups.len();
+        self.groups.retain(|g| g != handle);
+        if self.groups.len() != len {
+            self.updated_at = current_timestamp();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Check group membership.
+    pub fn in_group(&self, handle: &str) -> bool {
+        self.groups.iter().any(|g| g == handle)
+    }
+
+    /// The group used for price list resolution.
+    pub fn primary_group(&self) -> Option<&str> {
+        self.groups.first().map(String::as_str)
+    }
+
+    /// Check if an order belongs to this customer (by account, or by email
+    /// for guest orders).
+    pub fn owns(&self, order: &Order) -> bool {
+        match (&self.user_id, &order.user_id) {
+            (Some(mine), Some(theirs)) => mine == theirs,
+            _ => !self.email.is_empty() && order.email.eq_ignore_ascii_case(&self.email),
+        }
+    }
+
+    /// Link a placed order to the customer and add it to lifetime spend.
+    ///
+    /// Recording the same order twice has no effect.
+    pub fn record_order(&mut self, order: &Order) -> Result<(), CommerceError> {
+        if !self.owns(order) {
+            return Err(CommerceError::ValidationError(format!(
+                "order {} does not belong to customer {}",
+                order.order_number, self.id
+            )));
+        }
+        if self.order_ids.contains(&order.id) {
+            return Ok(());
+        }
+        match self
+            .total_spent
+            .iter_mut()
+            .find(|m| m.currency == order.grand_total.currency)
+        {
+            Some(total) => {
+                *total = total
+                    .try_add(&order.grand_total)
+                    .ok_or(CommerceError::Overflow)?;
+            }
+            None => self.total_spent.push(order.grand_total),
+        }
+        self.order_ids.push(order.id.clone());
+        self.last_order_at = Some(match self.last_order_at {
+            Some(last) => last.max(order.created_at),
+            None => order.created_at,
+        });
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    /// Number of orders placed.
+    pub fn order_count(&self) -> usize {
+        self.order_ids.len()
+    }
+
+    /// Lifetime spend in a currency.
+    pub fn total_spent_in(&self, currency: Currency) -> Money {
+        self.total_spent
+            .iter()
+            .find(|m| m.currency == currency)
+            .copied()
+            .unwrap_or_else(|| Money::zero(currency))
+    }
+
+    /// The customer's orders from a collection, newest first.
+    pub fn order_history<'a>(
+        &self,
+        orders: impl IntoIterator<Item = &'a Order>,
+    ) -> Vec<OrderSummary> {
+        let mut history: Vec<OrderSummary> = orders
+            .into_iter()
+            .filter(|o| self.order_ids.contains(&o.id) || self.owns(o))
+            .map(OrderSummary::from_order)
+            .collect();
+        history.sort_by_key(|o| std::cmp::Reverse(o.created_at));
+        history
+    }
+
+    /// Everything held about the customer, for a data access request.
+    pub fn export<'a>(&self, orders: impl IntoIterator<Item = &'a Order>) -> CustomerExport {
+        CustomerExport {
+            customer: self.clone(),
+            orders: orders
+                .into_iter()
+                .filter(|o| self.order_ids.contains(&o.id) || self.owns(o))
+                .cloned()
+                .collect(),
+            exported_at: current_timestamp(),
+        }
+    }
+
+    /// Erase personal data, keeping the record and order statistics.
+    pub fn anonymize(&mut self) {
+        let now = current_timestamp();
+        self.user_id = None;
+        self.email = anonymized_email(self.id.as_str());
+        self.first_name = None;
+        self.last_name = None;
+        self.phone = None;
+        self.accepts_marketing = false;
+        self.address_book = AddressBook::new();
+        self.tags.clear();
+        self.note = None;
+        self.anonymized_at = Some(now);
+        self.updated_at = now;
+    }
+
+    /// Check if personal data was erased.
+    pub fn is_anonymized(&self) -> bool {
+        self.anonymized_at.is_some()
+    }
+}
+
+impl Order {
+    /// Erase the customer's personal data from the order.
+    ///
+    /// Totals, line items, and the region of each address are kept for
+    /// accounting and tax records. Free-form data that may hold personal
+    /// details (note, tags, metadata, line item properties) is dropped, and
+    /// undispatched events are scrubbed too.
+    pub fn anonymize(&mut self) {
+        self.user_id = None;
+        self.email = anonymized_email(self.id.as_str());
+        scrub_address(&mut self.shipping_address);
+        scrub_address(&mut self.billing_address);
+        self.note = None;
+        self.tags.clear();
+        self.metadata = serde_json::Value::Null;
+        for line in &mut self.line_items {
+            line.properties.clear();
+        }
+        for event in &mut self.pending_events {
+            event.user_id = None;
+            event.email = self.email.clone();
+        }
+        self.updated_at = current_timestamp();
+    }
+}
+
+/// A compact view of an order for order history.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderSummary {
+    /// The order.
+    pub id: OrderId,
+    /// Human-readable order number.
+    pub order_number: String,
+    /// Order status (e.g., "shipped").
+    pub status: String,
+    /// Number of items.
+    pub item_count: i64,
+    /// Grand total.
+    pub grand_total: Money,
+    /// Unix timestamp the order was placed.
+    pub created_at: i64,
+}
+
+impl OrderSummary {
+    /// Summarize an order.
+    pub fn from_order(order: &Order) -> Self {
+        Self {
+            id: order.id.clone(),
+            order_number: order.order_number.clone(),
+            status: order.status.as_str().to_string(),
+            item_count: order.item_count(),
+            grand_total: order.grand_total,
+            created_at: order.created_at,
+        }
+    }
+}
+
+/// A customer's data, for a data access (portability) request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomerExport {
+    /// The profile, address book, and groups.
+    pub customer: Customer,
+    /// The customer's orders.
+    pub orders: Vec<Order>,
+    /// Unix timestamp of the export.
+    pub exported_at: i64,
+}
+
+impl CustomerExport {
+    /// Serialize as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, CommerceError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn anonymized_email(id: &str) -> String {
+    format!("anonymized+{}@invalid", id)
+}
+
+/// Clear the name, street, and phone; keep the region.
+fn scrub_address(address: &mut Address) {
+    address.first_name.clear();
+    address.last_name.clear();
+    address.company = None;
+    address.address1.clear();
+    address.address2 = None;
+    address.phone = None;
+}
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cart::LineItemProperty;
+    use crate::checkout::{fixtures, FinancialStatus};
+
+    fn order(id: &str, email: &str, total: i64, created_at: i64) -> Order {
+        let address = Address::new(
+            "Ada",
+            "Lovelace",
+            "1 Main St",
+            "Los Angeles",
+            "United States",
+            "US",
+            "90001",
+        );
+        Order {
+            id: OrderId::new(id),
+            order_number: format!("ORD-{}", id),
+            email: email.to_string(),
+            financial_status: FinancialStatus::Paid,
+            shipping_address: address.clone(),
+            billing_address: address,
+            grand_total: Money::new(total, Currency::USD),
+            note: Some("Leave at the door".to_string()),
+            created_at,
+            updated_at: created_at,
+            ..fixtures::order(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_order_history() {
+        let mut customer = Customer::new("ada@example.com").with_name("Ada", "Lovelace");
+        let orders = [
+            order("o1", "ADA@example.com", 2500, 100),
+            order("o2", "ada@example.com", 1000, 200),
+            order("o3", "bob@example.com", 9900, 300),
+        ];
+        customer.record_order(&orders[0]).unwrap();
+        customer.record_order(&orders[1]).unwrap();
+        customer.record_order(&orders[1]).unwrap();
+        assert!(customer.record_order(&orders[2]).is_err());
+
+        assert_eq!(customer.order_count(), 2);
+        assert_eq!(customer.total_spent_in(Currency::USD).amount_cents, 3500);
+        assert_eq!(customer.last_order_at, Some(200));
+
+        let history = customer.order_history(&orders);
+        let numbers: Vec<&str> = history.iter().map(|o| o.order_number.as_str()).collect();
+        assert_eq!(numbers, ["ORD-o2", "ORD-o1"]);
+    }
+
+    #[test]
+    fn test_export_and_anonymize() {
+        let mut customer =
+            Customer::for_user(UserId::new("u1"), "ada@example.com").with_phone("555-0100");
+        customer.add_group("wholesale");
+        let mut orders = [order("o1", "ada@example.com", 2500, 100)];
+        orders[0].user_id = Some(UserId::new("u1"));
+        orders[0].tags.push("ada@example.com".to_string());
+        orders[0].metadata = serde_json::json!({ "gift_for": "Lovelace" });
+        let mut line = fixtures::line("shirt", 1, 2500);
+        line.properties.push(LineItemProperty {
+            name: "Engraving".to_string(),
+            value: "Lovelace".to_string(),
+        });
+        orders[0].line_items.push(line);
+        orders[0].record_created();
+        customer.record_order(&orders[0]).unwrap();
+
+        let export = customer.export(&orders);
+        assert_eq!(export.orders.len(), 1);
+        assert!(export.to_json().unwrap().contains("555-0100"));
+
+        customer.anonymize();
+        orders[0].anonymize();
+        assert!(customer.is_anonymized());
+        assert!(customer.phone.is_none() && customer.user_id.is_none());
+        assert_eq!(customer.primary_group(), Some("wholesale"));
+        assert_eq!(customer.total_spent_in(Currency::USD).amount_cents, 2500);
+
+        let json = serde_json::to_string(&orders[0]).unwrap();
+        assert!(!json.contains("ada@example.com") && !json.contains("Lovelace"));
+        assert!(!json.contains("u1"));
+        assert_eq!(orders[0].pending_events.len(), 1);
+        assert_eq!(orders[0].line_items[0].quantity, 1);
+        assert_eq!(orders[0].shipping_address.zip, "90001");
+        assert!(orders[0].note.is_none());
+    }
+}
@@ -1,5 +1,6 @@
 //! Commerce error types.
 
+use crate::checkout::CheckoutRequirement;
 use thiserror::Error;
 
 /// Errors that can occur in e-commerce operations.
@@ -47,10 +48,40 @@ pub enum CommerceError {
     #[error("Invalid checkout transition from {from} to {to}")]
     InvalidCheckoutTransition { from: String, to: String },
 
+    /// Invalid return state transition.
+    #[error("Invalid return transition from {from} to {to}")]
+    InvalidReturnTransition { from: String, to: String },
+
+    /// Invalid quote state transition.
+    #[error("Invalid quote transition from {from} to {to}")]
+    InvalidQuoteTransition { from: String, to: String },
+
     /// Checkout incomplete.
     #[error("Checkout incomplete: missing {0}")]
     CheckoutIncomplete(String),
 
+    /// Checkout step can't be entered until requirements are met.
+    #[error(
+        "Cannot enter checkout step {step}: {}",
+        .requirements.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    CheckoutRequirementsUnmet {
+        step: String,
+        requirements: Vec<CheckoutRequirement>,
+    },
+
+    /// Checkout session expired.
+    #[error("Checkout expired: {0}")]
+    CheckoutExpired(String),
+
+    /// Checkout was started for a different cart.
+    #[error("Checkout {checkout} is not for cart {cart}")]
+    CheckoutCartMismatch { checkout: String, cart: String },
+
+    /// Quote validity window has passed.
+    #[error("Quote expired: {0}")]
+    QuoteExpired(String),
+
     /// Invalid discount code.
     #[error("Invalid discount code: {0}")]
     InvalidDiscountCode(String),
@@ -67,6 +98,10 @@ pub enum CommerceError {
     #[error("Currency mismatch: expected {expected}, got {got}")]
     CurrencyMismatch { expected: String, got: String },
 
+    /// No rate to convert between two currencies.
+    #[error("No exchange rate from {from} to {to}")]
+    ExchangeRateUnavailable { from: String, to: String },
+
     /// Arithmetic overflow.
     #[error("Arithmetic overflow in money calculation")]
     Overflow,
@@ -87,9 +122,29 @@ pub enum CommerceError {
     #[error("Serialization error: {0}")]
     SerializationError(String),
 
+    /// Discount exists but the customer or order isn't eligible.
+    #[error("Discount not applicable ({code}): {reason}")]
+    DiscountNotApplicable { code: String, reason: String },
+
+    /// Payment processor declined or failed a transaction.
+    #[error("Payment failed: {0}")]
+    PaymentFailed(String),
+
+    /// Authorized amount differs from the amount due.
+    #[error("Payment authorization of {authorized} does not match total {total}")]
+    AuthorizationMismatch { authorized: String, total: String },
+
     /// Validation error.
     #[error("Validation error: {0}")]
     ValidationError(String),
+
+    /// Download refused (expired, revoked, over limit, or bad signature).
+    #[error("Download denied: {0}")]
+    DownloadDenied(String),
+
+    /// Product attribute doesn't match its schema.
+    #[error("Invalid attribute {key}: {reason}")]
+    InvalidAttribute { key: String, reason: String },
 }
 
 #[cfg(feature = "storage")]
@@ -0,0 +1,265 @@
+//! Currency conversion.
+//!
+//! [`ExchangeRates`] is a snapshot of rates against a base currency, with
+//! the rounding mode used when converting. Rates come from an
+//! [`ExchangeRateProvider`]: [`StaticRateProvider`] serves a fixed table, and
+//! with the `storage` feature `CachedRateProvider` keeps another provider's
+//! rates in the key-value store until they go stale.
+//!
+//! Rates are stored as fixed-point integers (see [`RATE_SCALE`]) so
+//! conversion is exact up to the final rounding.
+
+use crate::error::CommerceError;
+use crate::money::{Currency, Money, RoundingMode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Fixed-point scale for stored rates (9 decimal places).
+pub const RATE_SCALE: i64 = 1_000_000_000;
+
+/// Exchange rates against a base currency.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExchangeRates {
+    /// Currency the rates are quoted against.
+    pub base: Currency,
+    /// Units of each currency per unit of base, scaled by [`RATE_SCALE`].
+    pub rates: HashMap<Currency, i64>,
+    /// How converted amounts are rounded to the target's minor unit.
+    pub rounding: RoundingMode,
+    /// Unix timestamp the rates were published.
+    pub as_of: i64,
+}
+
+impl ExchangeRates {
+    /// Create an empty table.
+    pub fn new(base: Currency, as_of: i64) -> Self {
+        let mut rates = HashMap::new();
+        rates.insert(base, RATE_SCALE);
+        Self {
+            base,
+            rates,
+            rounding: RoundingMode::HalfUp,
+            as_of,
+        }
+    }
+
+    /// Add a rate (units of `currency` per unit of base).
+    pub fn with_rate(mut self, currency: Currency, rate: f64) -> Result<Self, CommerceError> {
+        self.set_rate(currency, rate)?;
+        Ok(self)
+    }
+
+    /// Set the rounding mode.
+    pub fn with_rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Set a rate (units of `currency` per unit of base).
+    pub fn set_rate(&mut self, currency: Currency, rate: f64) -> Result<(), CommerceError> {
+        let scaled = rate * RATE_SCALE as f64;
+        if !scaled.is_finite() || scaled < 1.0 || scaled > i64::MAX as f64 {
+            return Err(CommerceError::ValidationError(format!(
+                "invalid exchange rate for {}: {}",
+                currency, rate
+            )));
+        }
+        if currency == self.base && scaled.round() as i64 != RATE_SCALE {
+            return Err(CommerceError::ValidationError(format!(
+                "base currency {} must have rate 1",
+                currency
+            )));
+        }
+        self.rates.insert(currency, scaled.round() as i64);
+        Ok(())
+    }
+
+    /// Units of `to` per unit of `from`.
+    pub fn rate(&self, from: Currency, to: Currency) -> Option<f64> {
+        let from_rate = *self.rates.get(&from)?;
+        let to_rate = *self.rates.get(&to)?;
+        Some(to_rate as f64 / from_rate as f64)
+    }
+
+    /// Check if the table has a rate for a currency.
+    pub fn supports(&self, currency: Currency) -> bool {
+        self.rates.contains_key(&currency)
+    }
+
+    /// Check if the rates are younger than `max_age_secs`.
+    pub fn is_fresh(&self, now: i64, max_age_secs: i64) -> bool {
+        now - self.as_of < max_age_secs
+    }
+
+    /// Convert an amount, crossing through the base currency if needed.
+    pub fn convert(&self, amount: &Money, to: Currency) -> Result<Money, CommerceError> {
+        if amount.currency == to {
+            return Ok(*amount);
+        }
+        let unavailable = || CommerceError::ExchangeRateUnavailable {
+            from: amount.currency.code().to_string(),
+            to: to.code().to_string(),
+        };
+        let from_rate = *self.rates.get(&amount.currency).ok_or_else(unavailable)?;
+        let to_rate = *self.rates.get(&to).ok_or_else(unavailable)?;
+
+        // minor_to = minor_from * (to_rate / from_rate) * 10^(to_dp - from_dp)
+        let numerator = i128::from(amount.amount_cents)
+            .checked_mul(i128::from(to_rate))
+            .and_then(|n| n.checked_mul(10_i128.pow(to.decimal_places())))
+            .ok_or(CommerceError::Overflow)?;
+        let denominator = i128::from(from_rate) * 10_i128.pow(amount.currency.decimal_places());
+        let converted = self
+            .rounding
+            .divide(numerator, denominator)
+            .ok_or(CommerceError::Overflow)?;
+        let converted = i64::try_from(converted).map_err(|_| CommerceError::Overflow)?;
+        Ok(Money::new(converted, to))
+    }
+}
+
+/// A source of exchange rates.
+pub trait ExchangeRateProvider {
+    /// Current rates.
+    fn latest(&self) -> Result<ExchangeRates, CommerceError>;
+}
+
+/// Serves a fixed rate table (configuration, tests, or manually set rates).
+#[derive(Debug, Clone)]
+pub struct StaticRateProvider {
+    rates: ExchangeRates,
+}
+
+impl StaticRateProvider {
+    /// Serve the given rates.
+    pub fn new(rates: ExchangeRates) -> Self {
+        Self { rates }
+    }
+}
+
+impl ExchangeRateProvider for StaticRateProvider {
+    fn latest(&self) -> Result<ExchangeRates, CommerceError> {
+        Ok(self.rates.clone())
+    }
+}
+
+#[cfg(feature = "storage")]
+mod storage {
+    use super::*;
+    use turbo_cache::Cache;
+
+    /// Caches another provider's rates in the key-value store.
+    ///
+    /// Rates older than `max_age_secs` are refetched. If the refetch fails,
+    /// the stale rates are served rather than failing conversion.
+    pub struct CachedRateProvider<'a, P> {
+        inner: P,
+        cache: &'a Cache,
+        key: String,
+        max_age_secs: i64,
+    }
+
+    impl<'a, P: ExchangeRateProvider> CachedRateProvider<'a, P> {
+        /// Wrap a provider, storing rates under `key`.
+        pub fn new(inner: P, cache: &'a Cache, key: impl Into<String>, max_age_secs: i64) -> Self {
+            Self {
+                inner,
+                cache,
+                key: key.into(),
+                max_age_secs,
+            }
+        }
+    }
+
+    impl<P: ExchangeRateProvider> ExchangeRateProvider for CachedRateProvider<'_, P> {
+        fn latest(&self) -> Result<ExchangeRates, CommerceError> {
+            let cached: Option<ExchangeRates> = self.cache.get(&self.key)?;
+            if let Some(ref rates) = cached {
+                if rates.is_fresh(current_timestamp(), self.max_age_secs) {
+                    return Ok(rates.clone());
+                }
+            }
+            match self.inner.latest() {
+                Ok(rates) => {
+                    self.cache.set(&self.key, &rates)?;
+                    Ok(rates)
+                }
+                Err(e) => cached.ok_or(e),
+            }
+        }
+    }
+
+    /// Get current Unix timestamp.
+    fn current_timestamp() -> i64 {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(feature = "storage")]
+pub use storage::CachedRateProvider;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> ExchangeRates {
+        ExchangeRates::new(Currency::USD, 1000)
+            .with_rate(Currency::EUR, 0.92)
+            .unwrap()
+            .with_rate(Currency::JPY, 151.37)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_convert_with_rounding() {
+        let rates = rates();
+        let price = Money::new(4999, Currency::USD);
+        assert_eq!(
+            price.convert_to(Currency::EUR, &rates).unwrap(),
+            Money::new(4599, Currency::EUR)
+        );
+        // 49.99 * 151.37 = 7566.9863 yen
+        assert_eq!(
+            price.convert_to(Currency::JPY, &rates).unwrap(),
+            Money::new(7567, Currency::JPY)
+        );
+        let truncating = rates.clone().with_rounding(RoundingMode::TowardZero);
+        assert_eq!(
+            price.convert_to(Currency::JPY, &truncating).unwrap(),
+            Money::new(7566, Currency::JPY)
+        );
+        // Cross rate through the base: 1000 yen -> EUR.
+        assert_eq!(
+            Money::new(1000, Currency::JPY)
+                .convert_to(Currency::EUR, &rates)
+                .unwrap(),
+            Money::new(608, Currency::EUR)
+        );
+        assert_eq!(price.convert_to(Currency::USD, &rates).unwrap(), price);
+        assert!(matches!(
+            price.convert_to(Currency::GBP, &rates),
+            Err(CommerceError::ExchangeRateUnavailable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rate_validation_and_provider() {
+        assert!(ExchangeRates::new(Currency::USD, 0)
+            .with_rate(Currency::EUR, -1.0)
+            .is_err());
+        assert!(ExchangeRates::new(Currency::USD, 0)
+            .with_rate(Currency::USD, 2.0)
+            .is_err());
+
+        let provider = StaticRateProvider::new(rates());
+        let latest = provider.latest().unwrap();
+        assert!(latest.supports(Currency::JPY));
+        assert!(latest.is_fresh(1500, 600));
+        assert!(!latest.is_fresh(1600, 600));
+        assert!((latest.rate(Currency::EUR, Currency::USD).unwrap() - 1.0 / 0.92).abs() < 1e-9);
+    }
+}
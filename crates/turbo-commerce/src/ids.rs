@@ -76,6 +76,16 @@ define_id!(AddressId);
 define_id!(MediaId);
 define_id!(UserId);
 define_id!(SessionId);
+define_id!(LocationId);
+define_id!(PriceListId);
+define_id!(DigitalAssetId);
+define_id!(EntitlementId);
+define_id!(ReturnId);
+define_id!(FulfillmentId);
+define_id!(AmendmentId);
+define_id!(CompanyId);
+define_id!(QuoteId);
+define_id!(CustomerId);
 
 /// Generate a unique ID using timestamp and random bytes.
 fn generate_id() -> String {
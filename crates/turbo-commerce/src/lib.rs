@@ -5,7 +5,11 @@
 //! - **Catalog**: Products, variants, categories, inventory
 //! - **Cart**: Shopping cart with line items, discounts, pricing
 //! - **Checkout**: Multi-step checkout flow, orders
+//! - **Customers**: Profiles, address books, groups, order history, data export
 //! - **Search**: Faceted search, filters, pagination
+//! - **Tax**: Tax zones, rates, and calculation
+//! - **B2B**: Company accounts, negotiated quotes, purchase orders
+//! - **Webhooks**: Signed order-event delivery with retries
 //!
 //! # Example
 //!
@@ -37,42 +41,71 @@
 //! ```
 
 pub mod error;
+pub mod exchange;
 pub mod ids;
 pub mod money;
 
+pub mod b2b;
 pub mod cart;
 pub mod catalog;
 pub mod checkout;
+pub mod customer;
 pub mod search;
+pub mod tax;
+pub mod webhooks;
 
 pub use error::CommerceError;
 pub use ids::*;
-pub use money::{Currency, Money};
+pub use money::{Currency, Money, RoundingMode};
 
 /// Prelude for convenient imports.
 pub mod prelude {
     pub use crate::error::CommerceError;
+    pub use crate::exchange::{ExchangeRateProvider, ExchangeRates};
     pub use crate::ids::*;
-    pub use crate::money::{Currency, Money};
+    pub use crate::money::{Currency, Money, RoundingMode};
+
+    // B2B
+    pub use crate::b2b::{Buyer, Company, PaymentTerms, Quote, QuoteStatus};
 
     // Catalog
     pub use crate::catalog::{
-        Category, InventoryLevel, Product, ProductMedia, ProductStatus, ProductType,
-        ProductVariant, VariantOption,
+        AllocationStrategy, AttributeSchema, AttributeValue, Category, DigitalAsset,
+        FulfillmentPlan, InventoryAllocator, InventoryLevel, PriceEntry, PriceList, PriceResolver,
+        PriceRule, Product, ProductMedia, ProductOption, ProductStatus, ProductType,
+        ProductVariant, ResolvedPrice, StockLocation, VariantOption, VariantOverride, VariantStock,
     };
 
     // Cart
     pub use crate::cart::{
-        AppliedDiscount, Cart, CartPricing, Discount, DiscountCondition, DiscountType,
-        DiscountValue, LineItem, LineItemPricing,
+        AppliedDiscount, Cart, CartAbandoned, CartExpiryPolicy, CartPricing, Coupon, CouponBook,
+        Discount, DiscountCondition, DiscountType, DiscountValue, LineItem, LineItemPricing,
+        Promotion, PromotionAction, PromotionCondition, PromotionContext, PromotionEngine,
     };
 
     // Checkout
     pub use crate::checkout::{
-        Address, CheckoutFlow, CheckoutStep, FinancialStatus, FulfillmentStatus, Order,
-        OrderLineItem, OrderStatus, ShippingMethod, ShippingSelection,
+        Address, CheckoutFlow, CheckoutStep, DigitalFulfiller, FinancialStatus, Fulfillment,
+        FulfillmentStatus, Order, OrderAmendment, OrderEdit, OrderEvent, OrderEventKind,
+        OrderLineItem, OrderPlacement, OrderStatus, Parcel, PaymentGateway, Shipment,
+        ShipmentRequest, ShipmentStatus, ShippingMethod, ShippingRateProvider, ShippingSelection,
+        TableRateProvider,
     };
 
+    // Customers
+    pub use crate::customer::{AddressBook, Customer, CustomerGroup};
+
     // Search
-    pub use crate::search::{Filter, Pagination, SearchQuery, SearchResults, SortOption};
+    pub use crate::search::{
+        Filter, Pagination, RankingConfig, SearchIndexer, SearchQuery, SearchResults, SortOption,
+    };
+
+    // Tax
+    pub use crate::tax::{
+        PriceMode, TableTaxCalculator, TaxCalculator, TaxClass, TaxRate, TaxRequest, TaxResult,
+        TaxZone,
+    };
+
+    // Webhooks
+    pub use crate::webhooks::{WebhookDispatcher, WebhookEndpoint, WebhookTransport};
 }
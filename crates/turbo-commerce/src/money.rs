@@ -3,6 +3,7 @@
 //! Uses cents-based integer representation to avoid floating-point
 //! precision issues that plague monetary calculations.
 
+use crate::error::CommerceError;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::ops::{Add, Mul, Sub};
@@ -21,64 +22,91 @@ pub enum Currency {
     CNY,
     INR,
     MXN,
+    KRW,
+    VND,
+    KWD,
+    BHD,
+    TND,
+}
+
+/// Static properties of a currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrencyInfo {
+    /// The currency.
+    pub currency: Currency,
+    /// ISO 4217 code.
+    pub code: &'static str,
+    /// Display symbol.
+    pub symbol: &'static str,
+    /// Digits in the minor unit (0 for JPY, 3 for KWD).
+    pub decimal_places: u32,
+}
+
+/// Currency table, in declaration order of [`Currency`].
+const CURRENCIES: &[CurrencyInfo] = &[
+    info(Currency::USD, "USD", "$", 2),
+    info(Currency::EUR, "EUR", "\u{20ac}", 2),
+    info(Currency::GBP, "GBP", "\u{00a3}", 2),
+    info(Currency::JPY, "JPY", "\u{00a5}", 0),
+    info(Currency::CAD, "CAD", "CA$", 2),
+    info(Currency::AUD, "AUD", "A$", 2),
+    info(Currency::CHF, "CHF", "CHF", 2),
+    info(Currency::CNY, "CNY", "\u{00a5}", 2),
+    info(Currency::INR, "INR", "\u{20b9}", 2),
+    info(Currency::MXN, "MXN", "MX$", 2),
+    info(Currency::KRW, "KRW", "\u{20a9}", 0),
+    info(Currency::VND, "VND", "\u{20ab}", 0),
+    info(Currency::KWD, "KWD", "KD", 3),
+    info(Currency::BHD, "BHD", "BD", 3),
+    info(Currency::TND, "TND", "DT", 3),
+];
+
+const fn info(
+    currency: Currency,
+    code: &'static str,
+    symbol: &'static str,
+    decimal_places: u32,
+) -> CurrencyInfo {
+    CurrencyInfo {
+        currency,
+        code,
+        symbol,
+        decimal_places,
+    }
 }
 
 impl Currency {
+    /// Get the currency's static properties.
+    pub fn info(&self) -> &'static CurrencyInfo {
+        &CURRENCIES[*self as usize]
+    }
+
+    /// All supported currencies.
+    pub fn all() -> impl Iterator<Item = Currency> {
+        CURRENCIES.iter().map(|info| info.currency)
+    }
+
     /// Get the currency code (e.g., "USD").
     pub fn code(&self) -> &'static str {
-        match self {
-            Currency::USD => "USD",
-            Currency::EUR => "EUR",
-            Currency::GBP => "GBP",
-            Currency::JPY => "JPY",
-            Currency::CAD => "CAD",
-            Currency::AUD => "AUD",
-            Currency::CHF => "CHF",
-            Currency::CNY => "CNY",
-            Currency::INR => "INR",
-            Currency::MXN => "MXN",
-        }
+        self.info().code
     }
 
     /// Get the currency symbol (e.g., "$").
     pub fn symbol(&self) -> &'static str {
-        match self {
-            Currency::USD => "$",
-            Currency::EUR => "\u{20ac}",
-            Currency::GBP => "\u{00a3}",
-            Currency::JPY => "\u{00a5}",
-            Currency::CAD => "CA$",
-            Currency::AUD => "A$",
-            Currency::CHF => "CHF",
-            Currency::CNY => "\u{00a5}",
-            Currency::INR => "\u{20b9}",
-            Currency::MXN => "MX$",
-        }
+        self.info().symbol
     }
 
     /// Get the number of decimal places for this currency.
     pub fn decimal_places(&self) -> u32 {
-        match self {
-            Currency::JPY => 0,
-            _ => 2,
-        }
+        self.info().decimal_places
     }
 
     /// Parse a currency code string.
     pub fn from_code(code: &str) -> Option<Self> {
-        match code.to_uppercase().as_str() {
-            "USD" => Some(Currency::USD),
-            "EUR" => Some(Currency::EUR),
-            "GBP" => Some(Currency::GBP),
-            "JPY" => Some(Currency::JPY),
-            "CAD" => Some(Currency::CAD),
-            "AUD" => Some(Currency::AUD),
-            "CHF" => Some(Currency::CHF),
-            "CNY" => Some(Currency::CNY),
-            "INR" => Some(Currency::INR),
-            "MXN" => Some(Currency::MXN),
-            _ => None,
-        }
+        CURRENCIES
+            .iter()
+            .find(|info| info.code.eq_ignore_ascii_case(code))
+            .map(|info| info.currency)
     }
 }
 
@@ -88,6 +116,60 @@ impl fmt::Display for Currency {
     }
 }
 
+/// How to round a result that falls between two minor units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum RoundingMode {
+    /// Round halves away from zero (2.5 -> 3, -2.5 -> -3).
+    #[default]
+    HalfUp,
+    /// Round halves to the nearest even unit (2.5 -> 2, 3.5 -> 4).
+    HalfEven,
+    /// Drop the fraction (2.9 -> 2, -2.9 -> -2).
+    TowardZero,
+}
+
+impl RoundingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoundingMode::HalfUp => "half_up",
+            RoundingMode::HalfEven => "half_even",
+            RoundingMode::TowardZero => "toward_zero",
+        }
+    }
+
+    /// Divide, rounding the quotient. Returns None if `denominator` is zero.
+    pub fn divide(&self, numerator: i128, denominator: i128) -> Option<i128> {
+        if denominator == 0 {
+            return None;
+        }
+        let quotient = numerator / denominator;
+        let remainder = numerator % denominator;
+        if remainder == 0 {
+            return Some(quotient);
+        }
+        let away = if (numerator < 0) == (denominator < 0) {
+            1
+        } else {
+            -1
+        };
+        let twice = remainder.unsigned_abs() * 2;
+        let divisor = denominator.unsigned_abs();
+        let round_away = match self {
+            RoundingMode::TowardZero => false,
+            RoundingMode::HalfUp => twice >= divisor,
+            RoundingMode::HalfEven => twice > divisor || (twice == divisor && quotient & 1 == 1),
+        };
+        Some(if round_away {
+            quotient + away
+        } else {
+            quotient
+        })
+    }
+}
+
+/// Fixed-point scale for decimal factors (18 decimal places).
+const DECIMAL_SCALE: i128 = 1_000_000_000_000_000_000;
+
 /// A monetary value with currency.
 ///
 /// Amounts are stored in the smallest unit of the currency (e.g., cents for USD).
@@ -181,16 +263,55 @@ impl Money {
 
     /// Format as a display string (e.g., "$49.99").
     pub fn display(&self) -> String {
-        let decimal = self.to_decimal();
-        let places = self.currency.decimal_places() as usize;
-        format!("{}{:.places$}", self.currency.symbol(), decimal)
+        format!("{}{}", self.currency.symbol(), self.to_decimal_string())
     }
 
     /// Format as a display string without symbol (e.g., "49.99").
     pub fn display_amount(&self) -> String {
-        let decimal = self.to_decimal();
-        let places = self.currency.decimal_places() as usize;
-        format!("{:.places$}", decimal)
+        self.to_decimal_string()
+    }
+
+    /// Format the exact amount as a decimal string (e.g., "49.99", "-0.05").
+    pub fn to_decimal_string(&self) -> String {
+        let places = self.currency.decimal_places();
+        let sign = if self.amount_cents < 0 { "-" } else { "" };
+        let cents = self.amount_cents.unsigned_abs();
+        if places == 0 {
+            return format!("{}{}", sign, cents);
+        }
+        let divisor = 10_u64.pow(places);
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            cents / divisor,
+            cents % divisor,
+            width = places as usize
+        )
+    }
+
+    /// Parse an exact decimal string (e.g., "49.99") in the given currency.
+    ///
+    /// Fails if the string has more fractional digits than the currency
+    /// allows, rather than rounding.
+    pub fn parse_decimal(amount: &str, currency: Currency) -> Result<Money, CommerceError> {
+        let invalid = || CommerceError::ValidationError(format!("invalid amount: {:?}", amount));
+        let (negative, digits) = match amount.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, amount),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let places = currency.decimal_places() as usize;
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || !all_digits(whole) || !all_digits(fraction) {
+            return Err(invalid());
+        }
+        if fraction.len() > places || (digits.contains('.') && fraction.is_empty()) {
+            return Err(invalid());
+        }
+        let cents = format!("{}{:0<places$}", whole, fraction)
+            .parse::<i64>()
+            .map_err(|_| CommerceError::Overflow)?;
+        Ok(Money::new(if negative { -cents } else { cents }, currency))
     }
 
     /// Add another Money value.
@@ -246,18 +367,28 @@ impl Money {
             .expect("Overflow in Money::multiply")
     }
 
-    /// Multiply by a decimal factor (e.g., for percentages).
+    /// Multiply by a decimal factor (e.g., for percentages), rounding half up.
     /// Returns None if result overflows i64 range.
     pub fn try_multiply_decimal(&self, factor: f64) -> Option<Money> {
-        let result = self.amount_cents as f64 * factor;
-        if result.is_finite() && result >= i64::MIN as f64 && result <= i64::MAX as f64 {
-            Some(Money::new(result.round() as i64, self.currency))
-        } else {
-            None
+        self.try_multiply_decimal_with(factor, RoundingMode::HalfUp)
+    }
+
+    /// Multiply by a decimal factor with the given rounding mode.
+    /// Returns None if result overflows i64 range.
+    pub fn try_multiply_decimal_with(&self, factor: f64, mode: RoundingMode) -> Option<Money> {
+        // Exact fixed-point product, so halves are seen as halves.
+        let scaled = factor * DECIMAL_SCALE as f64;
+        if !scaled.is_finite() || scaled.abs() >= i128::MAX as f64 {
+            return None;
         }
+        let product = i128::from(self.amount_cents).checked_mul(scaled.round() as i128)?;
+        let result = mode.divide(product, DECIMAL_SCALE)?;
+        i64::try_from(result)
+            .ok()
+            .map(|amount| Money::new(amount, self.currency))
     }
 
-    /// Multiply by a decimal factor (e.g., for percentages).
+    /// Multiply by a decimal factor (e.g., for percentages), rounding half up.
     ///
     /// # Panics
     /// Panics if result overflows. Prefer `try_multiply_decimal` for safe arithmetic.
@@ -266,11 +397,79 @@ impl Money {
             .expect("Overflow in Money::multiply_decimal")
     }
 
-    /// Calculate a percentage of this amount.
+    /// Multiply by a decimal factor with the given rounding mode.
+    ///
+    /// # Panics
+    /// Panics if result overflows. Prefer `try_multiply_decimal_with` for safe arithmetic.
+    pub fn multiply_decimal_with(&self, factor: f64, mode: RoundingMode) -> Money {
+        self.try_multiply_decimal_with(factor, mode)
+            .expect("Overflow in Money::multiply_decimal_with")
+    }
+
+    /// Calculate a percentage of this amount, rounding half up.
     pub fn percentage(&self, percent: f64) -> Money {
         self.multiply_decimal(percent / 100.0)
     }
 
+    /// Calculate a percentage of this amount with the given rounding mode.
+    pub fn percentage_with(&self, percent: f64, mode: RoundingMode) -> Money {
+        self.multiply_decimal_with(percent / 100.0, mode)
+    }
+
+    /// Split into `parts` equal shares that sum exactly to this amount.
+    ///
+    /// Leftover cents go one each to the first shares, so
+    /// `$10.00` in three parts is `[3.34, 3.33, 3.33]`. Returns None if
+    /// `parts` is zero.
+    pub fn allocate(&self, parts: usize) -> Option<Vec<Money>> {
+        self.allocate_weighted(&vec![1; parts])
+    }
+
+    /// Split in proportion to `weights`, with shares summing exactly to this amount.
+    ///
+    /// Each share is rounded toward zero, then leftover cents go to the
+    /// shares with the largest remainders (earlier shares win ties). Returns
+    /// None if a weight is negative or the weights sum to zero.
+    pub fn allocate_weighted(&self, weights: &[i64]) -> Option<Vec<Money>> {
+        if weights.iter().any(|w| *w < 0) {
+            return None;
+        }
+        let total: i128 = weights.iter().map(|w| i128::from(*w)).sum();
+        if total == 0 {
+            return None;
+        }
+        let amount = i128::from(self.amount_cents);
+        let mut shares = Vec::with_capacity(weights.len());
+        let mut remainders = Vec::with_capacity(weights.len());
+        for (i, weight) in weights.iter().enumerate() {
+            let product = amount * i128::from(*weight);
+            shares.push(product / total);
+            remainders.push(((product % total).unsigned_abs(), i));
+        }
+        let leftover = amount - shares.iter().sum::<i128>();
+        let unit = leftover.signum();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for (_, i) in remainders.iter().take(leftover.unsigned_abs() as usize) {
+            shares[*i] += unit;
+        }
+        // Every share lies between zero and the amount, so it fits in i64.
+        Some(
+            shares
+                .into_iter()
+                .map(|share| Money::new(share as i64, self.currency))
+                .collect(),
+        )
+    }
+
+    /// Convert to another currency using the rates' rounding mode.
+    pub fn convert_to(
+        &self,
+        currency: Currency,
+        rates: &crate::exchange::ExchangeRates,
+    ) -> Result<Money, CommerceError> {
+        rates.convert(self, currency)
+    }
+
     /// Try to sum an iterator of Money values. Returns None on overflow or currency mismatch.
     pub fn try_sum<'a>(
         mut iter: impl Iterator<Item = &'a Money>,
@@ -320,6 +519,84 @@ impl fmt::Display for Money {
     }
 }
 
+/// Opt-in serde representation with a string amount.
+///
+/// Serializes as `{"amount": "49.99", "currency": "USD"}` so JSON clients
+/// neither mistake minor units for whole units nor parse amounts as floats.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use turbo_commerce::money::{Currency, Money};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct PriceResponse {
+///     #[serde(with = "turbo_commerce::money::string_amount")]
+///     price: Money,
+/// }
+///
+/// let json = serde_json::to_string(&PriceResponse {
+///     price: Money::new(4999, Currency::USD),
+/// })
+/// .unwrap();
+/// assert_eq!(json, r#"{"price":{"amount":"49.99","currency":"USD"}}"#);
+/// ```
+pub mod string_amount {
+    use super::{Currency, Money};
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Repr {
+        amount: String,
+        currency: Currency,
+    }
+
+    impl From<&Money> for Repr {
+        fn from(money: &Money) -> Self {
+            Self {
+                amount: money.to_decimal_string(),
+                currency: money.currency,
+            }
+        }
+    }
+
+    impl TryFrom<Repr> for Money {
+        type Error = crate::error::CommerceError;
+
+        fn try_from(repr: Repr) -> Result<Self, Self::Error> {
+            Money::parse_decimal(&repr.amount, repr.currency)
+        }
+    }
+
+    pub fn serialize<S: Serializer>(money: &Money, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr::from(money).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        Money::try_from(Repr::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+
+    /// The same representation for `Option<Money>` fields.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            money: &Option<Money>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            money.as_ref().map(Repr::from).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Money>, D::Error> {
+            Option::<Repr>::deserialize(deserializer)?
+                .map(Money::try_from)
+                .transpose()
+                .map_err(de::Error::custom)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,6 +632,33 @@ mod tests {
         assert_eq!(m.display(), "\u{00a5}100");
     }
 
+    #[test]
+    fn test_three_and_zero_decimal_currencies() {
+        let m = Money::from_decimal(12.345, Currency::KWD);
+        assert_eq!(m.amount_cents, 12345);
+        assert_eq!(m.display(), "KD12.345");
+        assert_eq!(Money::new(-5, Currency::BHD).display_amount(), "-0.005");
+        assert_eq!(
+            Money::parse_decimal("1.5", Currency::TND).unwrap(),
+            Money::new(1500, Currency::TND)
+        );
+
+        let m = Money::from_decimal(15000.0, Currency::KRW);
+        assert_eq!(m.amount_cents, 15000);
+        assert_eq!(m.display(), "\u{20a9}15000");
+        assert_eq!(Money::new(25000, Currency::VND).to_decimal(), 25000.0);
+    }
+
+    #[test]
+    fn test_currency_table() {
+        for (i, currency) in Currency::all().enumerate() {
+            assert_eq!(currency as usize, i);
+            assert_eq!(Currency::from_code(currency.code()), Some(currency));
+        }
+        assert_eq!(Currency::KWD.decimal_places(), 3);
+        assert_eq!(Currency::VND.decimal_places(), 0);
+    }
+
     #[test]
     fn test_money_addition() {
         let a = Money::new(1000, Currency::USD);
@@ -393,6 +697,151 @@ mod tests {
         let _ = usd + eur;
     }
 
+    #[test]
+    fn test_rounding_modes() {
+        assert_eq!(RoundingMode::HalfUp.divide(5, 2), Some(3));
+        assert_eq!(RoundingMode::HalfUp.divide(-5, 2), Some(-3));
+        assert_eq!(RoundingMode::HalfEven.divide(5, 2), Some(2));
+        assert_eq!(RoundingMode::HalfEven.divide(7, 2), Some(4));
+        assert_eq!(RoundingMode::HalfEven.divide(-5, 2), Some(-2));
+        assert_eq!(RoundingMode::TowardZero.divide(29, 10), Some(2));
+        assert_eq!(RoundingMode::TowardZero.divide(-29, 10), Some(-2));
+        assert_eq!(RoundingMode::HalfUp.divide(1, 0), None);
+    }
+
+    #[test]
+    fn test_multiply_decimal_rounding() {
+        // 12.5 cents: half up rounds away, half even rounds to 12.
+        let price = Money::new(125, Currency::USD);
+        assert_eq!(price.multiply_decimal(0.1).amount_cents, 13);
+        assert_eq!(
+            price
+                .multiply_decimal_with(0.1, RoundingMode::HalfEven)
+                .amount_cents,
+            12
+        );
+        assert_eq!(
+            price
+                .percentage_with(10.0, RoundingMode::TowardZero)
+                .amount_cents,
+            12
+        );
+        assert_eq!(
+            Money::new(-125, Currency::USD)
+                .percentage_with(10.0, RoundingMode::HalfUp)
+                .amount_cents,
+            -13
+        );
+        // 15% of $10.05 is 150.75 cents.
+        assert_eq!(
+            Money::new(1005, Currency::USD)
+                .percentage(15.0)
+                .amount_cents,
+            151
+        );
+    }
+
+    #[test]
+    fn test_allocate() {
+        let total = Money::new(1000, Currency::USD);
+        let shares: Vec<i64> = total
+            .allocate(3)
+            .unwrap()
+            .iter()
+            .map(|m| m.amount_cents)
+            .collect();
+        assert_eq!(shares, [334, 333, 333]);
+        assert!(total.allocate(0).is_none());
+
+        let refund: Vec<i64> = Money::new(-1000, Currency::USD)
+            .allocate(3)
+            .unwrap()
+            .iter()
+            .map(|m| m.amount_cents)
+            .collect();
+        assert_eq!(refund, [-334, -333, -333]);
+    }
+
+    #[test]
+    fn test_allocate_weighted() {
+        // $1.00 over 1:1:1:0 weights, and a 70/30 split of 5 cents.
+        let shares: Vec<i64> = Money::new(100, Currency::USD)
+            .allocate_weighted(&[1, 1, 1, 0])
+            .unwrap()
+            .iter()
+            .map(|m| m.amount_cents)
+            .collect();
+        assert_eq!(shares, [34, 33, 33, 0]);
+
+        let shares: Vec<i64> = Money::new(5, Currency::USD)
+            .allocate_weighted(&[30, 70])
+            .unwrap()
+            .iter()
+            .map(|m| m.amount_cents)
+            .collect();
+        // 1.5 and 3.5: the tie goes to the first share.
+        assert_eq!(shares, [2, 3]);
+
+        let amount = Money::new(9_999, Currency::USD);
+        let shares = amount.allocate_weighted(&[2_499, 1_299, 6_201]).unwrap();
+        assert_eq!(Money::try_sum(shares.iter(), Currency::USD), Some(amount));
+
+        assert!(amount.allocate_weighted(&[0, 0]).is_none());
+        assert!(amount.allocate_weighted(&[1, -1]).is_none());
+        assert!(amount.allocate_weighted(&[]).is_none());
+    }
+
+    #[test]
+    fn test_decimal_string_round_trip() {
+        assert_eq!(Money::new(4999, Currency::USD).to_decimal_string(), "49.99");
+        assert_eq!(Money::new(-5, Currency::USD).to_decimal_string(), "-0.05");
+        assert_eq!(Money::new(1500, Currency::JPY).to_decimal_string(), "1500");
+        assert_eq!(
+            Money::parse_decimal("49.9", Currency::USD).unwrap(),
+            Money::new(4990, Currency::USD)
+        );
+        assert_eq!(
+            Money::parse_decimal("-0.05", Currency::USD).unwrap(),
+            Money::new(-5, Currency::USD)
+        );
+        for bad in ["", "49.999", "4a.00", "1.", ".5", "1e3", "+1"] {
+            assert!(Money::parse_decimal(bad, Currency::USD).is_err(), "{bad}");
+        }
+        assert!(Money::parse_decimal("1.5", Currency::JPY).is_err());
+    }
+
+    #[test]
+    fn test_string_amount_serde() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Line {
+            #[serde(with = "string_amount")]
+            price: Money,
+            #[serde(with = "string_amount::option")]
+            compare_at: Option<Money>,
+        }
+
+        let line = Line {
+            price: Money::new(4999, Currency::USD),
+            compare_at: None,
+        };
+        let json = serde_json::to_string(&line).unwrap();
+        assert_eq!(
+            json,
+            r#"{"price":{"amount":"49.99","currency":"USD"},"compare_at":null}"#
+        );
+        assert_eq!(serde_json::from_str::<Line>(&json).unwrap(), line);
+
+        let parsed: Line = serde_json::from_str(
+            r#"{"price":{"amount":"10","currency":"EUR"},"compare_at":{"amount":"12.50","currency":"EUR"}}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.compare_at, Some(Money::new(1250, Currency::EUR)));
+        assert!(serde_json::from_str::<Line>(
+            r#"{"price":{"amount":49.99,"currency":"USD"},"compare_at":null}"#
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_currency_from_code() {
         assert_eq!(Currency::from_code("USD"), Some(Currency::USD));
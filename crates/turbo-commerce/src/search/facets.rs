@@ -0,0 +1,393 @@
+//! Facet aggregation over full result sets.
+//!
+//! Counts cover every document matching the query, not just the current
+//! page. Facets are disjunctive by default: filters on a facet's own field
+//! are ignored when counting that facet, so selecting "Black" still shows
+//! how many "Orange" products there are.
+
+use crate::money::{Currency, Money};
+use crate::search::index::matches_filter;
+use crate::search::{Facet, Filter, ScoredDocument, FACET_CATEGORY, FACET_PRODUCT_TYPE, FACET_TAG};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Facet field for price buckets.
+pub const FACET_PRICE: &str = "price";
+/// Facet field for minimum ratings.
+pub const FACET_RATING: &str = "rating";
+
+/// An inclusive price range counted by the price facet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PriceBucket {
+    /// Lowest price in cents (None for no lower bound).
+    pub min_cents: Option<i64>,
+    /// Highest price in cents (None for no upper bound).
+    pub max_cents: Option<i64>,
+}
+
+impl PriceBucket {
+    /// Create a bucket.
+    pub fn new(min_cents: Option<i64>, max_cents: Option<i64>) -> Self {
+        Self {
+            min_cents,
+            max_cents,
+        }
+    }
+
+    /// Facet value for the bucket (e.g., "1000:2499", "5000:").
+    pub fn key(&self) -> String {
+        let bound = |b: Option<i64>| b.map(|c| c.to_string()).unwrap_or_default();
+        format!("{}:{}", bound(self.min_cents), bound(self.max_cents))
+    }
+
+    /// Check if a price falls in the bucket.
+    pub fn contains(&self, price_cents: i64) -> bool {
+        let above_min = match self.min_cents {
+            Some(min) => price_cents >= min,
+            None => true,
+        };
+        let below_max = match self.max_cents {
+            Some(max) => price_cents <= max,
+            None => true,
+        };
+        above_min && below_max
+    }
+
+    /// The filter that selects this bucket.
+    pub fn to_filter(&self, currency: Currency) -> Filter {
+        Filter::price_range(
+            self.min_cents.map(|c| Money::new(c, currency)),
+            self.max_cents.map(|c| Money::new(c, currency)),
+        )
+    }
+}
+
+/// Computes facet counts for a search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacetAggregator {
+    /// Terms facets to return, in order (empty for every indexed field).
+    pub fields: Vec<String>,
+    /// Buckets for the price facet (empty to omit it).
+    pub price_buckets: Vec<PriceBucket>,
+    /// Minimum ratings for the rating facet (e.g., 4.0 for "4 & up").
+    pub rating_thresholds: Vec<f64>,
+    /// Whether a facet's own filters are ignored when counting it.
+    pub disjunctive: bool,
+    /// Most values returned per terms facet (0 for unlimited).
+    pub max_values: usize,
+}
+
+impl Default for FacetAggregator {
+    fn default() -> Self {
+        Self {
+            fields: Vec::new(),
+            price_buckets: Vec::new(),
+            rating_thresholds: vec![4.0, 3.0, 2.0, 1.0],
+            disjunctive: true,
+            max_values: 0,
+        }
+    }
+}
+
+impl FacetAggregator {
+    /// Create an aggregator over every indexed field.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return these terms facets, in this order.
+    pub fn with_fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fields = fields
+            .into_iter()
+            .map(|f| f.into().to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Set the price buckets.
+    pub fn with_price_buckets(mut self, buckets: Vec<PriceBucket>) -> Self {
+        self.price_buckets = buckets;
+        self
+    }
+
+    /// Set the rating thresholds.
+    pub fn with_rating_thresholds(mut self, thresholds: Vec<f64>) -> Self {
+        self.rating_thresholds = thresholds;
+        self
+    }
+
+    /// Count facets conjunctively (only over the filtered results).
+    pub fn conjunctive(mut self) -> Self {
+        self.disjunctive = false;
+        self
+    }
+
+    /// Cap the number of values per terms facet.
+    pub fn with_max_values(mut self, max_values: usize) -> Self {
+        self.max_values = max_values;
+        self
+    }
+
+    /// Aggregate facets over the documents matching the query text.
+    ///
+    /// `candidates` must not have the filters applied yet; this applies them
+    /// itself, relaxing each facet's own filters when disjunctive.
+    pub fn aggregate(&self, candidates: &[ScoredDocument<'_>], filters: &[Filter]) -> Vec<Facet> {
+        let mut terms: BTreeMap<&str, BTreeMap<String, (String, i64)>> = BTreeMap::new();
+        let mut prices = vec![0_i64; self.price_buckets.len()];
+        let mut ratings = vec![0_i64; self.rating_thresholds.len()];
+
+        for candidate in candidates {
+            // Facet fields whose filters reject the document.
+            let mut failed: HashSet<String> = HashSet::new();
+            let mut excluded = false;
+            for filter in filters {
+                if matches_filter(candidate, filter) {
+                    continue;
+                }
+                match facet_field(filter) {
+                    Some(field) if self.disjunctive => {
+                        failed.insert(field);
+                    }
+                    _ => excluded = true,
+                }
+            }
+            if excluded || failed.len() > 1 {
+                continue;
+            }
+            let counts_for = |field: &str| match failed.iter().next() {
+                Some(only) => only == field,
+                None => true,
+            };
+
+            let document = candidate.document;
+            for (field, values) in &document.facets {
+                if !counts_for(field) {
+                    continue;
+                }
+                let field_counts = terms.entry(field.as_str()).or_default();
+                for value in values {
+                    field_counts
+                        .entry(value.to_lowercase())
+                        .or_insert_with(|| (value.clone(), 0))
+                        .1 += 1;
+                }
+            }
+            if let (true, Some(price)) = (counts_for(FACET_PRICE), document.price_cents) {
+                for (count, bucket) in prices.iter_mut().zip(&self.price_buckets) {
+                    if bucket.contains(price) {
+                        *count += 1;
+                    }
+                }
+            }
+            if let (true, Some(rating)) = (counts_for(FACET_RATING), candidate.rating) {
+                for (count, threshold) in ratings.iter_mut().zip(&self.rating_thresholds) {
+                    if rating >= *threshold {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+
+        let selected = selected_values(filters);
+        let fields: Vec<&str> = if self.fields.is_empty() {
+            terms.keys().copied().collect()
+        } else {
+            self.fields.iter().map(String::as_str).collect()
+        };
+
+        let mut facets = Vec::new();
+        for field in fields {
+            let Some(values) = terms.remove(field) else {
+                continue;
+            };
+            let mut values: Vec<_> = values.into_iter().collect();
+            values.sort_by(|(key_a, (_, a)), (key_b, (_, b))| b.cmp(a).then(key_a.cmp(key_b)));
+            if self.max_values > 0 {
+                values.truncate(self.max_values);
+            }
+            let mut facet = Facet::terms(field, field);
+            for (key, (value, count)) in values {
+                let is_selected = selected.contains(&(field.to_string(), key));
+                facet.add_value(value, count, is_selected);
+            }
+            facets.push(facet);
+        }
+
+        if prices.iter().any(|c| *c > 0) {
+            let mut facet = Facet::range(FACET_PRICE, FACET_PRICE);
+            for (bucket, count) in self.price_buckets.iter().zip(prices) {
+                let is_selected = filters.iter().any(|f| match f {
+                    Filter::PriceRange { min, max } => {
+                        min.map(|m| m.amount_cents) == bucket.min_cents
+                            && max.map(|m| m.amount_cents) == bucket.max_cents
+                    }
+                    _ => false,
+                });
+                facet.add_value(bucket.key(), count, is_selected);
+            }
+            facets.push(facet);
+        }
+
+        if ratings.iter().any(|c| *c > 0) {
+            let mut facet = Facet::range(FACET_RATING, FACET_RATING);
+            for (threshold, count) in self.rating_thresholds.iter().zip(ratings) {
+                let is_selected = filters
+                    .iter()
+                    .any(|f| matches!(f, Filter::Rating { min } if min == threshold));
+                facet.add_value(threshold.to_string(), count, is_selected);
+            }
+            facets.push(facet);
+        }
+
+        facets
+    }
+}
+
+/// The facet a filter selects values of, if any.
+fn facet_field(filter: &Filter) -> Option<String> {
+    match filter {
+        Filter::Category(_) | Filter::Categories(_) => Some(FACET_CATEGORY.to_string()),
+        Filter::Tag(_) | Filter::Tags(_) => Some(FACET_TAG.to_string()),
+        Filter::ProductType(_) => Some(FACET_PRODUCT_TYPE.to_string()),
+        Filter::Attribute { name, .. } => Some(name.to_lowercase()),
+        Filter::PriceRange { .. } => Some(FACET_PRICE.to_string()),
+        Filter::Rating { .. } => Some(FACET_RATING.to_string()),
+        _ => None,
+    }
+}
+
+/// Lowercased (field, value) pairs selected by terms filters.
+fn selected_values(filters: &[Filter]) -> HashSet<(String, String)> {
+    filters
+        .iter()
+        .flat_map(|filter| -> Vec<(&str, &str)> {
+            match filter {
+                Filter::Category(id) => vec![(FACET_CATEGORY, id.as_str())],
+                Filter::Categories(ids) => {
+                    ids.iter().map(|id| (FACET_CATEGORY, id.as_str())).collect()
+                }
+                Filter::Tag(tag) => vec![(FACET_TAG, tag.as_str())],
+                Filter::Tags(tags) => tags.iter().map(|t| (FACET_TAG, t.as_str())).collect(),
+                Filter::ProductType(pt) => vec![(FACET_PRODUCT_TYPE, pt.as_str())],
+                Filter::Attribute { name, values } => {
+                    values.iter().map(|v| (name.as_str(), v.as_str())).collect()
+                }
+                _ => Vec::new(),
+            }
+        })
+        .map(|(field, value)| (field.to_lowercase(), value.to_lowercase()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::{Product, ProductOption};
+    use crate::search::{SearchIndexer, SearchQuery};
+
+    fn index() -> SearchIndexer {
+        let mut products = Vec::new();
+        for (sku, price, colors) in [
+            ("TEE", 2500, ["Orange", "Black"].as_slice()),
+            ("HOODIE", 6000, ["Black"].as_slice()),
+            ("CAP", 1500, ["Orange"].as_slice()),
+        ] {
+            let mut p = Product::new(sku, sku, sku.to_lowercase());
+            p.set_option(ProductOption::new("Color", colors.iter().copied()));
+            p.generate_variants(Money::new(price, Currency::USD), &[])
+                .unwrap();
+            products.push(p);
+        }
+        let mut index = SearchIndexer::build(&products).with_facet_aggregator(
+            FacetAggregator::new()
+                .with_fields(["color"])
+                .with_price_buckets(vec![
+                    PriceBucket::new(None, Some(2499)),
+                    PriceBucket::new(Some(2500), Some(4999)),
+                    PriceBucket::new(Some(5000), None),
+                ])
+                .with_rating_thresholds(vec![4.0, 3.0]),
+        );
+        index.set_rating(&products[0].id, 4.5);
+        index.set_rating(&products[1].id, 3.2);
+        index
+    }
+
+    fn counts(facet: &Facet) -> Vec<(&str, i64, bool)> {
+        facet
+            .values
+            .iter()
+            .map(|v| (v.value.as_str(), v.count, v.selected))
+            .collect()
+    }
+
+    #[test]
+    fn test_disjunctive_facets() {
+        let index = index();
+        let query = SearchQuery::new()
+            .with_filter(Filter::attribute("Color", vec!["black".into()]))
+            .with_pagination(1, 1)
+            .with_facets();
+        let results = index.search(&query);
+        assert_eq!(results.pagination.total, 2);
+        assert_eq!(results.len(), 1);
+
+        // Color ignores its own filter; price counts only black products.
+        let color = &results.facets[0];
+        assert_eq!(counts(color), [("Black", 2, true), ("Orange", 2, false)]);
+        let price = results
+            .facets
+            .iter()
+            .find(|f| f.field == FACET_PRICE)
+            .unwrap();
+        assert_eq!(
+            counts(price),
+            [
+                (":2499", 0, false),
+                ("2500:4999", 1, false),
+                ("5000:", 1, false)
+            ]
+        );
+        let rating = results
+            .facets
+            .iter()
+            .find(|f| f.field == FACET_RATING)
+            .unwrap();
+        assert_eq!(counts(rating), [("4", 1, false), ("3", 2, false)]);
+
+        // A rating filter narrows color counts but not its own.
+        let rated = index.search(&query.clone().with_filter(Filter::Rating { min: 4.0 }));
+        assert_eq!(rated.pagination.total, 1);
+        assert_eq!(
+            counts(&rated.facets[0]),
+            [("Black", 1, true), ("Orange", 1, false)]
+        );
+        let rating = rated
+            .facets
+            .iter()
+            .find(|f| f.field == FACET_RATING)
+            .unwrap();
+        assert_eq!(counts(rating), [("4", 1, true), ("3", 2, false)]);
+    }
+
+    #[test]
+    fn test_conjunctive_facets() {
+        let index = index();
+        let aggregator = FacetAggregator::new().with_fields(["color"]).conjunctive();
+        let index = index.with_facet_aggregator(aggregator);
+        let results = index.search(
+            &SearchQuery::new()
+                .with_filter(Filter::attribute("Color", vec!["black".into()]))
+                .with_facets(),
+        );
+        assert_eq!(
+            counts(&results.facets[0]),
+            [("Black", 2, true), ("Orange", 1, false)]
+        );
+    }
+}
@@ -0,0 +1,790 @@
+//! In-process search index over the catalog.
+//!
+//! [`SearchIndexer`] keeps an inverted index of product text and a table of
+//! facet values, and executes [`SearchQuery`]s against them. With the
+//! `storage` feature the indexed documents are persisted in turbo-db so the
+//! index can be reloaded instead of rebuilt.
+
+use crate::catalog::{Category, Product};
+use crate::ids::{CategoryId, ProductId};
+use crate::search::rewrite::stem;
+use crate::search::suggest::{edit_distance, typo_budget};
+use crate::search::{
+    FacetAggregator, Filter, Pagination, QueryRewriter, RankingConfig, ScoredDocument, SearchField,
+    SearchQuery, SearchResults, SortOption, TermGroup,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Facet field for categories.
+pub const FACET_CATEGORY: &str = "category";
+/// Facet field for tags.
+pub const FACET_TAG: &str = "tag";
+/// Facet field for product type.
+pub const FACET_PRODUCT_TYPE: &str = "product_type";
+/// Attribute holding the product's brand.
+pub const BRAND_ATTRIBUTE: &str = "brand";
+
+/// The searchable projection of a product.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexedDocument {
+    /// The product.
+    pub product_id: ProductId,
+    /// Product name.
+    pub name: String,
+    /// Product SKU.
+    pub sku: String,
+    /// Product status (e.g., "active").
+    pub status: String,
+    /// Lowest variant price in cents (None if the product has no variants).
+    pub price_cents: Option<i64>,
+    /// Whether any variant can be purchased.
+    pub in_stock: bool,
+    /// Unix timestamp of creation.
+    pub created_at: i64,
+    /// Unix timestamp of last update.
+    pub updated_at: i64,
+    /// Brand, from the `brand` attribute.
+    #[serde(default)]
+    pub brand: Option<String>,
+    /// Fields each term appears in, as a mask of [`SearchField::bit`]s.
+    pub terms: BTreeMap<String, u32>,
+    /// Facet values by field (categories, tags, options, attributes).
+    pub facets: BTreeMap<String, Vec<String>>,
+}
+
+impl IndexedDocument {
+    /// Build the document for a product.
+    pub fn from_product(product: &Product) -> Self {
+        let brand = product
+            .attributes
+            .get(BRAND_ATTRIBUTE)
+            .map(|value| value.facet_value());
+
+        let mut terms = BTreeMap::new();
+        add_terms(&mut terms, &product.name, SearchField::Name);
+        add_terms(&mut terms, &product.sku, SearchField::Sku);
+        if let Some(brand) = &brand {
+            add_terms(&mut terms, brand, SearchField::Brand);
+        }
+        for id in &product.category_ids {
+            add_terms(&mut terms, id.as_str(), SearchField::Category);
+        }
+        for tag in &product.tags {
+            add_terms(&mut terms, tag, SearchField::Tag);
+        }
+        for text in [&product.short_description, &product.description]
+            .into_iter()
+            .flatten()
+        {
+            add_terms(&mut terms, text, SearchField::Description);
+        }
+
+        let mut facets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut add_facet = |field: &str, value: String| {
+            let values = facets.entry(field.to_lowercase()).or_default();
+            if !values.iter().any(|v| v.eq_ignore_ascii_case(&value)) {
+                values.push(value);
+            }
+        };
+        for id in &product.category_ids {
+            add_facet(FACET_CATEGORY, id.to_string());
+        }
+        for tag in &product.tags {
+            add_facet(FACET_TAG, tag.clone());
+        }
+        add_facet(
+            FACET_PRODUCT_TYPE,
+            product.product_type.as_str().to_string(),
+        );
+        for variant in &product.variants {
+            for option in &variant.options {
+                add_facet(&option.name, option.value.clone());
+            }
+        }
+        for (key, value) in &product.attributes {
+            add_facet(key, value.facet_value());
+        }
+
+        Self {
+            product_id: product.id.clone(),
+            name: product.name.clone(),
+            sku: product.sku.clone(),
+            status: product.status.as_str().to_string(),
+            price_cents: product.variants.iter().map(|v| v.price.amount_cents).min(),
+            in_stock: product.variants.is_empty()
+                || product.variants.iter().any(|v| v.inventory.is_available()),
+            created_at: product.created_at,
+            updated_at: product.updated_at,
+            brand,
+            terms,
+            facets,
+        }
+    }
+
+    fn has_facet(&self, field: &str, value: &str) -> bool {
+        self.facets
+            .get(&field.to_lowercase())
+            .map(|values| values.iter().any(|v| v.eq_ignore_ascii_case(value)))
+            .unwrap_or(false)
+    }
+}
+
+/// An inverted index and facet table over a product collection.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndexer {
+    documents: HashMap<ProductId, IndexedDocument>,
+    postings: HashMap<String, HashMap<ProductId, u32>>,
+    /// Indexed terms by stem, for stemmed matching.
+    stems: HashMap<String, HashSet<String>>,
+    category_names: HashMap<CategoryId, String>,
+    popularity: HashMap<ProductId, u64>,
+    ratings: HashMap<ProductId, f64>,
+    ranking: RankingConfig,
+    facets: FacetAggregator,
+    rewriter: QueryRewriter,
+}
+
+impl SearchIndexer {
+    /// Create an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build an index from a product collection.
+    pub fn build<'a>(products: impl IntoIterator<Item = &'a Product>) -> Self {
+        let mut index = Self::new();
+        for product in products {
+            index.upsert(product);
+        }
+        index
+    }
+
+    /// Index category names so products match searches for their categories.
+    ///
+    /// Set before indexing; products already indexed keep their old terms
+    /// until re-indexed.
+    pub fn with_categories<'a>(
+        mut self,
+        categories: impl IntoIterator<Item = &'a Category>,
+    ) -> Self {
+        for category in categories {
+            self.category_names
+                .insert(category.id.clone(), category.name.clone());
+        }
+        self
+    }
+
+    /// Set how relevance-sorted results are scored and ordered.
+    pub fn with_ranking(mut self, ranking: RankingConfig) -> Self {
+        self.ranking = ranking;
+        self
+    }
+
+    /// The ranking configuration.
+    pub fn ranking(&self) -> &RankingConfig {
+        &self.ranking
+    }
+
+    /// Set the store's synonyms, stemming, and spelling correction.
+    pub fn with_rewriter(mut self, rewriter: QueryRewriter) -> Self {
+        self.rewriter = rewriter;
+        self
+    }
+
+    /// Set how facets are counted.
+    pub fn with_facet_aggregator(mut self, facets: FacetAggregator) -> Self {
+        self.facets = facets;
+        self
+    }
+
+    /// Record a product's popularity signal (e.g., units sold recently).
+    pub fn set_popularity(&mut self, product_id: &ProductId, popularity: u64) {
+        self.popularity.insert(product_id.clone(), popularity);
+    }
+
+    /// Record a product's average review rating.
+    pub fn set_rating(&mut self, product_id: &ProductId, rating: f64) {
+        self.ratings.insert(product_id.clone(), rating);
+    }
+
+    /// Number of indexed products.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Check if the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Get the indexed document for a product.
+    pub fn document(&self, product_id: &ProductId) -> Option<&IndexedDocument> {
+        self.documents.get(product_id)
+    }
+
+    /// Add or re-index a product after it changes.
+    pub fn upsert(&mut self, product: &Product) {
+        let document = self.document_for(product);
+        self.insert_document(document);
+    }
+
+    fn document_for(&self, product: &Product) -> IndexedDocument {
+        let mut document = IndexedDocument::from_product(product);
+        for id in &product.category_ids {
+            if let Some(name) = self.category_names.get(id) {
+                add_terms(&mut document.terms, name, SearchField::Category);
+            }
+        }
+        document
+    }
+
+    /// Remove a product from the index.
+    ///
+    /// Returns true if the product was indexed.
+    pub fn remove(&mut self, product_id: &ProductId) -> bool {
+        let Some(document) = self.documents.remove(product_id) else {
+            return false;
+        };
+        for term in document.terms.keys() {
+            if let Some(posting) = self.postings.get_mut(term) {
+                posting.remove(product_id);
+                if posting.is_empty() {
+                    self.postings.remove(term);
+                    let key = stem(term);
+                    if let Some(terms) = self.stems.get_mut(&key) {
+                        terms.remove(term);
+                        if terms.is_empty() {
+                            self.stems.remove(&key);
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn insert_document(&mut self, document: IndexedDocument) {
+        self.remove(&document.product_id);
+        for (term, fields) in &document.terms {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(document.product_id.clone(), *fields);
+            self.stems
+                .entry(stem(term))
+                .or_default()
+                .insert(term.clone());
+        }
+        self.documents.insert(document.product_id.clone(), document);
+    }
+
+    /// Execute a search query.
+    ///
+    /// Text terms must all match (the last term also matches as a prefix,
+    /// for search-as-you-type), after the index's [`QueryRewriter`] expands
+    /// them. If nothing matches and spelling correction is on, the corrected
+    /// query is searched instead and reported in `corrected_query`.
+    /// Relevance sorting scores and orders results by the index's
+    /// [`RankingConfig`].
+    pub fn search(&self, query: &SearchQuery) -> SearchResults<ProductId> {
+        let mut text_terms: Vec<String> = query.query.as_deref().map(tokenize).unwrap_or_default();
+        for filter in &query.filters {
+            if let Filter::Text(text) = filter {
+                for term in tokenize(text) {
+                    if !text_terms.contains(&term) {
+                        text_terms.push(term);
+                    }
+                }
+            }
+        }
+
+        let mut text_scores = self.score(&self.rewriter.expand(&text_terms));
+        let mut corrected_query = None;
+        if self.rewriter.spelling_correction && text_scores.as_ref().is_some_and(|s| s.is_empty()) {
+            if let Some(corrected) = self.correct(&text_terms) {
+                text_scores = self.score(&self.rewriter.expand(&corrected));
+                corrected_query = Some(corrected.join(" "));
+            }
+        }
+
+        let matches: Vec<(&IndexedDocument, u32)> = match text_scores {
+            Some(scores) => scores
+                .into_iter()
+                .filter_map(|(id, score)| self.documents.get(id).map(|d| (d, score)))
+                .collect(),
+            None => self.documents.values().map(|d| (d, 0)).collect(),
+        };
+        let candidates: Vec<ScoredDocument<'_>> = matches
+            .into_iter()
+            .map(|(document, text_score)| {
+                let popularity = self
+                    .popularity
+                    .get(&document.product_id)
+                    .copied()
+                    .unwrap_or(0);
+                ScoredDocument {
+                    document,
+                    score: self.ranking.score(text_score, document, popularity),
+                    popularity,
+                    rating: self.ratings.get(&document.product_id).copied(),
+                }
+            })
+            .collect();
+
+        let facets = if query.include_facets {
+            self.facets.aggregate(&candidates, &query.filters)
+        } else {
+            Vec::new()
+        };
+
+        let mut scored: Vec<ScoredDocument<'_>> = candidates
+            .into_iter()
+            .filter(|candidate| {
+                query
+                    .filters
+                    .iter()
+                    .all(|filter| matches_filter(candidate, filter))
+            })
+            .collect();
+        sort_results(&mut scored, query.sort, &self.ranking);
+
+        let page = query.page.max(1);
+        let per_page = query.per_page.max(1);
+        let pagination = Pagination::new(page, per_page, scored.len() as i64);
+        let items = scored
+            .into_iter()
+            .skip(pagination.offset() as usize)
+            .take(per_page as usize)
+            .map(|s| s.document.product_id.clone())
+            .collect();
+
+        let mut results = SearchResults::new(items, pagination).with_facets(facets);
+        results.corrected_query = corrected_query;
+        results
+    }
+
+    /// Text-match scores of documents matching every group; None if there are no terms.
+    fn score(&self, groups: &[TermGroup]) -> Option<HashMap<&ProductId, u32>> {
+        let last = groups.len().checked_sub(1)?;
+        let mut scores: Option<HashMap<&ProductId, u32>> = None;
+
+        for (i, group) in groups.iter().enumerate() {
+            let mut matched: HashMap<&ProductId, u32> = HashMap::new();
+            for (a, words) in group.alternatives.iter().enumerate() {
+                // Only what was typed last matches as a prefix, not its synonyms.
+                let prefix = i == last && a == 0;
+                for (id, weight) in self.match_words(words, prefix) {
+                    let entry = matched.entry(id).or_insert(0);
+                    *entry = (*entry).max(weight);
+                }
+            }
+            scores = Some(match scores {
+                None => matched,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(id, score)| matched.get(id).map(|w| (id, score + w)))
+                    .collect(),
+            });
+        }
+        scores
+    }
+
+    /// Documents containing every word; the last word may match as a prefix.
+    fn match_words(&self, words: &[String], prefix: bool) -> HashMap<&ProductId, u32> {
+        let mut matched: Option<HashMap<&ProductId, u32>> = None;
+        for (i, word) in words.iter().enumerate() {
+            let hits = self.match_term(word, prefix && i + 1 == words.len());
+            matched = Some(match matched {
+                None => hits,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(id, score)| hits.get(id).map(|w| (id, score + w)))
+                    .collect(),
+            });
+        }
+        matched.unwrap_or_default()
+    }
+
+    fn match_term(&self, term: &str, prefix: bool) -> HashMap<&ProductId, u32> {
+        let mut hits: Vec<&HashMap<ProductId, u32>> = if prefix {
+            self.postings
+                .iter()
+                .filter(|(indexed, _)| indexed.starts_with(term))
+                .map(|(_, posting)| posting)
+                .collect()
+        } else {
+            self.postings.get(term).into_iter().collect()
+        };
+        if self.rewriter.stemming {
+            if let Some(terms) = self.stems.get(&stem(term)) {
+                hits.extend(terms.iter().filter_map(|t| self.postings.get(t)));
+            }
+        }
+
+        let mut matched: HashMap<&ProductId, u32> = HashMap::new();
+        for (id, fields) in hits.into_iter().flatten() {
+            let weight = self.ranking.field_weights.score(*fields);
+            let entry = matched.entry(id).or_insert(0);
+            *entry = (*entry).max(weight);
+        }
+        matched
+    }
+
+    /// Replace unmatched terms with the closest indexed term, preferring
+    /// common ones. None if nothing changes or a term has no close match.
+    fn correct(&self, terms: &[String]) -> Option<Vec<String>> {
+        let mut changed = false;
+        let mut corrected = Vec::with_capacity(terms.len());
+        for (i, term) in terms.iter().enumerate() {
+            if !self.match_term(term, i + 1 == terms.len()).is_empty() {
+                corrected.push(term.clone());
+                continue;
+            }
+            let budget = typo_budget(term);
+            let (_, _, best) = self
+                .postings
+                .iter()
+                .map(|(indexed, posting)| (edit_distance(term, indexed), indexed, posting.len()))
+                .filter(|(distance, _, _)| *distance <= budget)
+                .map(|(distance, indexed, count)| (distance, std::cmp::Reverse(count), indexed))
+                .min()?;
+            corrected.push(best.clone());
+            changed = true;
+        }
+        changed.then_some(corrected)
+    }
+}
+
+pub(super) fn matches_filter(candidate: &ScoredDocument<'_>, filter: &Filter) -> bool {
+    let doc = candidate.document;
+    match filter {
+        Filter::Category(id) => doc.has_facet(FACET_CATEGORY, id.as_str()),
+        Filter::Categories(ids) => ids
+            .iter()
+            .any(|id| doc.has_facet(FACET_CATEGORY, id.as_str())),
+        Filter::PriceRange { min, max } => match doc.price_cents {
+            Some(price) => {
+                min.map(|m| price >= m.amount_cents).unwrap_or(true)
+                    && max.map(|m| price <= m.amount_cents).unwrap_or(true)
+            }
+            None => min.is_none() && max.is_none(),
+        },
+        Filter::InStock => doc.in_stock,
+        Filter::Tag(tag) => doc.has_facet(FACET_TAG, tag),
+        Filter::Tags(tags) => tags.iter().any(|t| doc.has_facet(FACET_TAG, t)),
+        Filter::Attribute { name, values } => values.iter().any(|v| doc.has_facet(name, v)),
+        Filter::ProductType(pt) => doc.has_facet(FACET_PRODUCT_TYPE, pt),
+        Filter::Status(status) => doc.status.eq_ignore_ascii_case(status),
+        Filter::SkuPrefix(prefix) => doc.sku.to_lowercase().starts_with(&prefix.to_lowercase()),
+        Filter::DateRange { field, start, end } => {
+            let value = match field.as_str() {
+                "updated_at" => doc.updated_at,
+                _ => doc.created_at,
+            };
+            start.map(|s| value >= s).unwrap_or(true) && end.map(|e| value <= e).unwrap_or(true)
+        }
+        Filter::Rating { min } => match candidate.rating {
+            Some(rating) => rating >= *min,
+            None => false,
+        },
+        // Text is handled by scoring.
+        Filter::Text(_) => true,
+    }
+}
+
+fn sort_results(results: &mut [ScoredDocument<'_>], sort: SortOption, ranking: &RankingConfig) {
+    results.sort_by(|scored_a, scored_b| {
+        let (a, b) = (scored_a.document, scored_b.document);
+        let primary = match sort {
+            SortOption::PriceAsc => a
+                .price_cents
+                .unwrap_or(i64::MAX)
+                .cmp(&b.price_cents.unwrap_or(i64::MAX)),
+            SortOption::PriceDesc => b.price_cents.cmp(&a.price_cents),
+            SortOption::NameAsc => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortOption::NameDesc => b.name.to_lowercase().cmp(&a.name.to_lowercase()),
+            SortOption::Oldest => a.created_at.cmp(&b.created_at),
+            SortOption::Newest => b.created_at.cmp(&a.created_at),
+            SortOption::Rating => scored_b
+                .rating
+                .unwrap_or(0.0)
+                .total_cmp(&scored_a.rating.unwrap_or(0.0))
+                .then_with(|| ranking.compare(scored_a, scored_b)),
+            SortOption::BestSelling => scored_b
+                .popularity
+                .cmp(&scored_a.popularity)
+                .then_with(|| ranking.compare(scored_a, scored_b)),
+            _ => ranking.compare(scored_a, scored_b),
+        };
+        primary.then_with(|| a.product_id.as_str().cmp(b.product_id.as_str()))
+    });
+}
+
+/// Split text into lowercase alphanumeric terms.
+pub(super) fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_lowercase())
+        .collect()
+}
+
+fn add_terms(terms: &mut BTreeMap<String, u32>, text: &str, field: SearchField) {
+    for term in tokenize(text) {
+        *terms.entry(term).or_insert(0) |= field.bit();
+    }
+}
+
+#[cfg(feature = "storage")]
+mod storage {
+    use super::*;
+    use crate::error::CommerceError;
+    use turbo_db::{params, Db};
+
+    /// SQL to create the table that backs the search index.
+    pub const SEARCH_INDEX_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS search_documents (
+        product_id TEXT PRIMARY KEY,
+        document TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    )";
+
+    #[derive(Deserialize)]
+    struct DocumentRow {
+        document: String,
+    }
+
+    impl SearchIndexer {
+        /// Load a previously persisted index.
+        pub fn load(db: &Db) -> Result<Self, CommerceError> {
+            let rows: Vec<DocumentRow> =
+                db.query_as("SELECT document FROM search_documents", params![])?;
+            let mut index = Self::new();
+            for row in rows {
+                index.insert_document(serde_json::from_str(&row.document)?);
+            }
+            Ok(index)
+        }
+
+        /// Re-index a changed product and persist its document.
+        pub fn sync_product(&mut self, db: &Db, product: &Product) -> Result<(), CommerceError> {
+            let document = self.document_for(product);
+            db.execute(
+                "INSERT INTO search_documents (product_id, document, updated_at) VALUES (?, ?, ?)
+                 ON CONFLICT(product_id) DO UPDATE SET document = excluded.document,
+                 updated_at = excluded.updated_at",
+                params![
+                    document.product_id.as_str(),
+                    serde_json::to_string(&document)?,
+                    document.updated_at
+                ],
+            )?;
+            self.insert_document(document);
+            Ok(())
+        }
+
+        /// Remove a product from the index and from storage.
+        pub fn delete_product(
+            &mut self,
+            db: &Db,
+            product_id: &ProductId,
+        ) -> Result<(), CommerceError> {
+            db.execute(
+                "DELETE FROM search_documents WHERE product_id = ?",
+                params![product_id.as_str()],
+            )?;
+            self.remove(product_id);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "storage")]
+pub use storage::SEARCH_INDEX_SCHEMA;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::{ProductOption, ProductVariant};
+    use crate::money::{Currency, Money};
+
+    fn product(sku: &str, name: &str, price: i64, tags: &[&str]) -> Product {
+        let mut p = Product::new(sku, name, sku.to_lowercase());
+        for tag in tags {
+            p.add_tag(*tag);
+        }
+        p.variants.push(ProductVariant::new(
+            p.id.clone(),
+            sku,
+            Money::new(price, Currency::USD),
+        ));
+        p
+    }
+
+    fn catalog() -> Vec<Product> {
+        let mut tee = product("TEE", "Rust Logo Tee", 2500, &["apparel"]);
+        tee.set_option(ProductOption::new("Color", ["Orange", "Black"]));
+        tee.generate_variants(Money::new(2500, Currency::USD), &[])
+            .unwrap();
+        vec![
+            tee,
+            product("BOOK", "The Rust Programming Book", 4999, &["books"]),
+            product("MUG", "Ferris Mug", 1500, &["kitchen"]),
+        ]
+    }
+
+    #[test]
+    fn test_text_search_and_ranking() {
+        let index = SearchIndexer::build(&catalog());
+        let results = index.search(&SearchQuery::new().with_query("rust"));
+        assert_eq!(results.pagination.total, 2);
+
+        let prefix = index.search(&SearchQuery::new().with_query("rust prog"));
+        assert_eq!(prefix.items.len(), 1);
+        assert_eq!(index.document(&prefix.items[0]).unwrap().sku, "BOOK");
+    }
+
+    #[test]
+    fn test_relevance_ranking() {
+        use crate::catalog::{AttributeValue, InventoryLevel};
+        use crate::search::RankingRule;
+
+        let category = Category::new_root("Drinkware", "drinkware");
+        let mut mug = product("MUG", "Ferris Mug", 1500, &["kitchen"]);
+        mug.category_ids.push(category.id.clone());
+        let mut flask = product("FLASK", "Steel Flask", 3000, &[]);
+        flask.description = Some("Pairs well with a mug".to_string());
+        let mut cup = product("CUP", "Travel Cup", 1200, &["mug"]);
+        cup.attributes.insert(
+            "brand".to_string(),
+            AttributeValue::Text("Crab".to_string()),
+        );
+
+        let mut index = SearchIndexer::new().with_categories([&category]);
+        for p in [&mug, &flask, &cup] {
+            index.upsert(p);
+        }
+        let skus = |index: &SearchIndexer, q: &str| -> Vec<String> {
+            index
+                .search(&SearchQuery::new().with_query(q))
+                .items
+                .iter()
+                .map(|id| index.document(id).unwrap().sku.clone())
+                .collect()
+        };
+
+        // Name beats tag beats description.
+        assert_eq!(skus(&index, "mug"), ["MUG", "CUP", "FLASK"]);
+        assert_eq!(skus(&index, "drinkware"), ["MUG"]);
+        assert_eq!(skus(&index, "crab"), ["CUP"]);
+
+        // Enough popularity outweighs the field difference.
+        index.set_popularity(&flask.id, 1 << 20);
+        assert_eq!(skus(&index, "mug")[0], "FLASK");
+
+        // Rules can put stock ahead of score.
+        cup.variants[0].inventory = InventoryLevel::new(0);
+        index.upsert(&cup);
+        index = index.with_ranking(
+            RankingConfig::default()
+                .with_popularity_boost(0)
+                .with_rules(vec![RankingRule::InStock, RankingRule::Score]),
+        );
+        assert_eq!(skus(&index, "mug"), ["MUG", "FLASK", "CUP"]);
+    }
+
+    #[test]
+    fn test_filters_sort_and_facets() {
+        let index = SearchIndexer::build(&catalog());
+        let query = SearchQuery::new()
+            .with_filter(Filter::price_range(
+                Some(Money::new(2000, Currency::USD)),
+                None,
+            ))
+            .with_sort(SortOption::PriceDesc)
+            .with_facets();
+        let results = index.search(&query);
+        let skus: Vec<&str> = results
+            .items
+            .iter()
+            .map(|id| index.document(id).unwrap().sku.as_str())
+            .collect();
+        assert_eq!(skus, vec!["BOOK", "TEE"]);
+
+        let color = results.facets.iter().find(|f| f.field == "color").unwrap();
+        assert_eq!(color.values.len(), 2);
+
+        let black = index.search(
+            &SearchQuery::new().with_filter(Filter::attribute("Color", vec!["black".into()])),
+        );
+        assert_eq!(black.pagination.total, 1);
+    }
+
+    #[test]
+    fn test_query_rewriting() {
+        use crate::search::{QueryRewriter, SynonymDictionary};
+
+        let plain = SearchIndexer::build(&catalog());
+        assert!(plain
+            .search(&SearchQuery::new().with_query("t-shirt"))
+            .is_empty());
+
+        let index = SearchIndexer::build(&catalog()).with_rewriter(
+            QueryRewriter::new()
+                .with_synonyms(SynonymDictionary::new().with_equivalents(&["tee", "t-shirt"]))
+                .with_stemming(true)
+                .with_spelling_correction(true),
+        );
+        let sku = |results: &SearchResults<ProductId>| {
+            index.document(&results.items[0]).unwrap().sku.clone()
+        };
+
+        let results = index.search(&SearchQuery::new().with_query("rust t-shirt"));
+        assert_eq!((results.len(), sku(&results).as_str()), (1, "TEE"));
+        assert_eq!(results.corrected_query, None);
+
+        let results = index.search(&SearchQuery::new().with_query("mugs"));
+        assert_eq!(sku(&results), "MUG");
+
+        let results = index.search(&SearchQuery::new().with_query("feris mug"));
+        assert_eq!(sku(&results), "MUG");
+        assert_eq!(results.corrected_query.as_deref(), Some("ferris mug"));
+
+        let results = index.search(&SearchQuery::new().with_query("xyzzy"));
+        assert!(results.is_empty());
+        assert_eq!(results.corrected_query, None);
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let mut products = catalog();
+        let mut index = SearchIndexer::build(&products);
+
+        products[2].name = "Ferris Travel Cup".to_string();
+        index.upsert(&products[2]);
+        assert_eq!(
+            index
+                .search(&SearchQuery::new().with_query("ferris mug"))
+                .len(),
+            1
+        );
+        assert_eq!(
+            index
+                .search(&SearchQuery::new().with_query("ferris mugs"))
+                .len(),
+            0
+        );
+        assert_eq!(index.search(&SearchQuery::new().with_query("cup")).len(), 1);
+
+        assert!(index.remove(&products[2].id));
+        assert_eq!(index.len(), 2);
+        assert!(index
+            .stems
+            .values()
+            .flatten()
+            .all(|term| index.postings.contains_key(term)));
+        assert_eq!(
+            index.search(&SearchQuery::new().with_query("ferris")).len(),
+            0
+        );
+    }
+}
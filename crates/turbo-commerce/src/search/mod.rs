@@ -1,11 +1,29 @@
 //! Search module.
 //!
-//! Contains types for faceted search, filters, and pagination.
+//! Contains types for faceted search, filters, and pagination, plus an
+//! in-process index that executes queries without an external service and
+//! rewrites queries (synonyms, stemming, spelling correction) and ranks
+//! results by configurable relevance scoring, plus an autocomplete index for
+//! type-ahead suggestions.
 
+mod facets;
 mod filter;
+mod index;
 mod query;
+mod ranking;
 mod results;
+mod rewrite;
+mod suggest;
 
+pub use facets::{FacetAggregator, PriceBucket, FACET_PRICE, FACET_RATING};
 pub use filter::Filter;
+#[cfg(feature = "storage")]
+pub use index::SEARCH_INDEX_SCHEMA;
+pub use index::{
+    IndexedDocument, SearchIndexer, BRAND_ATTRIBUTE, FACET_CATEGORY, FACET_PRODUCT_TYPE, FACET_TAG,
+};
 pub use query::{SearchQuery, SortOption};
-pub use results::{Pagination, SearchResults};
+pub use ranking::{FieldWeights, RankingConfig, RankingRule, ScoredDocument, SearchField};
+pub use results::{Facet, FacetType, FacetValue, Pagination, SearchResults};
+pub use rewrite::{stem, QueryRewriter, SynonymDictionary, TermGroup};
+pub use suggest::{SuggestResponse, Suggestion, SuggestionIndex, SuggestionKind};
@@ -0,0 +1,242 @@
+//! Relevance scoring for the search index.
+//!
+//! A document's relevance score is the sum of its text-match score (the
+//! best [`FieldWeights`] entry among the fields each query term matched)
+//! plus in-stock and popularity boosts. [`RankingRule`]s then order results
+//! by score and tie-breakers, in the configured order.
+
+use crate::search::IndexedDocument;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// A product field that text terms are indexed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SearchField {
+    /// Product name.
+    Name,
+    /// The `brand` attribute.
+    Brand,
+    /// Category names and IDs.
+    Category,
+    /// Product tags.
+    Tag,
+    /// Product SKU.
+    Sku,
+    /// Short and long descriptions.
+    Description,
+}
+
+impl SearchField {
+    /// Every field, in bit order.
+    pub const ALL: [SearchField; 6] = [
+        SearchField::Name,
+        SearchField::Brand,
+        SearchField::Category,
+        SearchField::Tag,
+        SearchField::Sku,
+        SearchField::Description,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchField::Name => "name",
+            SearchField::Brand => "brand",
+            SearchField::Category => "category",
+            SearchField::Tag => "tag",
+            SearchField::Sku => "sku",
+            SearchField::Description => "description",
+        }
+    }
+
+    /// The field's bit in an indexed term's field mask.
+    pub fn bit(&self) -> u32 {
+        1 << (*self as u32)
+    }
+}
+
+/// Score for a term match in each field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldWeights {
+    pub name: u32,
+    pub brand: u32,
+    pub category: u32,
+    pub tag: u32,
+    pub sku: u32,
+    pub description: u32,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        Self {
+            name: 10,
+            brand: 8,
+            category: 5,
+            tag: 4,
+            sku: 6,
+            description: 1,
+        }
+    }
+}
+
+impl FieldWeights {
+    /// Weight for a single field.
+    pub fn weight(&self, field: SearchField) -> u32 {
+        match field {
+            SearchField::Name => self.name,
+            SearchField::Brand => self.brand,
+            SearchField::Category => self.category,
+            SearchField::Tag => self.tag,
+            SearchField::Sku => self.sku,
+            SearchField::Description => self.description,
+        }
+    }
+
+    /// Score a term from its field mask: the best weight among its fields.
+    pub fn score(&self, mask: u32) -> u32 {
+        SearchField::ALL
+            .iter()
+            .filter(|field| mask & field.bit() != 0)
+            .map(|field| self.weight(*field))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A criterion for ordering relevance-sorted results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RankingRule {
+    /// Higher relevance score first.
+    Score,
+    /// Purchasable products before out-of-stock ones, regardless of score.
+    InStock,
+    /// More popular first.
+    Popularity,
+    /// Newer first.
+    Newest,
+    /// Cheaper first.
+    PriceAsc,
+}
+
+impl RankingRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RankingRule::Score => "score",
+            RankingRule::InStock => "in_stock",
+            RankingRule::Popularity => "popularity",
+            RankingRule::Newest => "newest",
+            RankingRule::PriceAsc => "price_asc",
+        }
+    }
+}
+
+/// A document with its computed relevance.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoredDocument<'a> {
+    /// The indexed document.
+    pub document: &'a IndexedDocument,
+    /// Text-match score plus boosts.
+    pub score: u32,
+    /// Popularity signal (e.g., recent sales).
+    pub popularity: u64,
+    /// Average review rating, if known.
+    pub rating: Option<f64>,
+}
+
+/// How the index scores and orders results for [`SortOption::Relevance`].
+///
+/// [`SortOption::Relevance`]: crate::search::SortOption::Relevance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankingConfig {
+    /// Per-field term weights.
+    pub field_weights: FieldWeights,
+    /// Added to the score of purchasable products.
+    pub in_stock_boost: u32,
+    /// Added per doubling of popularity (`boost * log2(1 + popularity)`).
+    pub popularity_boost: u32,
+    /// Ordering criteria, applied in turn until two results differ.
+    pub rules: Vec<RankingRule>,
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            field_weights: FieldWeights::default(),
+            in_stock_boost: 5,
+            popularity_boost: 1,
+            rules: vec![
+                RankingRule::Score,
+                RankingRule::Popularity,
+                RankingRule::Newest,
+            ],
+        }
+    }
+}
+
+impl RankingConfig {
+    /// Set the field weights.
+    pub fn with_field_weights(mut self, weights: FieldWeights) -> Self {
+        self.field_weights = weights;
+        self
+    }
+
+    /// Set the in-stock boost.
+    pub fn with_in_stock_boost(mut self, boost: u32) -> Self {
+        self.in_stock_boost = boost;
+        self
+    }
+
+    /// Set the popularity boost.
+    pub fn with_popularity_boost(mut self, boost: u32) -> Self {
+        self.popularity_boost = boost;
+        self
+    }
+
+    /// Replace the ranking rules.
+    pub fn with_rules(mut self, rules: Vec<RankingRule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Total score from a text-match score and the document's signals.
+    pub fn score(&self, text_score: u32, document: &IndexedDocument, popularity: u64) -> u32 {
+        let mut score = text_score;
+        if document.in_stock {
+            score = score.saturating_add(self.in_stock_boost);
+        }
+        let doublings = (popularity.saturating_add(1)).ilog2();
+        score.saturating_add(self.popularity_boost.saturating_mul(doublings))
+    }
+
+    /// Compare two results by the ranking rules.
+    pub fn compare(&self, a: &ScoredDocument<'_>, b: &ScoredDocument<'_>) -> Ordering {
+        self.rules
+            .iter()
+            .map(|rule| match rule {
+                RankingRule::Score => b.score.cmp(&a.score),
+                RankingRule::InStock => b.document.in_stock.cmp(&a.document.in_stock),
+                RankingRule::Popularity => b.popularity.cmp(&a.popularity),
+                RankingRule::Newest => b.document.created_at.cmp(&a.document.created_at),
+                RankingRule::PriceAsc => a
+                    .document
+                    .price_cents
+                    .unwrap_or(i64::MAX)
+                    .cmp(&b.document.price_cents.unwrap_or(i64::MAX)),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_weights() {
+        let weights = FieldWeights::default();
+        let mask = SearchField::Description.bit() | SearchField::Name.bit();
+        assert_eq!(weights.score(mask), 10);
+        assert_eq!(weights.score(SearchField::Tag.bit()), 4);
+        assert_eq!(weights.score(0), 0);
+    }
+}
@@ -99,6 +99,10 @@ pub struct SearchResults<T> {
     pub query_time_ms: i64,
     /// Facets (if requested).
     pub facets: Vec<Facet>,
+    /// Spelling-corrected query the results are for, if the original
+    /// matched nothing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corrected_query: Option<String>,
 }
 
 impl<T> SearchResults<T> {
@@ -109,6 +113,7 @@ impl<T> SearchResults<T> {
             pagination,
             query_time_ms: 0,
             facets: Vec::new(),
+            corrected_query: None,
         }
     }
 
@@ -119,6 +124,7 @@ impl<T> SearchResults<T> {
             pagination: Pagination::default(),
             query_time_ms: 0,
             facets: Vec::new(),
+            corrected_query: None,
         }
     }
 
@@ -134,6 +140,12 @@ impl<T> SearchResults<T> {
         self
     }
 
+    /// Set the corrected query.
+    pub fn with_corrected_query(mut self, query: impl Into<String>) -> Self {
+        self.corrected_query = Some(query.into());
+        self
+    }
+
     /// Check if empty.
     pub fn is_empty(&self) -> bool {
         self.items.is_empty()
@@ -175,6 +187,16 @@ impl Facet {
         }
     }
 
+    /// Create a new range facet.
+    pub fn range(name: impl Into<String>, field: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            field: field.into(),
+            facet_type: FacetType::Range,
+            values: Vec::new(),
+        }
+    }
+
     /// Add a value to the facet.
     pub fn add_value(&mut self, value: impl Into<String>, count: i64, selected: bool) {
         self.values.push(FacetValue {
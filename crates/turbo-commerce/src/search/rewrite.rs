@@ -0,0 +1,215 @@
+//! Query rewriting: synonyms, stemming, and spelling correction.
+//!
+//! A [`QueryRewriter`] turns the typed terms into groups of alternatives
+//! (the typed words plus their synonyms) before the index matches them.
+//! Each store configures its own; the default rewrites nothing.
+
+use crate::search::index::tokenize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Synonyms for terms and phrases.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct SynonymDictionary {
+    /// Normalized term or phrase to the phrases it also matches.
+    entries: BTreeMap<String, Vec<String>>,
+}
+
+impl SynonymDictionary {
+    /// Create an empty dictionary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add terms that all mean the same thing (e.g., "tee", "t-shirt").
+    pub fn with_equivalents(mut self, terms: &[&str]) -> Self {
+        self.add_equivalents(terms);
+        self
+    }
+
+    /// Add synonyms that `term` matches, but not the other way around.
+    pub fn with_one_way(mut self, term: &str, synonyms: &[&str]) -> Self {
+        self.add_one_way(term, synonyms);
+        self
+    }
+
+    /// Add terms that all mean the same thing.
+    pub fn add_equivalents(&mut self, terms: &[&str]) {
+        for term in terms {
+            let others: Vec<&str> = terms.iter().filter(|t| *t != term).copied().collect();
+            self.add_one_way(term, &others);
+        }
+    }
+
+    /// Add synonyms that `term` matches, but not the other way around.
+    pub fn add_one_way(&mut self, term: &str, synonyms: &[&str]) {
+        let key = normalize(term);
+        if key.is_empty() {
+            return;
+        }
+        let entry = self.entries.entry(key.clone()).or_default();
+        for synonym in synonyms.iter().map(|s| normalize(s)) {
+            if !synonym.is_empty() && synonym != key && !entry.contains(&synonym) {
+                entry.push(synonym);
+            }
+        }
+    }
+
+    /// Synonyms of a normalized term or phrase.
+    pub fn lookup(&self, phrase: &str) -> &[String] {
+        self.entries.get(phrase).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Check if the dictionary is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn longest_phrase(&self) -> usize {
+        self.entries
+            .keys()
+            .map(|k| k.split(' ').count())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// One position in a rewritten query: any alternative may match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermGroup {
+    /// The typed words first, then synonyms. Every word of an alternative
+    /// must match.
+    pub alternatives: Vec<Vec<String>>,
+}
+
+/// Per-store query rewriting settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct QueryRewriter {
+    /// Synonym dictionary.
+    pub synonyms: SynonymDictionary,
+    /// Whether words match other forms with the same stem ("mugs" finds "mug").
+    pub stemming: bool,
+    /// Whether a query with no results is retried with misspellings corrected.
+    pub spelling_correction: bool,
+}
+
+impl QueryRewriter {
+    /// Create a rewriter that changes nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the synonym dictionary.
+    pub fn with_synonyms(mut self, synonyms: SynonymDictionary) -> Self {
+        self.synonyms = synonyms;
+        self
+    }
+
+    /// Enable or disable stemming.
+    pub fn with_stemming(mut self, stemming: bool) -> Self {
+        self.stemming = stemming;
+        self
+    }
+
+    /// Enable or disable spelling correction.
+    pub fn with_spelling_correction(mut self, enabled: bool) -> Self {
+        self.spelling_correction = enabled;
+        self
+    }
+
+    /// Group the terms, expanding synonyms. Multi-word synonym phrases are
+    /// matched greedily, longest first.
+    pub fn expand(&self, terms: &[String]) -> Vec<TermGroup> {
+        let longest = self.synonyms.longest_phrase().max(1);
+        let mut groups = Vec::new();
+        let mut i = 0;
+        while i < terms.len() {
+            let (len, synonyms) = (1..=longest.min(terms.len() - i))
+                .rev()
+                .map(|len| (len, self.synonyms.lookup(&terms[i..i + len].join(" "))))
+                .find(|(len, synonyms)| *len == 1 || !synonyms.is_empty())
+                .unwrap_or((1, &[]));
+            let mut alternatives = vec![terms[i..i + len].to_vec()];
+            alternatives.extend(
+                synonyms
+                    .iter()
+                    .map(|s| s.split(' ').map(str::to_string).collect()),
+            );
+            groups.push(TermGroup { alternatives });
+            i += len;
+        }
+        groups
+    }
+}
+
+/// Reduce an English word to a crude stem ("boxes" -> "box", "running" -> "run").
+pub fn stem(word: &str) -> String {
+    // (suffix, replacement, shortest remaining base)
+    let rules: [(&str, &str, usize); 7] = [
+        ("ies", "y", 2),
+        ("sses", "ss", 2),
+        ("xes", "x", 1),
+        ("ches", "ch", 1),
+        ("shes", "sh", 1),
+        ("ing", "", 3),
+        ("ed", "", 3),
+    ];
+    for (suffix, replacement, min_base) in rules {
+        let Some(base) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        if base.chars().count() < min_base {
+            continue;
+        }
+        let mut stem = format!("{}{}", base, replacement);
+        // "running" -> "runn" -> "run"
+        let mut last = stem.chars().rev();
+        let doubled = matches!(
+            (last.next(), last.next()),
+            (Some(a), Some(b)) if a == b && !"lsz".contains(a)
+        );
+        if doubled && replacement.is_empty() {
+            stem.pop();
+        }
+        return stem;
+    }
+    match word.strip_suffix('s') {
+        Some(base) if base.chars().count() >= 3 && !base.ends_with('s') => base.to_string(),
+        _ => word.to_string(),
+    }
+}
+
+fn normalize(text: &str) -> String {
+    tokenize(text).join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_synonyms() {
+        let rewriter = QueryRewriter::new().with_synonyms(
+            SynonymDictionary::new()
+                .with_equivalents(&["tee", "t-shirt"])
+                .with_one_way("hoodie", &["sweatshirt"]),
+        );
+        let terms: Vec<String> = ["black", "t", "shirt"].map(String::from).to_vec();
+        let groups = rewriter.expand(&terms);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].alternatives, [vec!["t", "shirt"], vec!["tee"]]);
+
+        let groups = rewriter.expand(&["sweatshirt".to_string()]);
+        assert_eq!(groups[0].alternatives.len(), 1);
+    }
+
+    #[test]
+    fn test_stem() {
+        assert_eq!(stem("mugs"), "mug");
+        assert_eq!(stem("boxes"), "box");
+        assert_eq!(stem("accessories"), "accessory");
+        assert_eq!(stem("running"), "run");
+        assert_eq!(stem("glass"), "glass");
+        assert_eq!(stem("tees"), "tee");
+    }
+}
@@ -0,0 +1,371 @@
+//! Autocomplete suggestions.
+//!
+//! [`SuggestionIndex`] prefix-indexes the words of product names, category
+//! names, and popular queries. Every typed word must match a word of the
+//! suggestion (the last as a prefix), with a few typos allowed in longer
+//! words. Matches are ranked by typos, then popularity.
+
+use crate::catalog::{Category, Product};
+use crate::search::index::tokenize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// What a suggestion points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    /// A past search query.
+    Query,
+    /// A product.
+    Product,
+    /// A category.
+    Category,
+}
+
+impl SuggestionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionKind::Query => "query",
+            SuggestionKind::Product => "product",
+            SuggestionKind::Category => "category",
+        }
+    }
+}
+
+/// A single type-ahead suggestion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// What the suggestion points at.
+    pub kind: SuggestionKind,
+    /// Text to display (and search for, for queries).
+    pub text: String,
+    /// Product or category ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Ranking score (not serialized).
+    #[serde(skip)]
+    pub score: u32,
+}
+
+/// Response body for a type-ahead endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct SuggestResponse {
+    /// The text the suggestions are for.
+    pub query: String,
+    /// Best suggestions first.
+    pub suggestions: Vec<Suggestion>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    kind: SuggestionKind,
+    text: String,
+    id: Option<String>,
+    words: Vec<String>,
+    popularity: u64,
+}
+
+/// A prefix index of suggestions.
+#[derive(Debug, Clone, Default)]
+pub struct SuggestionIndex {
+    entries: Vec<Entry>,
+    keys: HashMap<(SuggestionKind, String), usize>,
+    words: BTreeMap<String, Vec<usize>>,
+}
+
+impl SuggestionIndex {
+    /// Create an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build an index of available products and categories.
+    pub fn build<'a>(
+        products: impl IntoIterator<Item = &'a Product>,
+        categories: impl IntoIterator<Item = &'a Category>,
+    ) -> Self {
+        let mut index = Self::new();
+        for product in products {
+            index.add_product(product);
+        }
+        for category in categories {
+            index.add_category(category);
+        }
+        index
+    }
+
+    /// Number of suggestions.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add or update a product. Unavailable products are not suggested.
+    pub fn add_product(&mut self, product: &Product) {
+        let key = (SuggestionKind::Product, product.id.to_string());
+        if product.is_available() {
+            self.upsert(key, &product.name);
+        } else if let Some(&i) = self.keys.get(&key) {
+            // Keep the slot (and its popularity) but stop matching it.
+            self.set_words(i, Vec::new());
+        }
+    }
+
+    /// Add or update a category.
+    pub fn add_category(&mut self, category: &Category) {
+        self.upsert(
+            (SuggestionKind::Category, category.id.to_string()),
+            &category.name,
+        );
+    }
+
+    /// Count a search, so frequent queries are suggested.
+    pub fn record_query(&mut self, query: &str) {
+        let normalized = tokenize(query).join(" ");
+        if normalized.is_empty() {
+            return;
+        }
+        let i = self.upsert((SuggestionKind::Query, normalized.clone()), &normalized);
+        self.entries[i].popularity += 1;
+    }
+
+    /// Set the popularity of a product or category (e.g., recent sales or views).
+    pub fn set_popularity(&mut self, kind: SuggestionKind, id: &str, popularity: u64) {
+        if let Some(&i) = self.keys.get(&(kind, id.to_string())) {
+            self.entries[i].popularity = popularity;
+        }
+    }
+
+    /// Suggestions for partially typed text, best first.
+    pub fn suggest(&self, query: &str, limit: usize) -> SuggestResponse {
+        let terms = tokenize(query);
+        let mut response = SuggestResponse {
+            query: query.to_string(),
+            suggestions: Vec::new(),
+        };
+        let Some((last, rest)) = terms.split_last() else {
+            return response;
+        };
+
+        // Entries whose words match the last term as a prefix, with typo counts.
+        let mut candidates: HashMap<usize, u32> = HashMap::new();
+        let budget = typo_budget(last);
+        let prefix_hits = self
+            .words
+            .range(last.clone()..)
+            .take_while(|(word, _)| word.starts_with(last.as_str()))
+            .map(|(_, entries)| (entries, 0));
+        let typo_hits = self
+            .words
+            .iter()
+            .filter(|(word, _)| budget > 0 && !word.starts_with(last.as_str()))
+            .filter_map(|(word, entries)| {
+                let typos = prefix_distance(last, word);
+                (typos <= budget).then_some((entries, typos))
+            });
+        for (entries, typos) in prefix_hits.chain(typo_hits) {
+            for &i in entries {
+                let best = candidates.entry(i).or_insert(typos);
+                *best = (*best).min(typos);
+            }
+        }
+
+        let mut suggestions: Vec<Suggestion> = candidates
+            .into_iter()
+            .filter_map(|(i, last_typos)| {
+                let entry = &self.entries[i];
+                let mut typos = last_typos;
+                for term in rest {
+                    typos += entry
+                        .words
+                        .iter()
+                        .map(|word| edit_distance(term, word))
+                        .filter(|d| *d <= typo_budget(term))
+                        .min()?;
+                }
+                let mut score = 1000_u32.saturating_sub(200 * typos);
+                let leads = typos == 0
+                    && entry.words.len() > rest.len()
+                    && entry.words[..rest.len()] == *rest
+                    && entry.words[rest.len()].starts_with(last.as_str());
+                if leads {
+                    score += 100;
+                }
+                score += 10 * (entry.popularity.saturating_add(1)).ilog2();
+                Some(Suggestion {
+                    kind: entry.kind,
+                    text: entry.text.clone(),
+                    id: entry.id.clone(),
+                    score,
+                })
+            })
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.kind.cmp(&b.kind))
+                .then_with(|| a.text.cmp(&b.text))
+        });
+        suggestions.truncate(limit);
+        response.suggestions = suggestions;
+        response
+    }
+
+    fn upsert(&mut self, key: (SuggestionKind, String), text: &str) -> usize {
+        let words = tokenize(text);
+        let i = match self.keys.get(&key) {
+            Some(&i) => {
+                self.entries[i].text = text.to_string();
+                i
+            }
+            None => {
+                let id = match key.0 {
+                    SuggestionKind::Query => None,
+                    _ => Some(key.1.clone()),
+                };
+                self.entries.push(Entry {
+                    kind: key.0,
+                    text: text.to_string(),
+                    id,
+                    words: Vec::new(),
+                    popularity: 0,
+                });
+                self.keys.insert(key, self.entries.len() - 1);
+                self.entries.len() - 1
+            }
+        };
+        self.set_words(i, words);
+        i
+    }
+
+    fn set_words(&mut self, i: usize, words: Vec<String>) {
+        for word in &self.entries[i].words {
+            if let Some(entries) = self.words.get_mut(word) {
+                entries.retain(|e| *e != i);
+                if entries.is_empty() {
+                    self.words.remove(word);
+                }
+            }
+        }
+        for word in &words {
+            let entries = self.words.entry(word.clone()).or_default();
+            if !entries.contains(&i) {
+                entries.push(i);
+            }
+        }
+        self.entries[i].words = words;
+    }
+}
+
+/// Typos allowed in a term: none under 4 characters, one under 8, else two.
+pub(super) fn typo_budget(term: &str) -> u32 {
+    match term.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Levenshtein distance between two words.
+pub(super) fn edit_distance(a: &str, b: &str) -> u32 {
+    distances(a, b).pop().unwrap_or(0)
+}
+
+/// Smallest edit distance between `term` and any prefix of `word`.
+fn prefix_distance(term: &str, word: &str) -> u32 {
+    distances(term, word).into_iter().min().unwrap_or(0)
+}
+
+/// Edit distances from `a` to each prefix of `b` (the last row of the
+/// Levenshtein table).
+fn distances(a: &str, b: &str) -> Vec<u32> {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<u32> = (0..=b.len() as u32).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i as u32 + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous + u32::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> (Vec<Product>, Vec<Category>) {
+        (
+            vec![
+                Product::new("TEE", "Rust Logo Tee", "rust-logo-tee"),
+                Product::new("BOOK", "The Rust Programming Book", "rust-book"),
+                Product::new("MUG", "Ferris Mug", "ferris-mug"),
+            ],
+            vec![Category::new_root("Programming Books", "programming-books")],
+        )
+    }
+
+    fn texts(response: &SuggestResponse) -> Vec<&str> {
+        response
+            .suggestions
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_prefix_and_typos() {
+        let (products, categories) = catalog();
+        let index = SuggestionIndex::build(&products, &categories);
+        assert_eq!(
+            texts(&index.suggest("rust prog", 5)),
+            ["The Rust Programming Book"]
+        );
+        // "Rust Logo Tee" starts with the query, so it outranks the book.
+        assert_eq!(
+            texts(&index.suggest("ru", 5)),
+            ["Rust Logo Tee", "The Rust Programming Book"]
+        );
+        // One typo in a five-letter word.
+        assert_eq!(texts(&index.suggest("feris", 5)), ["Ferris Mug"]);
+        assert_eq!(
+            texts(&index.suggest("progrem", 5)),
+            ["The Rust Programming Book", "Programming Books"]
+        );
+        // Short words must be typed exactly.
+        assert!(index.suggest("mgu", 5).suggestions.is_empty());
+        assert!(index.suggest("  ", 5).suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_popularity_and_queries() {
+        let (mut products, categories) = catalog();
+        let mut index = SuggestionIndex::build(&products, &categories);
+        for _ in 0..3 {
+            index.record_query("Rust  Mugs");
+        }
+        let response = index.suggest("rust", 10);
+        assert_eq!(response.suggestions[0].kind, SuggestionKind::Query);
+        assert_eq!(response.suggestions[0].text, "rust mugs");
+
+        let book = &products[1];
+        index.set_popularity(SuggestionKind::Product, book.id.as_str(), 100_000);
+        assert_eq!(index.suggest("rust", 1).suggestions[0].text, book.name);
+
+        products[0].status = crate::catalog::ProductStatus::Archived;
+        index.add_product(&products[0]);
+        assert_eq!(index.len(), 5);
+        assert!(!texts(&index.suggest("logo", 5)).contains(&"Rust Logo Tee"));
+
+        let json = serde_json::to_string(&index.suggest("ferris", 1)).unwrap();
+        assert!(json.contains(r#""kind":"product","text":"Ferris Mug","id":"#));
+        assert!(!json.contains("score"));
+    }
+}
@@ -0,0 +1,434 @@
+//! Tax calculation.
+
+use crate::cart::{Cart, CartPricing};
+use crate::checkout::Address;
+use crate::error::CommerceError;
+use crate::ids::ProductId;
+use crate::money::{Currency, Money};
+use crate::tax::{TaxClass, TaxZone};
+use serde::{Deserialize, Serialize};
+
+/// Whether catalog prices already include tax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum PriceMode {
+    /// Prices exclude tax; tax is added on top (typical in the US).
+    #[default]
+    Exclusive,
+    /// Prices include tax; tax is extracted (typical for EU VAT).
+    Inclusive,
+}
+
+/// When tax amounts are rounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum TaxRounding {
+    /// Round each line, then sum.
+    #[default]
+    PerLine,
+    /// Sum unrounded line taxes and round once.
+    PerTotal,
+}
+
+/// A taxable line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaxLine {
+    /// Caller's identifier for the line (e.g., line item ID, "shipping").
+    pub id: String,
+    /// Product on the line (None for shipping/fees).
+    pub product_id: Option<ProductId>,
+    /// Taxable amount after discounts.
+    pub amount: Money,
+    /// Tax class.
+    pub class: TaxClass,
+}
+
+/// Everything a calculator needs to tax an order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaxRequest {
+    /// Where the order ships.
+    pub destination: Address,
+    /// Currency of every line.
+    pub currency: Currency,
+    /// Lines to tax.
+    pub lines: Vec<TaxLine>,
+}
+
+impl TaxRequest {
+    /// Create an empty request.
+    pub fn new(destination: Address, currency: Currency) -> Self {
+        Self {
+            destination,
+            currency,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Build a request from a cart.
+    ///
+    /// Cart-level discounts are spread across lines in proportion to their
+    /// totals. Every line starts in [`TaxClass::Standard`].
+    pub fn for_cart(cart: &Cart, destination: Address) -> Result<Self, CommerceError> {
+        let subtotal = Money::try_sum(cart.items.iter().map(|i| &i.total_price), cart.currency)
+            .ok_or(CommerceError::Overflow)?;
+        let discount = Money::try_sum(cart.discounts.iter().map(|d| &d.amount), cart.currency)
+            .ok_or(CommerceError::Overflow)?
+            .amount_cents
+            .clamp(0, subtotal.amount_cents);
+
+        let weights: Vec<i64> = cart
+            .items
+            .iter()
+            .map(|i| i.total_price.amount_cents.max(0))
+            .collect();
+        let shares = Money::new(discount, cart.currency)
+            .allocate_weighted(&weights)
+            .unwrap_or_default();
+
+        let mut request = Self::new(destination, cart.currency);
+        for (i, item) in cart.items.iter().enumerate() {
+            let share = shares.get(i).map_or(0, |m| m.amount_cents);
+            request.lines.push(TaxLine {
+                id: item.id.to_string(),
+                product_id: Some(item.product_id.clone()),
+                amount: Money::new(item.total_price.amount_cents - share, cart.currency),
+                class: TaxClass::Standard,
+            });
+        }
+        Ok(request)
+    }
+
+    /// Add a line (e.g., shipping).
+    pub fn with_line(mut self, id: impl Into<String>, amount: Money, class: TaxClass) -> Self {
+        self.lines.push(TaxLine {
+            id: id.into(),
+            product_id: None,
+            amount,
+            class,
+        });
+        self
+    }
+
+    /// Set the tax class for every line of a product.
+    pub fn with_class(mut self, product_id: &ProductId, class: TaxClass) -> Self {
+        for line in self
+            .lines
+            .iter_mut()
+            .filter(|l| l.product_id.as_ref() == Some(product_id))
+        {
+            line.class = class.clone();
+        }
+        self
+    }
+}
+
+/// Tax from one rate on one line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaxComponent {
+    /// Rate name (e.g., "CA State Tax").
+    pub name: String,
+    /// Rate as a percentage.
+    pub percent: f64,
+    /// Tax amount.
+    pub amount: Money,
+}
+
+/// Tax for one line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LineTax {
+    /// Line identifier from the request.
+    pub id: String,
+    /// Tax amount for the line.
+    pub tax: Money,
+    /// Per-rate breakdown.
+    pub components: Vec<TaxComponent>,
+}
+
+/// The result of a tax calculation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaxResult {
+    /// Per-line taxes, in request order.
+    pub lines: Vec<LineTax>,
+    /// Total tax.
+    pub total_tax: Money,
+    /// Whether the taxes are already included in the prices.
+    pub prices_include_tax: bool,
+}
+
+impl TaxResult {
+    /// Get the tax for a line.
+    pub fn line(&self, id: &str) -> Option<&LineTax> {
+        self.lines.iter().find(|l| l.id == id)
+    }
+}
+
+/// Computes taxes for an order.
+///
+/// Implemented by [`TableTaxCalculator`]; external tax services can be
+/// plugged in by implementing this trait.
+pub trait TaxCalculator {
+    /// Calculate taxes for a request.
+    fn calculate(&self, request: &TaxRequest) -> Result<TaxResult, CommerceError>;
+}
+
+/// A tax calculator backed by configured zones and rates.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableTaxCalculator {
+    /// Tax zones.
+    pub zones: Vec<TaxZone>,
+    /// Whether prices include tax.
+    pub price_mode: PriceMode,
+    /// When to round.
+    pub rounding: TaxRounding,
+}
+
+impl TableTaxCalculator {
+    /// Create a calculator with no zones (everything is untaxed).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a zone.
+    pub fn with_zone(mut self, zone: TaxZone) -> Self {
+        self.zones.push(zone);
+        self
+    }
+
+    /// Set the price mode.
+    pub fn with_price_mode(mut self, mode: PriceMode) -> Self {
+        self.price_mode = mode;
+        self
+    }
+
+    /// Set the rounding mode.
+    pub fn with_rounding(mut self, rounding: TaxRounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+}
+
+impl TaxCalculator for TableTaxCalculator {
+    fn calculate(&self, request: &TaxRequest) -> Result<TaxResult, CommerceError> {
+        let zones: Vec<&TaxZone> = self
+            .zones
+            .iter()
+            .filter(|z| z.contains(&request.destination))
+            .collect();
+
+        // Unrounded tax per rate, per line.
+        let mut raw: Vec<Vec<(&str, f64, f64)>> = Vec::with_capacity(request.lines.len());
+        for line in &request.lines {
+            if line.amount.currency != request.currency {
+                return Err(CommerceError::CurrencyMismatch {
+                    expected: request.currency.code().to_string(),
+                    got: line.amount.currency.code().to_string(),
+                });
+            }
+            let rates: Vec<_> = if line.class == TaxClass::Exempt {
+                Vec::new()
+            } else {
+                zones
+                    .iter()
+                    .filter_map(|z| z.rate_for(&line.class))
+                    .collect()
+            };
+            let total_percent: f64 = rates.iter().map(|r| r.percent).sum();
+            let amount = line.amount.amount_cents as f64;
+            let line_tax = match self.price_mode {
+                PriceMode::Exclusive => amount * total_percent / 100.0,
+                PriceMode::Inclusive => amount - amount / (1.0 + total_percent / 100.0),
+            };
+            raw.push(
+                rates
+                    .iter()
+                    .map(|r| {
+                        let share = if total_percent > 0.0 {
+                            line_tax * r.percent / total_percent
+                        } else {
+                            0.0
+                        };
+                        (r.name.as_str(), r.percent, share)
+                    })
+                    .collect(),
+            );
+        }
+
+        let mut lines = Vec::with_capacity(request.lines.len());
+        for (line, line_raw) in request.lines.iter().zip(&raw) {
+            let unrounded: f64 = line_raw.iter().map(|(_, _, a)| a).sum();
+            let components: Vec<TaxComponent> = line_raw
+                .iter()
+                .map(|(name, percent, amount)| TaxComponent {
+                    name: name.to_string(),
+                    percent: *percent,
+                    amount: Money::new(amount.round() as i64, request.currency),
+                })
+                .collect();
+            let tax = match self.rounding {
+                TaxRounding::PerLine => components.iter().map(|c| c.amount.amount_cents).sum(),
+                TaxRounding::PerTotal => unrounded.round() as i64,
+            };
+            lines.push(LineTax {
+                id: line.id.clone(),
+                tax: Money::new(tax, request.currency),
+                components,
+            });
+        }
+
+        let mut total: i64 = lines.iter().map(|l| l.tax.amount_cents).sum();
+        if self.rounding == TaxRounding::PerTotal {
+            let exact: f64 = raw.iter().flatten().map(|(_, _, a)| a).sum();
+            let rounded = exact.round() as i64;
+            // Put the rounding difference on the largest line so lines sum to the total.
+            if let Some(largest) = lines.iter_mut().max_by_key(|l| l.tax.amount_cents) {
+                largest.tax.amount_cents += rounded - total;
+            }
+            total = rounded;
+        }
+
+        Ok(TaxResult {
+            lines,
+            total_tax: Money::new(total, request.currency),
+            prices_include_tax: self.price_mode == PriceMode::Inclusive,
+        })
+    }
+}
+
+impl CartPricing {
+    /// Apply a tax result to this pricing.
+    ///
+    /// Line `tax_amount`s are filled in by line item ID. Exclusive taxes
+    /// are added to the totals; inclusive taxes are already part of them.
+    pub fn apply_tax(&mut self, taxes: &TaxResult) -> Result<(), CommerceError> {
+        for line in &mut self.line_items {
+            if let Some(tax) = taxes.line(line.line_item_id.as_str()) {
+                line.tax_amount = tax.tax;
+                if !taxes.prices_include_tax {
+                    line.total = line
+                        .total
+                        .try_add(&tax.tax)
+                        .ok_or(CommerceError::Overflow)?;
+                }
+            }
+        }
+
+        let previous = self.tax_total;
+        self.tax_total = taxes.total_tax;
+        if !taxes.prices_include_tax {
+            self.grand_total = self
+                .grand_total
+                .try_subtract(&previous)
+                .and_then(|g| g.try_add(&taxes.total_tax))
+                .ok_or(CommerceError::Overflow)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cart::AppliedDiscount;
+    use crate::checkout::fixtures::usd;
+    use crate::ids::{DiscountId, VariantId};
+    use crate::tax::TaxRate;
+
+    fn los_angeles() -> Address {
+        let mut address = Address::new("A", "B", "1 Main St", "Los Angeles", "US", "US", "90001");
+        address.province_code = Some("CA".to_string());
+        address
+    }
+
+    fn us_calculator() -> TableTaxCalculator {
+        TableTaxCalculator::new()
+            .with_zone(
+                TaxZone::country("California", "US")
+                    .with_province("CA")
+                    .with_rate(TaxRate::new("CA State", TaxClass::Standard, 6.0)),
+            )
+            .with_zone(
+                TaxZone::country("Los Angeles", "US")
+                    .with_province("CA")
+                    .with_postal_prefix("900")
+                    .with_rate(TaxRate::new("LA County", TaxClass::Standard, 3.5)),
+            )
+    }
+
+    #[test]
+    fn test_exclusive_stacked_rates() {
+        let request = TaxRequest::new(los_angeles(), Currency::USD)
+            .with_line("a", usd(10000), TaxClass::Standard)
+            .with_line("b", usd(5000), TaxClass::Exempt);
+        let result = us_calculator().calculate(&request).unwrap();
+
+        assert_eq!(result.total_tax, usd(950));
+        assert_eq!(result.line("a").unwrap().components.len(), 2);
+        assert_eq!(result.line("b").unwrap().tax, usd(0));
+    }
+
+    #[test]
+    fn test_inclusive_vat() {
+        let calculator = TableTaxCalculator::new()
+            .with_price_mode(PriceMode::Inclusive)
+            .with_zone(TaxZone::country("Germany", "DE").with_rate(TaxRate::new(
+                "VAT",
+                TaxClass::Standard,
+                19.0,
+            )));
+        let berlin = Address::new("A", "B", "Str. 1", "Berlin", "Germany", "DE", "10115");
+        let request = TaxRequest::new(berlin, Currency::EUR).with_line(
+            "a",
+            Money::new(11900, Currency::EUR),
+            TaxClass::Standard,
+        );
+        let result = calculator.calculate(&request).unwrap();
+        assert_eq!(result.total_tax, Money::new(1900, Currency::EUR));
+        assert!(result.prices_include_tax);
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let request = TaxRequest::new(los_angeles(), Currency::USD)
+            .with_line("a", usd(5), TaxClass::Standard)
+            .with_line("b", usd(5), TaxClass::Standard)
+            .with_line("c", usd(5), TaxClass::Standard);
+        // 9.5% of 5 cents = 0.475 per line.
+        let per_line = us_calculator().calculate(&request).unwrap();
+        assert_eq!(per_line.total_tax, usd(0));
+
+        let per_total = us_calculator()
+            .with_rounding(TaxRounding::PerTotal)
+            .calculate(&request)
+            .unwrap();
+        assert_eq!(per_total.total_tax, usd(1));
+        let line_sum: i64 = per_total.lines.iter().map(|l| l.tax.amount_cents).sum();
+        assert_eq!(line_sum, 1);
+    }
+
+    #[test]
+    fn test_cart_pricing_tax() {
+        let mut cart = Cart::new("session");
+        cart.add_item(
+            VariantId::new("v1"),
+            ProductId::new("p1"),
+            "Shirt",
+            2,
+            usd(5000),
+        )
+        .unwrap();
+        cart.apply_discount(AppliedDiscount {
+            discount_id: DiscountId::new("d1"),
+            code: "SAVE".to_string(),
+            description: "Save".to_string(),
+            amount: usd(2000),
+        });
+
+        let request = TaxRequest::for_cart(&cart, los_angeles()).unwrap();
+        assert_eq!(request.lines[0].amount, usd(8000));
+
+        let taxes = us_calculator().calculate(&request).unwrap();
+        let mut pricing = cart.calculate_pricing().unwrap();
+        pricing.apply_tax(&taxes).unwrap();
+        assert_eq!(pricing.tax_total, usd(760));
+        assert_eq!(pricing.grand_total, usd(8760));
+        assert_eq!(pricing.line_items[0].tax_amount, usd(760));
+    }
+}
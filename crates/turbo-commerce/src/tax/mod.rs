@@ -0,0 +1,13 @@
+//! Tax module.
+//!
+//! Contains tax zones and rates, and the [`TaxCalculator`] trait used to
+//! compute taxes for a cart or order.
+
+mod calculator;
+mod rates;
+
+pub use calculator::{
+    LineTax, PriceMode, TableTaxCalculator, TaxCalculator, TaxComponent, TaxLine, TaxRequest,
+    TaxResult, TaxRounding,
+};
+pub use rates::{TaxClass, TaxRate, TaxZone};
@@ -0,0 +1,157 @@
+//! Tax classes, rates, and zones.
+
+use crate::checkout::Address;
+use serde::{Deserialize, Serialize};
+
+/// Tax class of a product, used to pick the rate within a zone.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum TaxClass {
+    /// Standard rate.
+    #[default]
+    Standard,
+    /// Reduced rate (e.g., food, books).
+    Reduced,
+    /// Zero-rated (taxable at 0%).
+    Zero,
+    /// Exempt from tax.
+    Exempt,
+    /// Merchant-defined class.
+    Custom(String),
+}
+
+impl TaxClass {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TaxClass::Standard => "standard",
+            TaxClass::Reduced => "reduced",
+            TaxClass::Zero => "zero",
+            TaxClass::Exempt => "exempt",
+            TaxClass::Custom(name) => name,
+        }
+    }
+}
+
+/// A rate for one tax class.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaxRate {
+    /// Display name (e.g., "CA State Tax").
+    pub name: String,
+    /// Class this rate applies to.
+    pub class: TaxClass,
+    /// Rate as a percentage (e.g., 7.25).
+    pub percent: f64,
+}
+
+impl TaxRate {
+    /// Create a new rate.
+    pub fn new(name: impl Into<String>, class: TaxClass, percent: f64) -> Self {
+        Self {
+            name: name.into(),
+            class,
+            percent,
+        }
+    }
+}
+
+/// A tax jurisdiction and its rates.
+///
+/// Zones stack: an address in a city zone is also in its state and country
+/// zones, and pays every matching rate (e.g., state + county sales tax).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaxZone {
+    /// Zone name (e.g., "California").
+    pub name: String,
+    /// Country code (e.g., "US").
+    pub country_code: String,
+    /// State/province code (None = whole country).
+    pub province_code: Option<String>,
+    /// Postal code prefixes (empty = whole province/country).
+    pub postal_prefixes: Vec<String>,
+    /// Rates by class.
+    pub rates: Vec<TaxRate>,
+}
+
+impl TaxZone {
+    /// Create a zone covering a whole country.
+    pub fn country(name: impl Into<String>, country_code: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            country_code: country_code.into(),
+            province_code: None,
+            postal_prefixes: Vec::new(),
+            rates: Vec::new(),
+        }
+    }
+
+    /// Restrict the zone to a province.
+    pub fn with_province(mut self, province_code: impl Into<String>) -> Self {
+        self.province_code = Some(province_code.into());
+        self
+    }
+
+    /// Restrict the zone to postal codes starting with a prefix.
+    pub fn with_postal_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.postal_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Add a rate.
+    pub fn with_rate(mut self, rate: TaxRate) -> Self {
+        self.rates.push(rate);
+        self
+    }
+
+    /// Check if an address is inside this zone.
+    pub fn contains(&self, address: &Address) -> bool {
+        if !self
+            .country_code
+            .eq_ignore_ascii_case(&address.country_code)
+        {
+            return false;
+        }
+        if let Some(ref province) = self.province_code {
+            let matches = address
+                .province_code
+                .as_ref()
+                .map(|p| p.eq_ignore_ascii_case(province))
+                .unwrap_or(false);
+            if !matches {
+                return false;
+            }
+        }
+        if self.postal_prefixes.is_empty() {
+            return true;
+        }
+        let zip = address.zip.replace(' ', "").to_uppercase();
+        self.postal_prefixes
+            .iter()
+            .any(|p| zip.starts_with(&p.replace(' ', "").to_uppercase()))
+    }
+
+    /// Get the rate for a class.
+    pub fn rate_for(&self, class: &TaxClass) -> Option<&TaxRate> {
+        self.rates.iter().find(|r| &r.class == class)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_matching() {
+        let mut address = Address::new("A", "B", "1 Main St", "Los Angeles", "US", "US", "90001");
+        address.province_code = Some("CA".to_string());
+
+        let california = TaxZone::country("California", "US").with_province("ca");
+        let la = TaxZone::country("Los Angeles", "US")
+            .with_province("CA")
+            .with_postal_prefix("900");
+        let nevada = TaxZone::country("Nevada", "US").with_province("NV");
+
+        assert!(california.contains(&address));
+        assert!(la.contains(&address));
+        assert!(!nevada.contains(&address));
+        assert!(!TaxZone::country("UK", "GB").contains(&address));
+    }
+}
@@ -0,0 +1,439 @@
+//! Webhook endpoints, the delivery outbox, and the dispatcher.
+
+use crate::checkout::{OrderEvent, OrderEventKind};
+use crate::error::CommerceError;
+use crate::ids::OrderId;
+use crate::webhooks::{sign_payload, SIGNATURE_HEADER};
+use serde::{Deserialize, Serialize};
+
+/// A subscriber URL.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookEndpoint {
+    /// Endpoint identifier.
+    pub id: String,
+    /// URL deliveries are POSTed to.
+    pub url: String,
+    /// Shared secret used to sign payloads.
+    pub secret: String,
+    /// Subscribed event kinds (empty = all).
+    pub events: Vec<OrderEventKind>,
+    /// Whether the endpoint receives deliveries.
+    pub active: bool,
+}
+
+impl WebhookEndpoint {
+    /// Create an endpoint subscribed to every event.
+    pub fn new(id: impl Into<String>, url: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            url: url.into(),
+            secret: secret.into(),
+            events: Vec::new(),
+            active: true,
+        }
+    }
+
+    /// Subscribe to an event kind (once any are given, only those are sent).
+    pub fn subscribe(mut self, kind: OrderEventKind) -> Self {
+        if !self.events.contains(&kind) {
+            self.events.push(kind);
+        }
+        self
+    }
+
+    /// Check if the endpoint wants an event kind.
+    pub fn subscribes_to(&self, kind: OrderEventKind) -> bool {
+        self.active && (self.events.is_empty() || self.events.contains(&kind))
+    }
+}
+
+/// State of a delivery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    /// Waiting for its next attempt.
+    Pending,
+    /// Accepted by the endpoint (2xx).
+    Delivered,
+    /// Gave up after the last retry.
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+/// One event queued for one endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookDelivery {
+    /// Delivery identifier (sent as `X-Turbo-Delivery`).
+    pub id: String,
+    /// Target endpoint.
+    pub endpoint_id: String,
+    /// Event identifier.
+    pub event_id: String,
+    /// Event kind.
+    pub kind: OrderEventKind,
+    /// Order the event is about.
+    pub order_id: OrderId,
+    /// JSON body.
+    pub payload: String,
+    /// Attempts made so far.
+    pub attempts: u32,
+    /// Unix timestamp of the next attempt.
+    pub next_attempt_at: i64,
+    /// Current state.
+    pub status: DeliveryStatus,
+    /// HTTP status of the last attempt.
+    pub last_status_code: Option<u16>,
+    /// Error from the last attempt.
+    pub last_error: Option<String>,
+    /// Unix timestamp of successful delivery.
+    pub delivered_at: Option<i64>,
+}
+
+/// Exponential backoff between attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts before giving up.
+    pub max_attempts: u32,
+    /// Delay after the first failure.
+    pub base_delay_secs: i64,
+    /// Longest delay between attempts.
+    pub max_delay_secs: i64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            base_delay_secs: 30,
+            max_delay_secs: 6 * 3600,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the next attempt after `attempts` failures.
+    pub fn delay_after(&self, attempts: u32) -> i64 {
+        let exponent = attempts.saturating_sub(1).min(30);
+        self.base_delay_secs
+            .saturating_mul(1i64 << exponent)
+            .min(self.max_delay_secs)
+    }
+}
+
+/// An outgoing webhook HTTP request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookRequest {
+    /// Target URL.
+    pub url: String,
+    /// Request headers.
+    pub headers: Vec<(String, String)>,
+    /// JSON body.
+    pub body: String,
+}
+
+/// Sends webhook requests, returning the HTTP status code.
+pub trait WebhookTransport {
+    /// POST the request. `Err` means no response was received.
+    fn send(&self, request: &WebhookRequest) -> Result<u16, String>;
+}
+
+#[cfg(feature = "webhooks")]
+impl WebhookTransport for turbo_data::FetchClient {
+    fn send(&self, request: &WebhookRequest) -> Result<u16, String> {
+        let mut builder = self.post(request.url.clone()).text(request.body.clone());
+        for (key, value) in &request.headers {
+            builder = builder.header(key.clone(), value.clone());
+        }
+        builder
+            .send()
+            .map(|response| response.status)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Outcome of a dispatch run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DispatchReport {
+    /// Deliveries accepted.
+    pub delivered: usize,
+    /// Deliveries that failed and will be retried.
+    pub retrying: usize,
+    /// Deliveries that failed for the last time.
+    pub failed: usize,
+}
+
+/// Fans order events out to endpoints and delivers them with retries.
+///
+/// The dispatcher is its own outbox: enqueue events drained from orders,
+/// persist it, and call [`WebhookDispatcher::dispatch`] periodically.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhookDispatcher {
+    /// Registered endpoints.
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Queued and finished deliveries.
+    pub deliveries: Vec<WebhookDelivery>,
+    /// Retry schedule.
+    pub retry_policy: RetryPolicy,
+}
+
+impl WebhookDispatcher {
+    /// Create a dispatcher with no endpoints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an endpoint.
+    pub fn with_endpoint(mut self, endpoint: WebhookEndpoint) -> Self {
+        self.endpoints.push(endpoint);
+        self
+    }
+
+    /// Set the retry schedule.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Queue events for every subscribed endpoint. Returns the number of
+    /// deliveries queued.
+    pub fn enqueue(
+        &mut self,
+        events: impl IntoIterator<Item = OrderEvent>,
+        now: i64,
+    ) -> Result<usize, CommerceError> {
+        let mut queued = 0;
+        for event in events {
+            let payload = serde_json::to_string(&serde_json::json!({
+                "id": event.id,
+                "type": event.kind.as_str(),
+                "created_at": event.occurred_at,
+                "data": event,
+            }))?;
+            for endpoint in self
+                .endpoints
+                .iter()
+                .filter(|e| e.subscribes_to(event.kind))
+            {
+                self.deliveries.push(WebhookDelivery {
+                    id: format!("{}:{}", event.id, endpoint.id),
+                    endpoint_id: endpoint.id.clone(),
+                    event_id: event.id.clone(),
+                    kind: event.kind,
+                    order_id: event.order_id.clone(),
+                    payload: payload.clone(),
+                    attempts: 0,
+                    next_attempt_at: now,
+                    status: DeliveryStatus::Pending,
+                    last_status_code: None,
+                    last_error: None,
+                    delivered_at: None,
+                });
+                queued += 1;
+            }
+        }
+        Ok(queued)
+    }
+
+    /// Number of deliveries still pending.
+    pub fn pending_count(&self) -> usize {
+        self.deliveries
+            .iter()
+            .filter(|d| d.status == DeliveryStatus::Pending)
+            .count()
+    }
+
+    /// Attempt every pending delivery that is due.
+    pub fn dispatch(&mut self, transport: &dyn WebhookTransport, now: i64) -> DispatchReport {
+        let mut report = DispatchReport::default();
+        let policy = self.retry_policy;
+        for delivery in self
+            .deliveries
+            .iter_mut()
+            .filter(|d| d.status == DeliveryStatus::Pending && d.next_attempt_at <= now)
+        {
+            let Some(endpoint) = self
+                .endpoints
+                .iter()
+                .find(|e| e.id == delivery.endpoint_id && e.active)
+            else {
+                continue;
+            };
+            let request = WebhookRequest {
+                url: endpoint.url.clone(),
+                headers: vec![
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                    (
+                        "X-Turbo-Event".to_string(),
+                        delivery.kind.as_str().to_string(),
+                    ),
+                    ("X-Turbo-Delivery".to_string(), delivery.id.clone()),
+                    (
+                        SIGNATURE_HEADER.to_string(),
+                        sign_payload(&endpoint.secret, now, &delivery.payload),
+                    ),
+                ],
+                body: delivery.payload.clone(),
+            };
+
+            delivery.attempts += 1;
+            let outcome = transport.send(&request);
+            delivery.last_status_code = outcome.as_ref().ok().copied();
+            delivery.last_error = match outcome {
+                Ok(status) if (200..300).contains(&status) => None,
+                Ok(status) => Some(format!("HTTP {}", status)),
+                Err(e) => Some(e),
+            };
+
+            if delivery.last_error.is_none() {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.delivered_at = Some(now);
+                report.delivered += 1;
+            } else if delivery.attempts >= policy.max_attempts {
+                delivery.status = DeliveryStatus::Failed;
+                report.failed += 1;
+            } else {
+                delivery.next_attempt_at = now + policy.delay_after(delivery.attempts);
+                report.retrying += 1;
+            }
+        }
+        report
+    }
+
+    /// Drop delivered deliveries, keeping failures for inspection.
+    pub fn prune_delivered(&mut self) {
+        self.deliveries
+            .retain(|d| d.status != DeliveryStatus::Delivered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webhooks::verify_payload;
+    use std::cell::RefCell;
+
+    struct FakeTransport {
+        statuses: RefCell<Vec<Result<u16, String>>>,
+        sent: RefCell<Vec<WebhookRequest>>,
+    }
+
+    impl FakeTransport {
+        fn new(statuses: Vec<Result<u16, String>>) -> Self {
+            Self {
+                statuses: RefCell::new(statuses),
+                sent: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl WebhookTransport for FakeTransport {
+        fn send(&self, request: &WebhookRequest) -> Result<u16, String> {
+            self.sent.borrow_mut().push(request.clone());
+            self.statuses.borrow_mut().remove(0)
+        }
+    }
+
+    fn event(kind: OrderEventKind) -> OrderEvent {
+        use crate::checkout::{FinancialStatus, FulfillmentStatus, OrderStatus};
+        use crate::money::{Currency, Money};
+        OrderEvent {
+            id: format!("evt_{}", kind.as_str()),
+            kind,
+            order_id: OrderId::new("o1"),
+            order_number: "ORD-1".to_string(),
+            user_id: None,
+            email: "a@example.com".to_string(),
+            status: OrderStatus::Confirmed,
+            financial_status: FinancialStatus::Paid,
+            fulfillment_status: FulfillmentStatus::Unfulfilled,
+            grand_total: Money::new(5000, Currency::USD),
+            occurred_at: 100,
+        }
+    }
+
+    fn dispatcher() -> WebhookDispatcher {
+        WebhookDispatcher::new()
+            .with_endpoint(WebhookEndpoint::new(
+                "erp",
+                "https://erp.example.com/hook",
+                "s1",
+            ))
+            .with_endpoint(
+                WebhookEndpoint::new("mail", "https://mail.example.com/hook", "s2")
+                    .subscribe(OrderEventKind::Paid),
+            )
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 2,
+                base_delay_secs: 10,
+                max_delay_secs: 60,
+            })
+    }
+
+    #[test]
+    fn test_fan_out_and_signing() {
+        let mut dispatcher = dispatcher();
+        let queued = dispatcher
+            .enqueue(
+                vec![event(OrderEventKind::Created), event(OrderEventKind::Paid)],
+                100,
+            )
+            .unwrap();
+        assert_eq!(queued, 3);
+
+        let transport = FakeTransport::new(vec![Ok(200), Ok(204), Ok(200)]);
+        let report = dispatcher.dispatch(&transport, 100);
+        assert_eq!(report.delivered, 3);
+
+        let sent = transport.sent.borrow();
+        let mail = sent.iter().find(|r| r.url.contains("mail")).unwrap();
+        let signature = &mail
+            .headers
+            .iter()
+            .find(|(k, _)| k == SIGNATURE_HEADER)
+            .unwrap()
+            .1;
+        assert!(verify_payload("s2", signature, &mail.body, 100, 300));
+        assert!(mail.body.contains("\"type\":\"order.paid\""));
+
+        dispatcher.prune_delivered();
+        assert!(dispatcher.deliveries.is_empty());
+    }
+
+    #[test]
+    fn test_retry_then_fail() {
+        let mut dispatcher = dispatcher();
+        dispatcher
+            .enqueue(vec![event(OrderEventKind::Cancelled)], 100)
+            .unwrap();
+
+        let transport = FakeTransport::new(vec![Ok(500), Err("timeout".to_string())]);
+        assert_eq!(dispatcher.dispatch(&transport, 100).retrying, 1);
+        assert_eq!(dispatcher.deliveries[0].next_attempt_at, 110);
+        assert_eq!(
+            dispatcher.dispatch(&transport, 105),
+            DispatchReport::default()
+        );
+
+        assert_eq!(dispatcher.dispatch(&transport, 110).failed, 1);
+        let delivery = &dispatcher.deliveries[0];
+        assert_eq!(delivery.status, DeliveryStatus::Failed);
+        assert_eq!(delivery.last_error.as_deref(), Some("timeout"));
+        assert_eq!(dispatcher.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay_after(1), 30);
+        assert_eq!(policy.delay_after(3), 120);
+        assert_eq!(policy.delay_after(20), 6 * 3600);
+    }
+}
@@ -0,0 +1,14 @@
+//! Webhook delivery for order events.
+//!
+//! Contains webhook endpoints, the delivery outbox with retry scheduling,
+//! payload signing, and the dispatcher. The `webhooks` feature adds a
+//! [`WebhookTransport`] backed by turbo-data.
+
+mod dispatch;
+mod signature;
+
+pub use dispatch::{
+    DeliveryStatus, DispatchReport, RetryPolicy, WebhookDelivery, WebhookDispatcher,
+    WebhookEndpoint, WebhookRequest, WebhookTransport,
+};
+pub use signature::{sign_payload, verify_payload, SIGNATURE_HEADER};
@@ -21,6 +21,10 @@ pub enum DbError {
     #[error("Type conversion error: {0}")]
     TypeError(String),
 
+    /// A schema migration failed or the migration set is invalid.
+    #[error("Migration failed: {0}")]
+    MigrationError(String),
+
     /// No rows returned when one was expected.
     #[error("No rows returned")]
     NotFound,
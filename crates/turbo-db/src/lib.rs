@@ -1,7 +1,7 @@
-//! Type-safe SQLite database layer for TurboCommerce.
+//! Type-safe database layer for TurboCommerce.
 //!
 //! Provides a simple, ergonomic API for working with Spin's SQLite database
-//! with type-safe query results.
+//! (or PostgreSQL, see [`Db::connect`]) with type-safe query results.
 //!
 //! # Example
 //!
@@ -31,18 +31,31 @@
 //!     params![100.0]
 //! )?;
 //! ```
+//!
+//! Schema changes are applied with numbered [`Migration`]s; see
+//! [`Db::migrate`]. Listings are paginated with [`Db::query_page`], and
+//! [`Db::upsert`] builds `ON CONFLICT` statements. [`Db::execute_batch`] and
+//! [`Db::insert_many`] write many rows atomically.
 
 mod db;
 mod error;
+mod migrations;
+mod pagination;
+mod postgres;
 mod types;
+mod write;
 
-pub use db::Db;
+pub use db::{Backend, Db};
 pub use error::DbError;
+pub use migrations::{Migration, MigrationReport, SCHEMA_VERSION_TABLE};
+pub use pagination::{Page, PageRequest};
 pub use types::{QueryResult, Row, Value};
 
 /// Prelude for convenient imports.
 pub mod prelude {
-    pub use crate::{params, Db, DbError, QueryResult, Row, Value};
+    pub use crate::{
+        params, Backend, Db, DbError, Migration, Page, PageRequest, QueryResult, Row, Value,
+    };
 }
 
 /// Create a parameter list for SQL queries.
@@ -90,6 +103,22 @@ mod tests {
         assert!(matches!(&p[2], Value::Real(f) if (*f - 3.14).abs() < 0.01));
     }
 
+    // Native builds run against no-op stubs, so this only covers the
+    // transaction bookkeeping and error path, not the SQL.
+    #[test]
+    fn test_transaction_nesting_and_errors() {
+        let db = Db::open_default().unwrap();
+        let result: Result<(), DbError> = db.transaction(|db| {
+            assert!(db.in_transaction());
+            let inner: Result<(), DbError> = db.transaction(|_| Err(DbError::NotFound));
+            assert!(matches!(inner, Err(DbError::NotFound)));
+            assert!(db.in_transaction());
+            db.execute_batch(&[("DELETE FROM cart_items WHERE session_id = ?", params!["s1"])])
+        });
+        assert!(result.is_ok());
+        assert!(!db.in_transaction());
+    }
+
     #[test]
     fn test_params_trailing_comma() {
         let p = params!["a", "b",];
@@ -0,0 +1,304 @@
+//! Schema migrations.
+//!
+//! Migrations are numbered SQL scripts compiled into the component and
+//! applied in order by [`Db::migrate`]. Applied versions are recorded in the
+//! `schema_version` table, so running the same set again is a no-op, even
+//! when several instances start on a fresh database at once.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use turbo_db::{Db, Migration};
+//!
+//! const MIGRATIONS: &[Migration] = &[
+//!     Migration::new(1, "init", include_str!("../migrations/0001_init.sql")),
+//!     Migration::new(2, "add_sku", "ALTER TABLE products ADD COLUMN sku TEXT;"),
+//! ];
+//!
+//! let db = Db::open_default()?;
+//! db.migrate(MIGRATIONS)?;
+//! ```
+
+use crate::{Backend, Db, DbError};
+use std::collections::HashSet;
+
+/// Table that records applied migrations.
+pub const SCHEMA_VERSION_TABLE: &str = "schema_version";
+
+const CREATE_SCHEMA_VERSION: &str = "CREATE TABLE IF NOT EXISTS schema_version (
+    version BIGINT PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at BIGINT NOT NULL
+)";
+
+/// A numbered schema change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    /// Version number (positive, unique, ascending).
+    pub version: i64,
+    /// Short description (e.g., "add_product_sku").
+    pub name: &'static str,
+    /// SQL statements separated by semicolons.
+    pub sql: &'static str,
+}
+
+impl Migration {
+    /// Create a migration.
+    pub const fn new(version: i64, name: &'static str, sql: &'static str) -> Self {
+        Self { version, name, sql }
+    }
+
+    /// The individual statements of the script.
+    ///
+    /// Splits on semicolons outside quotes and comments. Statements that
+    /// themselves contain semicolons (e.g., `CREATE TRIGGER`) are not
+    /// supported.
+    pub fn statements(&self) -> Vec<&'static str> {
+        split_statements(self.sql)
+    }
+}
+
+/// The outcome of [`Db::migrate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Versions applied by this run, in order.
+    pub applied: Vec<i64>,
+    /// Highest applied version after the run.
+    pub current_version: i64,
+}
+
+impl MigrationReport {
+    fn new(applied: &HashSet<i64>) -> Self {
+        Self {
+            applied: Vec::new(),
+            current_version: applied.iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    fn record(&mut self, version: i64) {
+        self.applied.push(version);
+        self.current_version = self.current_version.max(version);
+    }
+
+    /// Check if the schema was already up to date.
+    pub fn is_up_to_date(&self) -> bool {
+        self.applied.is_empty()
+    }
+}
+
+impl Db {
+    /// Apply pending migrations in version order.
+    ///
+    /// Each migration runs in its own transaction together with its
+    /// `schema_version` row, so a failed migration leaves no partial changes
+    /// and is retried on the next run. The transaction locks out other
+    /// migrators and skips a version they applied in the meantime, so this
+    /// is safe to call on every startup or request.
+    pub fn migrate(&self, migrations: &[Migration]) -> Result<MigrationReport, DbError> {
+        validate(migrations)?;
+        let applied = self.applied_versions()?;
+        let mut report = MigrationReport::new(&applied);
+
+        for migration in pending(migrations, &applied) {
+            let ran = self.transaction(|db| db.apply(migration)).map_err(|e| {
+                DbError::MigrationError(format!(
+                    "{} ({}): {}",
+                    migration.version, migration.name, e
+                ))
+            })?;
+            if ran {
+                report.record(migration.version);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Get the highest applied migration version (0 if none).
+    pub fn schema_version(&self) -> Result<i64, DbError> {
+        Ok(self.applied_versions()?.into_iter().max().unwrap_or(0))
+    }
+
+    fn applied_versions(&self) -> Result<HashSet<i64>, DbError> {
+        self.execute(CREATE_SCHEMA_VERSION, &[])?;
+        let result = self.query("SELECT version FROM schema_version", &[])?;
+        Ok(result
+            .iter()
+            .filter_map(|row| row.get("version").and_then(|v| v.as_integer()))
+            .collect())
+    }
+
+    /// Apply a migration unless another connection already has. Returns
+    /// whether it ran.
+    fn apply(&self, migration: &Migration) -> Result<bool, DbError> {
+        // SQLite transactions already hold the write lock.
+        if self.backend() == Backend::Postgres {
+            self.execute("LOCK TABLE schema_version IN EXCLUSIVE MODE", &[])?;
+        }
+        let existing = self.query(
+            "SELECT version FROM schema_version WHERE version = ?",
+            crate::params![migration.version],
+        )?;
+        if !existing.is_empty() {
+            return Ok(false);
+        }
+        for statement in migration.statements() {
+            self.execute(statement, &[])?;
+        }
+        self.execute(
+            "INSERT INTO schema_version (version, name, applied_at) VALUES (?, ?, ?)",
+            crate::params![migration.version, migration.name, current_timestamp()],
+        )?;
+        Ok(true)
+    }
+}
+
+/// Migrations not yet applied, in order.
+fn pending<'a>(migrations: &'a [Migration], applied: &HashSet<i64>) -> Vec<&'a Migration> {
+    migrations
+        .iter()
+        .filter(|m| !applied.contains(&m.version))
+        .collect()
+}
+
+/// Check that versions are positive and strictly ascending.
+fn validate(migrations: &[Migration]) -> Result<(), DbError> {
+    let mut previous = 0;
+    for migration in migrations {
+        if migration.version <= previous {
+            return Err(DbError::MigrationError(format!(
+                "{} ({}): versions must be positive and strictly ascending",
+                migration.version, migration.name
+            )));
+        }
+        previous = migration.version;
+    }
+    Ok(())
+}
+
+/// Split a SQL script into statements.
+fn split_statements(sql: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum State {
+        Code,
+        Quoted(u8),
+        LineComment,
+        BlockComment,
+    }
+
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut state = State::Code;
+    let mut start = 0;
+    let mut has_code = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match state {
+            State::Code => match bytes[i] {
+                b'\'' | b'"' | b'`' => {
+                    state = State::Quoted(bytes[i]);
+                    has_code = true;
+                }
+                b'-' if next == Some(b'-') => state = State::LineComment,
+                b'/' if next == Some(b'*') => {
+                    state = State::BlockComment;
+                    i += 1;
+                }
+                b';' => {
+                    if has_code {
+                        statements.push(sql[start..i].trim());
+                    }
+                    start = i + 1;
+                    has_code = false;
+                }
+                c if !c.is_ascii_whitespace() => has_code = true,
+                _ => {}
+            },
+            // A doubled quote is an escaped quote: leaving and re-entering
+            // the string gives the same result.
+            State::Quoted(quote) if bytes[i] == quote => state = State::Code,
+            State::LineComment if bytes[i] == b'\n' => state = State::Code,
+            State::BlockComment if bytes[i] == b'*' && next == Some(b'/') => {
+                state = State::Code;
+                i += 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if has_code {
+        statements.push(sql[start..].trim());
+    }
+    statements
+}
+
+/// Get current Unix timestamp.
+fn current_timestamp() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        let sql = "-- products; with a comment\n\
+                   CREATE TABLE t (a TEXT);\n\
+                   /* seed; data */ INSERT INTO t VALUES ('x;y'), ('it''s');\n\
+                   UPDATE t SET a = \"b;c\"";
+        assert_eq!(
+            split_statements(sql),
+            [
+                "-- products; with a comment\nCREATE TABLE t (a TEXT)",
+                "/* seed; data */ INSERT INTO t VALUES ('x;y'), ('it''s')",
+                "UPDATE t SET a = \"b;c\"",
+            ]
+        );
+        assert!(split_statements(" ;\n-- only a comment;\n").is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let ok = [Migration::new(1, "a", ""), Migration::new(3, "b", "")];
+        assert!(validate(&ok).is_ok());
+        let duplicate = [Migration::new(1, "a", ""), Migration::new(1, "b", "")];
+        assert!(matches!(
+            validate(&duplicate),
+            Err(DbError::MigrationError(_))
+        ));
+        assert!(validate(&[Migration::new(0, "zero", "")]).is_err());
+    }
+
+    #[test]
+    fn test_pending_and_report() {
+        let migrations = [
+            Migration::new(1, "init", "CREATE TABLE a (id INTEGER);"),
+            Migration::new(2, "seed", "INSERT INTO a VALUES (1);"),
+            Migration::new(3, "index", "CREATE INDEX a_id ON a (id);"),
+        ];
+        let applied: HashSet<i64> = [1].into_iter().collect();
+        let versions: Vec<i64> = pending(&migrations, &applied)
+            .iter()
+            .map(|m| m.version)
+            .collect();
+        assert_eq!(versions, [2, 3]);
+
+        let mut report = MigrationReport::new(&applied);
+        assert_eq!(report.current_version, 1);
+        assert!(report.is_up_to_date());
+        report.record(3);
+        assert_eq!(report.applied, [3]);
+        assert_eq!(report.current_version, 3);
+        assert!(!report.is_up_to_date());
+
+        let all: HashSet<i64> = [1, 2, 3].into_iter().collect();
+        assert!(pending(&migrations, &all).is_empty());
+        assert_eq!(MigrationReport::new(&HashSet::new()).current_version, 0);
+    }
+}
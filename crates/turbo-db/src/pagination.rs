@@ -0,0 +1,354 @@
+//! Paginated queries.
+//!
+//! [`Db::query_page`] wraps a query with the LIMIT/OFFSET or keyset
+//! clause for a [`PageRequest`] and returns a [`Page`] with the total count
+//! and, for keyset pagination, the cursor of the next page.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use turbo_db::{params, Page, PageRequest};
+//!
+//! // Offset: page 2 of 20, ordered by id.
+//! let page: Page<Product> = db.query_page(
+//!     "SELECT id, name, price FROM products WHERE price < ?",
+//!     params![100.0],
+//!     &PageRequest::offset("id", 2, 20),
+//! )?;
+//!
+//! // Keyset: the 20 products after the last one seen.
+//! let request = PageRequest::keyset("id", 20).with_cursor(cursor);
+//! let page: Page<Product> = db.query_page("SELECT id, name, price FROM products", params![], &request)?;
+//! ```
+
+use crate::write::check_identifier;
+use crate::{Db, DbError, Value};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// How to select a page of results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageRequest {
+    /// A numbered page. Simple, but deep pages are slow and rows shift when
+    /// the data changes between requests.
+    Offset {
+        /// Unique column to order by, so pages don't overlap.
+        column: String,
+        /// Page number (1-based).
+        page: u32,
+        /// Rows per page.
+        per_page: u32,
+        /// Whether to order by the column descending.
+        descending: bool,
+    },
+    /// Rows after a cursor, ordered by a unique column.
+    Keyset {
+        /// Unique, non-null column to order and seek by.
+        column: String,
+        /// Cursor from the previous page (None for the first page).
+        cursor: Option<String>,
+        /// Rows per page.
+        limit: u32,
+        /// Whether to order by the column descending.
+        descending: bool,
+    },
+}
+
+impl PageRequest {
+    /// Request a numbered page (1-based) ordered by a unique column.
+    pub fn offset(column: impl Into<String>, page: u32, per_page: u32) -> Self {
+        PageRequest::Offset {
+            column: column.into(),
+            page: page.max(1),
+            per_page: per_page.max(1),
+            descending: false,
+        }
+    }
+
+    /// Request the first page ordered by a unique column.
+    pub fn keyset(column: impl Into<String>, limit: u32) -> Self {
+        PageRequest::Keyset {
+            column: column.into(),
+            cursor: None,
+            limit: limit.max(1),
+            descending: false,
+        }
+    }
+
+    /// Continue after a cursor returned by a previous page (keyset only).
+    pub fn with_cursor(mut self, next_cursor: impl Into<String>) -> Self {
+        if let PageRequest::Keyset { cursor, .. } = &mut self {
+            *cursor = Some(next_cursor.into());
+        }
+        self
+    }
+
+    /// Order by the column descending.
+    pub fn descending(mut self) -> Self {
+        match &mut self {
+            PageRequest::Offset { descending, .. } | PageRequest::Keyset { descending, .. } => {
+                *descending = true;
+            }
+        }
+        self
+    }
+
+    /// Rows per page.
+    pub fn limit(&self) -> u32 {
+        match self {
+            PageRequest::Offset { per_page, .. } => *per_page,
+            PageRequest::Keyset { limit, .. } => *limit,
+        }
+    }
+
+    /// Wrap a query to select this page, plus one row to detect a next page.
+    fn to_sql(&self, sql: &str, params: &[Value]) -> Result<(String, Vec<Value>), DbError> {
+        let mut params = params.to_vec();
+        let fetch = i64::from(self.limit()) + 1;
+        let sql = match self {
+            PageRequest::Offset {
+                column,
+                page,
+                per_page,
+                descending,
+            } => {
+                check_identifier(column)?;
+                params.push(Value::Integer(fetch));
+                params.push(Value::Integer(
+                    i64::from(page.saturating_sub(1)) * i64::from(*per_page),
+                ));
+                format!(
+                    "SELECT * FROM ({}) AS _page ORDER BY {} {} LIMIT ? OFFSET ?",
+                    sql,
+                    column,
+                    if *descending { "DESC" } else { "ASC" }
+                )
+            }
+            PageRequest::Keyset {
+                column,
+                cursor,
+                descending,
+                ..
+            } => {
+                check_identifier(column)?;
+                let (op, order) = if *descending {
+                    ("<", "DESC")
+                } else {
+                    (">", "ASC")
+                };
+                let filter = match cursor {
+                    Some(cursor) => {
+                        params.push(decode_cursor(cursor)?);
+                        format!(" WHERE {} {} ?", column, op)
+                    }
+                    None => String::new(),
+                };
+                params.push(Value::Integer(fetch));
+                format!(
+                    "SELECT * FROM ({}) AS _page{} ORDER BY {} {} LIMIT ?",
+                    sql, filter, column, order
+                )
+            }
+        };
+        Ok((sql, params))
+    }
+}
+
+/// One page of query results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// The rows on this page.
+    pub items: Vec<T>,
+    /// Rows matching the query across all pages.
+    pub total: u64,
+    /// Whether another page follows.
+    pub has_next: bool,
+    /// Cursor for the next page (keyset pagination only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Create an empty page.
+    pub fn empty() -> Self {
+        Self {
+            items: Vec::new(),
+            total: 0,
+            has_next: false,
+            next_cursor: None,
+        }
+    }
+
+    /// Convert the items, keeping the paging details.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            has_next: self.has_next,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+impl Db {
+    /// Execute a query and return one page of deserialized rows.
+    ///
+    /// The query must not have its own LIMIT or ORDER BY: it is ordered by
+    /// the request's column, which must be selected.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let page: Page<Order> = db.query_page(
+    ///     "SELECT * FROM orders WHERE email = ?",
+    ///     params![email],
+    ///     &PageRequest::keyset("order_number", 10).descending(),
+    /// )?;
+    /// ```
+    pub fn query_page<T: DeserializeOwned>(
+        &self,
+        sql: &str,
+        params: &[Value],
+        request: &PageRequest,
+    ) -> Result<Page<T>, DbError> {
+        let count = self.query(
+            &format!("SELECT COUNT(*) AS total FROM ({}) AS _page", sql),
+            params,
+        )?;
+        let total = count
+            .first()
+            .and_then(|row| row.get("total"))
+            .and_then(|v| v.as_integer())
+            .unwrap_or(0);
+
+        let (page_sql, page_params) = request.to_sql(sql, params)?;
+        let mut result = self.query(&page_sql, &page_params)?;
+        let limit = request.limit() as usize;
+        let has_next = result.rows.len() > limit;
+        result.rows.truncate(limit);
+
+        let next_cursor = match request {
+            PageRequest::Keyset { column, .. } if has_next => result
+                .rows
+                .last()
+                .and_then(|row| row.get(column))
+                .and_then(encode_cursor),
+            _ => None,
+        };
+
+        Ok(Page {
+            items: result.deserialize_all()?,
+            total: total.max(0) as u64,
+            has_next,
+            next_cursor,
+        })
+    }
+}
+
+/// Encode a key value as a cursor (type-tagged so it round-trips).
+fn encode_cursor(value: &Value) -> Option<String> {
+    match value {
+        Value::Integer(i) => Some(format!("i:{}", i)),
+        Value::Real(f) => Some(format!("f:{}", f)),
+        Value::Text(s) => Some(format!("s:{}", s)),
+        Value::Null | Value::Blob(_) => None,
+    }
+}
+
+fn decode_cursor(cursor: &str) -> Result<Value, DbError> {
+    let invalid = || DbError::TypeError(format!("invalid page cursor: {:?}", cursor));
+    let (tag, value) = cursor.split_once(':').ok_or_else(invalid)?;
+    match tag {
+        "i" => value.parse().map(Value::Integer).map_err(|_| invalid()),
+        "f" => value.parse().map(Value::Real).map_err(|_| invalid()),
+        "s" => Ok(Value::Text(value.to_string())),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_sql() {
+        let (sql, params) = PageRequest::offset("id", 3, 20)
+            .to_sql(
+                "SELECT * FROM products WHERE price < ?",
+                crate::params![100],
+            )
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT * FROM products WHERE price < ?) AS _page \
+             ORDER BY id ASC LIMIT ? OFFSET ?"
+        );
+        assert!(matches!(
+            params.as_slice(),
+            [Value::Integer(100), Value::Integer(21), Value::Integer(40)]
+        ));
+
+        let (sql, _) = PageRequest::offset("sku", 1, 20)
+            .descending()
+            .to_sql("SELECT * FROM products", &[])
+            .unwrap();
+        assert!(sql.ends_with("ORDER BY sku DESC LIMIT ? OFFSET ?"));
+        assert!(PageRequest::offset("id desc", 1, 20)
+            .to_sql("SELECT * FROM products", &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_keyset_sql() {
+        let first = PageRequest::keyset("id", 10);
+        let (sql, params) = first.to_sql("SELECT * FROM orders", &[]).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT * FROM orders) AS _page ORDER BY id ASC LIMIT ?"
+        );
+        assert_eq!(params.len(), 1);
+
+        let next = PageRequest::keyset("created_at", 10)
+            .descending()
+            .with_cursor("i:1700000000");
+        let (sql, params) = next.to_sql("SELECT * FROM orders", &[]).unwrap();
+        assert!(sql.ends_with("WHERE created_at < ? ORDER BY created_at DESC LIMIT ?"));
+        assert!(matches!(params[0], Value::Integer(1_700_000_000)));
+
+        assert!(PageRequest::keyset("id; DROP TABLE orders", 10)
+            .to_sql("SELECT * FROM orders", &[])
+            .is_err());
+        assert!(PageRequest::keyset("id", 10)
+            .with_cursor("garbage")
+            .to_sql("SELECT * FROM orders", &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        for value in [Value::Integer(-7), Value::Text("sku:42".to_string())] {
+            let cursor = encode_cursor(&value).unwrap();
+            assert_eq!(
+                format!("{:?}", decode_cursor(&cursor).unwrap()),
+                format!("{:?}", value)
+            );
+        }
+        assert!(encode_cursor(&Value::Null).is_none());
+    }
+
+    #[test]
+    fn test_page_map() {
+        let page = Page {
+            items: vec![1, 2],
+            total: 5,
+            has_next: true,
+            next_cursor: Some("i:2".to_string()),
+        };
+        let page = page.map(|n| n * 10);
+        assert_eq!(page.items, [10, 20]);
+        assert!(page.has_next);
+
+        let json = serde_json::to_string(&Page::<i32>::empty()).unwrap();
+        assert_eq!(json, r#"{"items":[],"total":0,"has_next":false}"#);
+    }
+}
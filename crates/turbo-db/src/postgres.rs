@@ -0,0 +1,146 @@
+//! PostgreSQL support.
+//!
+//! Queries are written with SQLite-style `?` placeholders and rewritten to
+//! Postgres' numbered `$1, $2, ...` before they are sent. Integers are sent
+//! as `int8` and reals as `float8`, so Postgres columns compared with or
+//! assigned parameters should be `BIGINT` and `DOUBLE PRECISION`.
+
+/// Rewrite `?` placeholders outside string literals, quoted identifiers,
+/// and comments to `$1`, `$2`, ...
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))] // Only host tests call it off wasm
+pub(crate) fn placeholders(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len() + 8);
+    let mut chars = sql.chars().peekable();
+    // The character that ends the current literal or comment ('*' for
+    // block comments).
+    let mut quote: Option<char> = None;
+    let mut n = 0;
+    while let Some(c) = chars.next() {
+        match quote {
+            Some('*') => {
+                out.push(c);
+                if c == '*' && chars.peek() == Some(&'/') {
+                    out.extend(chars.next());
+                    quote = None;
+                }
+            }
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+                out.push(c);
+            }
+            None => match c {
+                '?' => {
+                    n += 1;
+                    out.push_str(&format!("${}", n));
+                }
+                '\'' | '"' => {
+                    quote = Some(c);
+                    out.push(c);
+                }
+                '-' if chars.peek() == Some(&'-') => {
+                    // Line comments end at the newline.
+                    quote = Some('\n');
+                    out.push(c);
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    quote = Some('*');
+                    out.push(c);
+                    out.extend(chars.next());
+                }
+                _ => out.push(c),
+            },
+        }
+    }
+    out
+}
+
+/// Convert parameters for Spin Postgres.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn params(params: &[crate::Value]) -> Vec<spin_sdk::pg::ParameterValue> {
+    use crate::Value;
+    use spin_sdk::pg::ParameterValue;
+
+    params
+        .iter()
+        .map(|v| match v {
+            Value::Null => ParameterValue::DbNull,
+            Value::Integer(i) => ParameterValue::Int64(*i),
+            Value::Real(f) => ParameterValue::Floating64(*f),
+            Value::Text(s) => ParameterValue::Str(s.clone()),
+            Value::Blob(b) => ParameterValue::Binary(b.clone()),
+        })
+        .collect()
+}
+
+/// Convert a Spin Postgres row set.
+///
+/// Fails with [`crate::DbError::TypeError`] naming the column if a value has
+/// a type Spin can't represent, rather than reading it as NULL.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn query_result(
+    result: spin_sdk::pg::RowSet,
+) -> Result<crate::QueryResult, crate::DbError> {
+    use crate::{DbError, QueryResult, Row, Value};
+    use spin_sdk::pg::DbValue;
+
+    let columns: Vec<String> = result.columns.iter().map(|c| c.name.clone()).collect();
+    let mut rows = Vec::with_capacity(result.rows.len());
+    for row in result.rows {
+        let values = row
+            .into_iter()
+            .zip(&columns)
+            .map(|(v, column)| {
+                Ok(match v {
+                    DbValue::Boolean(b) => Value::from(b),
+                    DbValue::Int8(i) => Value::Integer(i.into()),
+                    DbValue::Int16(i) => Value::Integer(i.into()),
+                    DbValue::Int32(i) => Value::Integer(i.into()),
+                    DbValue::Int64(i) => Value::Integer(i),
+                    DbValue::Uint8(i) => Value::Integer(i.into()),
+                    DbValue::Uint16(i) => Value::Integer(i.into()),
+                    DbValue::Uint32(i) => Value::Integer(i.into()),
+                    DbValue::Uint64(i) => match i64::try_from(i) {
+                        Ok(i) => Value::Integer(i),
+                        Err(_) => Value::Real(i as f64),
+                    },
+                    DbValue::Floating32(f) => Value::Real(f.into()),
+                    DbValue::Floating64(f) => Value::Real(f),
+                    DbValue::Str(s) => Value::Text(s),
+                    DbValue::Binary(b) => Value::Blob(b),
+                    DbValue::DbNull => Value::Null,
+                    DbValue::Unsupported => {
+                        return Err(DbError::TypeError(format!(
+                            "column {} has a Postgres type that can't be read",
+                            column
+                        )))
+                    }
+                })
+            })
+            .collect::<Result<Vec<Value>, DbError>>()?;
+        rows.push(Row::new(columns.clone(), values));
+    }
+    Ok(QueryResult::new(columns, rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            placeholders("SELECT * FROM products WHERE price < ? AND category = ?"),
+            "SELECT * FROM products WHERE price < $1 AND category = $2"
+        );
+        assert_eq!(
+            placeholders("SELECT '?', \"a?\" FROM t -- why?\nWHERE a = ? /* ? */ AND b = ?"),
+            "SELECT '?', \"a?\" FROM t -- why?\nWHERE a = $1 /* ? */ AND b = $2"
+        );
+        assert_eq!(
+            placeholders("SELECT 'it''s ?' WHERE x = ?"),
+            "SELECT 'it''s ?' WHERE x = $1"
+        );
+    }
+}
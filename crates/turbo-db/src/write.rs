@@ -0,0 +1,281 @@
+//! Insert and upsert helpers.
+//!
+//! These build the INSERT statement from column/value pairs, so callers
+//! don't have to keep placeholder counts and `ON CONFLICT` clauses in sync
+//! by hand.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use turbo_db::Value;
+//!
+//! db.upsert(
+//!     "products",
+//!     &["id"],
+//!     &[
+//!         ("id", Value::from("rust-book")),
+//!         ("name", Value::from("Rust Programming Book")),
+//!         ("price_cents", Value::from(4999)),
+//!     ],
+//! )?;
+//!
+//! let id = db.insert_returning_id(
+//!     "cart_items",
+//!     &[("session_id", Value::from(session)), ("product_id", Value::from("rust-book"))],
+//! )?;
+//!
+//! db.insert_many(
+//!     "products",
+//!     &["id", "name", "price_cents"],
+//!     &[
+//!         vec![Value::from("rust-book"), Value::from("Rust Programming Book"), Value::from(4999)],
+//!         vec![Value::from("wasm-kit"), Value::from("WASM Development Kit"), Value::from(9999)],
+//!     ],
+//! )?;
+//! ```
+
+use crate::{Db, DbError, Value};
+use serde::de::DeserializeOwned;
+
+/// Most parameters bound in one statement (SQLite's historical default
+/// limit; Postgres allows more).
+const MAX_PARAMS: usize = 999;
+
+impl Db {
+    /// Insert a row, or update its non-key columns if a row with the same
+    /// key already exists.
+    ///
+    /// `key_cols` must match a primary key or unique index of the table.
+    pub fn upsert(
+        &self,
+        table: &str,
+        key_cols: &[&str],
+        values: &[(&str, Value)],
+    ) -> Result<(), DbError> {
+        let sql = upsert_sql(table, key_cols, values)?;
+        self.execute(&sql, &params_of(values))
+    }
+
+    /// Upsert a row and return it as stored.
+    ///
+    /// If every column is a key column, an existing row is left untouched
+    /// and not returned ([`DbError::NotFound`]).
+    pub fn upsert_returning<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        key_cols: &[&str],
+        values: &[(&str, Value)],
+    ) -> Result<T, DbError> {
+        let sql = format!("{} RETURNING *", upsert_sql(table, key_cols, values)?);
+        self.query_one(&sql, &params_of(values))
+    }
+
+    /// Insert a row and return its generated integer `id`.
+    pub fn insert_returning_id(
+        &self,
+        table: &str,
+        values: &[(&str, Value)],
+    ) -> Result<i64, DbError> {
+        let sql = format!("{} RETURNING id", insert_sql(table, values)?);
+        let result = self.query(&sql, &params_of(values))?;
+        let id = result
+            .first()
+            .ok_or(DbError::NotFound)?
+            .get("id")
+            .and_then(|v| v.as_integer());
+        id.ok_or_else(|| DbError::TypeError(format!("{}.id is not an integer", table)))
+    }
+
+    /// Insert many rows with multi-row `INSERT` statements, atomically.
+    ///
+    /// Rows are sent in as few statements as the parameter limit allows.
+    /// Inside a [`Db::transaction`] the insert runs in a savepoint.
+    pub fn insert_many(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: &[Vec<Value>],
+    ) -> Result<(), DbError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let chunks = insert_many_sql(table, columns, rows)?;
+        self.transaction(|db| {
+            for (sql, params) in &chunks {
+                db.execute(sql, params)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Check that a table or column name is a plain identifier, since names
+/// cannot be bound as parameters.
+pub(crate) fn check_identifier(name: &str) -> Result<(), DbError> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(DbError::QueryError(format!(
+            "invalid identifier: {:?}",
+            name
+        )))
+    }
+}
+
+fn insert_sql(table: &str, values: &[(&str, Value)]) -> Result<String, DbError> {
+    check_identifier(table)?;
+    if values.is_empty() {
+        return Err(DbError::QueryError(format!(
+            "insert into {} has no values",
+            table
+        )));
+    }
+    for (column, _) in values {
+        check_identifier(column)?;
+    }
+    let columns: Vec<&str> = values.iter().map(|(c, _)| *c).collect();
+    Ok(format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table,
+        columns.join(", "),
+        vec!["?"; columns.len()].join(", ")
+    ))
+}
+
+/// Build multi-row INSERT statements, each within the parameter limit.
+fn insert_many_sql(
+    table: &str,
+    columns: &[&str],
+    rows: &[Vec<Value>],
+) -> Result<Vec<(String, Vec<Value>)>, DbError> {
+    check_identifier(table)?;
+    if columns.is_empty() {
+        return Err(DbError::QueryError(format!(
+            "insert into {} has no columns",
+            table
+        )));
+    }
+    for column in columns {
+        check_identifier(column)?;
+    }
+    if let Some(row) = rows.iter().find(|r| r.len() != columns.len()) {
+        return Err(DbError::QueryError(format!(
+            "insert into {} has {} columns but a row has {} values",
+            table,
+            columns.len(),
+            row.len()
+        )));
+    }
+
+    let placeholders = format!("({})", vec!["?"; columns.len()].join(", "));
+    let rows_per_statement = (MAX_PARAMS / columns.len()).max(1);
+    Ok(rows
+        .chunks(rows_per_statement)
+        .map(|chunk| {
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES {}",
+                table,
+                columns.join(", "),
+                vec![placeholders.as_str(); chunk.len()].join(", ")
+            );
+            (sql, chunk.concat())
+        })
+        .collect())
+}
+
+fn upsert_sql(table: &str, key_cols: &[&str], values: &[(&str, Value)]) -> Result<String, DbError> {
+    let insert = insert_sql(table, values)?;
+    if key_cols.is_empty() {
+        return Err(DbError::QueryError(format!(
+            "upsert into {} has no key columns",
+            table
+        )));
+    }
+    for key in key_cols {
+        if !values.iter().any(|(c, _)| c == key) {
+            return Err(DbError::QueryError(format!(
+                "upsert into {} is missing key column {}",
+                table, key
+            )));
+        }
+    }
+    let updates: Vec<String> = values
+        .iter()
+        .map(|(c, _)| *c)
+        .filter(|c| !key_cols.contains(c))
+        .map(|c| format!("{} = excluded.{}", c, c))
+        .collect();
+    let action = if updates.is_empty() {
+        "DO NOTHING".to_string()
+    } else {
+        format!("DO UPDATE SET {}", updates.join(", "))
+    };
+    Ok(format!(
+        "{} ON CONFLICT ({}) {}",
+        insert,
+        key_cols.join(", "),
+        action
+    ))
+}
+
+fn params_of(values: &[(&str, Value)]) -> Vec<Value> {
+    values.iter().map(|(_, v)| v.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upsert_sql() {
+        let values = [
+            ("product_id", Value::from("rust-book")),
+            ("location_id", Value::from("main")),
+            ("on_hand", Value::from(10)),
+        ];
+        assert_eq!(
+            upsert_sql("inventory_levels", &["product_id", "location_id"], &values).unwrap(),
+            "INSERT INTO inventory_levels (product_id, location_id, on_hand) VALUES (?, ?, ?) \
+             ON CONFLICT (product_id, location_id) DO UPDATE SET on_hand = excluded.on_hand"
+        );
+        assert!(
+            upsert_sql("tags", &["name"], &[("name", Value::from("sale"))])
+                .unwrap()
+                .ends_with("ON CONFLICT (name) DO NOTHING")
+        );
+        assert!(upsert_sql("inventory_levels", &["sku"], &values).is_err());
+    }
+
+    #[test]
+    fn test_insert_many_sql() {
+        let rows: Vec<Vec<Value>> = (0..700)
+            .map(|i| vec![Value::from(i), Value::from("sku")])
+            .collect();
+        let statements = insert_many_sql("variants", &["position", "sku"], &rows).unwrap();
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0]
+            .0
+            .starts_with("INSERT INTO variants (position, sku) VALUES (?, ?), (?, ?),"));
+        assert_eq!(statements[0].1.len(), 998);
+        assert_eq!(statements[1].1.len(), 402);
+        assert!(matches!(statements[1].1[0], Value::Integer(499)));
+
+        let ragged = [vec![Value::from(1)]];
+        assert!(insert_many_sql("variants", &["position", "sku"], &ragged).is_err());
+    }
+
+    #[test]
+    fn test_identifiers() {
+        assert!(check_identifier("cart_items").is_ok());
+        assert!(check_identifier("1st").is_err());
+        assert!(check_identifier("").is_err());
+        assert!(insert_sql("products; DROP TABLE products", &[("id", Value::from(1))]).is_err());
+        assert!(insert_sql("products", &[("name) VALUES ('x')--", Value::from(1))]).is_err());
+        assert!(insert_sql("products", &[]).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 -- Initialize TurboCommerce database schema
--- Run with: spin up --sqlite @migrations/init.sql
+-- Applied automatically by Db::migrate (see MIGRATIONS in src/app.rs)
 
 -- Products table
 CREATE TABLE IF NOT EXISTS products (
@@ -27,7 +27,7 @@ CREATE TABLE IF NOT EXISTS cart_items (
 CREATE INDEX IF NOT EXISTS idx_cart_session ON cart_items(session_id);
 
 -- Seed initial product data
-INSERT OR REPLACE INTO products (id, name, description, price_cents, image_url, category, stock) VALUES
+INSERT OR IGNORE INTO products (id, name, description, price_cents, image_url, category, stock) VALUES
     ('rust-book', 'Rust Programming Book', 'The complete guide to Rust programming language. Learn memory safety, ownership, and zero-cost abstractions.', 4999, '/images/rust_programming_book.png', 'books', 100),
     ('wasm-kit', 'WASM Development Kit', 'Everything you need to build WebAssembly applications. Includes tooling, examples, and best practices.', 9999, '/images/wasm-dev-kit.png', 'tools', 50),
     ('edge-guide', 'Edge Computing Guide', 'Master edge computing patterns. Deploy to Cloudflare Workers, Fermyon Cloud, and more.', 3999, '/images/edge_computing.png', 'books', 75),
@@ -0,0 +1,8 @@
+-- Backfill image URLs for seed products created before images were added
+
+UPDATE products SET image_url = '/images/rust_programming_book.png' WHERE id = 'rust-book' AND (image_url IS NULL OR image_url = '');
+UPDATE products SET image_url = '/images/wasm-dev-kit.png' WHERE id = 'wasm-kit' AND (image_url IS NULL OR image_url = '');
+UPDATE products SET image_url = '/images/edge_computing.png' WHERE id = 'edge-guide' AND (image_url IS NULL OR image_url = '');
+UPDATE products SET image_url = '/images/spin-framework.png' WHERE id = 'perf-pro' AND (image_url IS NULL OR image_url = '');
+UPDATE products SET image_url = '/images/cargo_crate_stickers.png' WHERE id = 'turbo-course' AND (image_url IS NULL OR image_url = '');
+UPDATE products SET image_url = '/images/ferris_plushie.png' WHERE id = 'leptos-book' AND (image_url IS NULL OR image_url = '');
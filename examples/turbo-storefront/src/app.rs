@@ -106,7 +106,9 @@ fn map_row_to_storefront(row: ProductRow) -> StorefrontProduct {
             .unwrap_or_default(),
         tags: Vec::new(),
         default_variant_id: Some(variant_id.clone()),
-        metadata: serde_json::Value::Object(serde_json::Map::new()),
+        options: Vec::new(),
+        variants: Vec::new(),
+        attributes: Default::default(),
         created_at: now,
         updated_at: now,
     };
@@ -180,30 +182,43 @@ fn default_image_for_row(row: &ProductRow) -> Option<String> {
     None
 }
 
+/// Schema migrations, applied before the database is first read.
 #[cfg(feature = "ssr")]
-fn ensure_image_urls(db: &turbo_db::Db) -> Result<(), ServerFnError> {
-    use turbo_db::params;
-
-    let updates = [
-        ("rust-book", "/images/rust_programming_book.png"),
-        ("wasm-kit", "/images/wasm-dev-kit.png"),
-        ("edge-guide", "/images/edge_computing.png"),
-        ("perf-pro", "/images/spin-framework.png"),
-        ("turbo-course", "/images/cargo_crate_stickers.png"),
-        ("leptos-book", "/images/ferris_plushie.png"),
-    ];
-
-    for (id, url) in updates {
-        db.execute(
-            "UPDATE products SET image_url = ? WHERE id = ? AND (image_url IS NULL OR image_url = '')",
-            params![url, id],
-        )
-        .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
-    }
+const MIGRATIONS: &[turbo_db::Migration] = &[
+    turbo_db::Migration::new(1, "init", include_str!("../migrations/0001_init.sql")),
+    turbo_db::Migration::new(
+        2,
+        "product_image_urls",
+        include_str!("../migrations/0002_product_image_urls.sql"),
+    ),
+];
+
+/// Apply [`MIGRATIONS`] once per instance. Concurrent instances are safe:
+/// `Db::migrate` skips versions another one applied first.
+#[cfg(feature = "ssr")]
+fn migrate(db: &turbo_db::Db) -> Result<(), ServerFnError> {
+    use std::sync::atomic::{AtomicBool, Ordering};
 
+    static MIGRATED: AtomicBool = AtomicBool::new(false);
+    if MIGRATED.load(Ordering::Acquire) {
+        return Ok(());
+    }
+    db.migrate(MIGRATIONS)
+        .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    MIGRATED.store(true, Ordering::Release);
     Ok(())
 }
 
+/// Open the default database with [`MIGRATIONS`] applied. Every server
+/// function reads the database through this.
+#[cfg(feature = "ssr")]
+fn open_db() -> Result<turbo_db::Db, ServerFnError> {
+    let db = turbo_db::Db::open_default()
+        .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    migrate(&db)?;
+    Ok(db)
+}
+
 // ============================================================================
 // Shell (SSR entry point)
 // ============================================================================
@@ -786,11 +801,9 @@ fn CartSkeleton() -> impl IntoView {
 pub async fn get_products() -> Result<Vec<StorefrontProduct>, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
-        use turbo_db::{Db, params};
+        use turbo_db::params;
 
-        let db = Db::open_default()
-            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
-        ensure_image_urls(&db)?;
+        let db = open_db()?;
 
         let rows: Vec<ProductRow> = db.query_as(
             "SELECT id, name, description, price_cents, image_url, category, stock FROM products ORDER BY name",
@@ -811,11 +824,9 @@ pub async fn get_products() -> Result<Vec<StorefrontProduct>, ServerFnError> {
 pub async fn get_product(id: String) -> Result<Option<StorefrontProduct>, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
-        use turbo_db::{Db, params};
+        use turbo_db::params;
 
-        let db = Db::open_default()
-            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
-        ensure_image_urls(&db)?;
+        let db = open_db()?;
 
         let product: Option<ProductRow> = db.query_optional(
             "SELECT id, name, description, price_cents, image_url, category, stock FROM products WHERE id = ?",
@@ -862,11 +873,10 @@ pub async fn add_to_cart(product_id: String, variant_id: String, quantity: i64)
     #[cfg(feature = "ssr")]
     {
         use turbo_cache::Cache;
-        use turbo_db::{Db, params};
+        use turbo_db::params;
 
         // Get product info from database
-        let db = Db::open_default()
-            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+        let db = open_db()?;
 
         let product: Option<ProductRow> = db.query_optional(
             "SELECT id, name, description, price_cents, image_url, category, stock FROM products WHERE id = ?",
@@ -930,7 +940,7 @@ pub async fn update_cart_item(variant_id: String, quantity: i64) -> Result<(), S
     #[cfg(feature = "ssr")]
     {
         use turbo_cache::Cache;
-        use turbo_db::{Db, params};
+        use turbo_db::params;
 
         let cache = Cache::open_default()
             .map_err(|e| ServerFnError::new(format!("Cache error: {}", e)))?;
@@ -950,8 +960,7 @@ pub async fn update_cart_item(variant_id: String, quantity: i64) -> Result<(), S
         }
 
         if quantity > 0 {
-            let db = Db::open_default()
-                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            let db = open_db()?;
             let product: Option<ProductRow> = db.query_optional(
                 "SELECT id, name, description, price_cents, image_url, category, stock FROM products WHERE id = ?",
                 params![line_item.product_id.as_str()]